@@ -0,0 +1,57 @@
+use clap::Subcommand;
+use metaverse_core::{
+    element::CreateElementTemplatePayload,
+    repo::{ElementRepo, PgElementRepo, PgUserRepo, UserRepo},
+    user::CreateAvatarPayload,
+};
+use sqlx::PgPool;
+use std::{io::Write, path::PathBuf};
+
+use crate::{AdminError, read_json};
+
+#[derive(Subcommand)]
+pub enum ImportCommand {
+    /// Create one row per entry of a JSON array, in the shape the matching
+    /// admin endpoint accepts.
+    Import { file: PathBuf },
+}
+
+pub async fn avatars(
+    pool: &PgPool,
+    ImportCommand::Import { file }: ImportCommand,
+    out: &mut dyn Write,
+) -> Result<(), AdminError> {
+    // Parsed up front so a bad entry doesn't leave half the file imported.
+    let avatars: Vec<CreateAvatarPayload> = read_json(&file)?;
+    let users = PgUserRepo::new(pool.clone());
+    for avatar in &avatars {
+        let id = users.create_avatar(avatar).await?;
+        writeln!(out, "Created avatar {id} {}", avatar.name)?;
+    }
+    Ok(())
+}
+
+pub async fn templates(
+    pool: &PgPool,
+    ImportCommand::Import { file }: ImportCommand,
+    out: &mut dyn Write,
+) -> Result<(), AdminError> {
+    // Checked up front and created in one transaction, so neither a bad
+    // entry nor a failed insert leaves half the file imported.
+    let templates: Vec<CreateElementTemplatePayload> = read_json(&file)?;
+    for template in &templates {
+        template
+            .validate()
+            .map_err(|source| AdminError::InvalidTemplate {
+                name: template.name.clone(),
+                source,
+            })?;
+    }
+    let ids = PgElementRepo::new(pool.clone())
+        .create_templates(&templates)
+        .await?;
+    for (id, template) in ids.iter().zip(&templates) {
+        writeln!(out, "Created template {id} {}", template.name)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! `metaverse-admin`: operator commands run straight against the database,
+//! through the same repositories the server uses.
+
+use clap::Subcommand;
+use metaverse_core::{bundle::BundleError, element::TemplateError, repo::RepoError};
+use serde::de::DeserializeOwned;
+use sqlx::PgPool;
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+mod imports;
+pub mod seed;
+mod sessions;
+mod tiled;
+mod users;
+mod worlds;
+
+pub use imports::ImportCommand;
+pub use sessions::SessionsCommand;
+pub use tiled::TiledCommand;
+pub use users::UsersCommand;
+pub use worlds::WorldsCommand;
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List users and manage their roles, access and passwords.
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// Create avatars from a JSON file.
+    #[command(subcommand)]
+    Avatars(ImportCommand),
+    /// Create element templates from a JSON file.
+    #[command(subcommand)]
+    Templates(ImportCommand),
+    /// Copy worlds in and out as JSON bundles.
+    #[command(subcommand)]
+    Worlds(WorldsCommand),
+    /// Import maps and spaces drawn in Tiled.
+    #[command(subcommand)]
+    Tiled(TiledCommand),
+    /// Inspect, end and prune realtime sessions.
+    #[command(subcommand)]
+    Sessions(SessionsCommand),
+    /// Create a demo world, avatars and sample users. Running it again only
+    /// adds what is missing.
+    Seed {
+        /// Password of the sample users.
+        #[arg(long, default_value = "metaverse")]
+        password: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdminError {
+    #[error("no user named {0}")]
+    UnknownUser(String),
+    #[error("no world with id {0}")]
+    UnknownWorld(i32),
+    #[error("passwords do not match")]
+    PasswordMismatch,
+    #[error("password must not be empty")]
+    EmptyPassword,
+    #[error("could not read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("{} is not valid: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("not imported because of {0} conflicts; resolve them or pass --force")]
+    Conflicts(usize),
+    #[error("bundle is not valid: {0}")]
+    Bundle(#[from] BundleError),
+    #[error("template {name:?} is not valid: {source}")]
+    InvalidTemplate { name: String, source: TemplateError },
+    #[error(transparent)]
+    Tiled(#[from] tiled::TiledError),
+    #[error("could not hash password: {0}")]
+    Hash(#[from] bcrypt::BcryptError),
+    #[error(transparent)]
+    Repo(#[from] RepoError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Runs `command`, writing its report to `out`. Prompts read from `input`.
+pub async fn run(
+    pool: &PgPool,
+    command: Command,
+    out: &mut dyn Write,
+    input: &mut dyn BufRead,
+) -> Result<(), AdminError> {
+    match command {
+        Command::Users(command) => users::run(pool, command, out, input).await,
+        Command::Avatars(command) => imports::avatars(pool, command, out).await,
+        Command::Templates(command) => imports::templates(pool, command, out).await,
+        Command::Worlds(command) => worlds::run(pool, command, out).await,
+        Command::Tiled(command) => tiled::run(pool, command, out).await,
+        Command::Sessions(command) => sessions::run(pool, command, out).await,
+        Command::Seed { password } => seed::run(pool, &password, out).await,
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, AdminError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| AdminError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_str(&contents).map_err(|source| AdminError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
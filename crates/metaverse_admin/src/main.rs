@@ -0,0 +1,52 @@
+use clap::Parser;
+use dotenv::dotenv;
+use metaverse_admin::Command;
+use metaverse_core::db;
+use metaverse_server::config::{Config, ConfigArgs};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser)]
+#[command(version, about = "Operate a metaverse database")]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let cli = Cli::parse();
+    let database = match Config::load_database(cli.config) {
+        Ok(database) => database,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+    // Logs go to stderr so they can't end up in an exported bundle.
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+    let pool = db::connect(&database).await?;
+
+    let result = metaverse_admin::run(
+        &pool,
+        cli.command,
+        &mut std::io::stdout(),
+        &mut std::io::stdin().lock(),
+    )
+    .await;
+
+    pool.close().await;
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+    Ok(())
+}
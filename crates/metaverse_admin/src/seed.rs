@@ -0,0 +1,306 @@
+//! `seed`: a demo world for local development, identical on every run.
+
+use metaverse_core::{
+    bundle::{
+        BundleMap, BundleMapElement, BundleSpace, BundleSpaceElement, BundleTemplate, BundleWorld,
+        FORMAT, ImportOptions, WorldBundle,
+    },
+    common::Role,
+    element::ElementType,
+    repo::{
+        BundleRepo, NewUser, PgBundleRepo, PgUserRepo, PgWorldRepo, RepoError, UserRepo, WorldRepo,
+    },
+    user::CreateAvatarPayload,
+};
+use metaverse_server::common::hash_password;
+use serde_json::json;
+use sqlx::PgPool;
+use std::io::Write;
+
+use crate::AdminError;
+
+pub const WORLD_NAME: &str = "Demo World";
+
+const AVATARS: [&str; 4] = ["Fox", "Owl", "Otter", "Badger"];
+
+/// Username, role and avatar of each sample user.
+const USERS: [(&str, Role, &str); 4] = [
+    ("demo_admin", Role::Admin, "Owl"),
+    ("alice", Role::User, "Fox"),
+    ("bob", Role::User, "Otter"),
+    ("carol", Role::User, "Badger"),
+];
+
+/// Creates whatever part of the demo data is missing, so running it again
+/// only fills gaps.
+pub async fn run(pool: &PgPool, password: &str, out: &mut dyn Write) -> Result<(), AdminError> {
+    let users = PgUserRepo::new(pool.clone());
+
+    let mut avatars = users.avatars().await?;
+    for name in AVATARS {
+        if avatars.iter().any(|avatar| avatar.name == name) {
+            continue;
+        }
+        let avatar = CreateAvatarPayload {
+            name: name.to_string(),
+            image_url: format!("/assets/demo/avatars/{}.png", name.to_lowercase()),
+            image_asset_id: None,
+        };
+        users.create_avatar(&avatar).await?;
+        writeln!(out, "Created avatar {name}")?;
+        avatars = users.avatars().await?;
+    }
+
+    let password_hash = hash_password(password)?;
+    let mut creator_id = None;
+    for (username, role, avatar) in USERS {
+        let user_id = match users.credentials(username).await {
+            Ok(existing) => existing.id,
+            Err(RepoError::NotFound) => {
+                let avatar_id = avatars.iter().find(|a| a.name == avatar).map(|a| a.id);
+                let id = users
+                    .create(NewUser {
+                        username: username.to_string(),
+                        email: format!("{username}@demo.metaverse.local"),
+                        password_hash: password_hash.clone(),
+                        avatar_id,
+                        role,
+                    })
+                    .await?;
+                writeln!(out, "Created user {username} ({})", role.as_str())?;
+                id
+            }
+            Err(err) => return Err(err.into()),
+        };
+        creator_id.get_or_insert(user_id);
+    }
+
+    let worlds = PgWorldRepo::new(pool.clone()).list().await?;
+    if worlds.iter().any(|world| world.name == WORLD_NAME) {
+        writeln!(out, "{WORLD_NAME} already exists")?;
+        return Ok(());
+    }
+    let bundle = demo_world();
+    bundle.validate()?;
+    let creator_id = creator_id.expect("USERS is not empty");
+    // Differing templates of the same name are expected in a used database.
+    let options = ImportOptions {
+        allow_conflicts: true,
+        ..Default::default()
+    };
+    let report = PgBundleRepo::new(pool.clone())
+        .import(&bundle, creator_id, options)
+        .await?;
+    let world_id = report
+        .world_id
+        .expect("imports with conflicts allowed commit");
+    writeln!(
+        out,
+        "Created world {world_id} {WORLD_NAME} with {} maps and {} spaces",
+        bundle.maps.len(),
+        bundle.spaces.len()
+    )?;
+    Ok(())
+}
+
+/// Three maps of seven spaces between them, joined by portals, furnished
+/// from a template library with at least one template of every type.
+pub fn demo_world() -> WorldBundle {
+    let templates = vec![
+        template(1, "Stone wall", ElementType::Static, (1, 1), true, None),
+        template(2, "Oak tree", ElementType::Static, (2, 2), true, None),
+        template(
+            3,
+            "Bench",
+            ElementType::Interactive,
+            (2, 1),
+            true,
+            Some(json!({ "action": "start_call" })),
+        ),
+        template(
+            4,
+            "Notice board",
+            ElementType::Interactive,
+            (1, 1),
+            true,
+            Some(json!({
+                "action": "show_text",
+                "title": "Notice",
+                "text": "Welcome to the demo world!"
+            })),
+        ),
+        template(
+            5,
+            "Flower bed",
+            ElementType::Decorative,
+            (1, 1),
+            false,
+            None,
+        ),
+        template(
+            6,
+            "Lamp post",
+            ElementType::Interactive,
+            (1, 2),
+            false,
+            Some(json!({ "action": "toggle" })),
+        ),
+        template(7, "Door", ElementType::Portal, (1, 1), false, None),
+        template(8, "Trail marker", ElementType::Portal, (1, 1), false, None),
+    ];
+
+    let maps = vec![
+        map(1, "Town", 128, 96),
+        map(2, "Forest", 96, 96),
+        map(3, "Harbour", 64, 48),
+    ];
+
+    let spaces = vec![
+        space(1, 1, "Town Square", (40, 30), 0, false),
+        space(2, 1, "Cafe", (16, 12), 12, false),
+        space(3, 1, "Library", (24, 18), 20, false),
+        space(4, 2, "Clearing", (32, 32), 0, false),
+        space(5, 2, "Cabin", (10, 8), 4, true),
+        space(6, 3, "Dock", (48, 16), 0, false),
+        space(7, 3, "Lighthouse", (8, 8), 2, true),
+    ];
+
+    // A door into every space from its map, plus trail markers between
+    // maps that lead to each map's main space.
+    let map_elements = vec![
+        portal(1, 7, (20, 20), 1),
+        portal(1, 7, (60, 24), 2),
+        portal(1, 7, (90, 40), 3),
+        portal(1, 8, (126, 48), 4),
+        portal(1, 8, (64, 94), 6),
+        portal(2, 7, (30, 30), 4),
+        portal(2, 7, (70, 60), 5),
+        portal(2, 8, (1, 48), 1),
+        portal(3, 7, (20, 10), 6),
+        portal(3, 7, (56, 40), 7),
+        portal(3, 8, (32, 1), 1),
+    ];
+
+    let space_elements = vec![
+        furniture(1, 4, (20, 2), 0),
+        furniture(1, 3, (10, 15), 0),
+        furniture(1, 3, (30, 15), 180),
+        furniture(1, 6, (5, 5), 0),
+        furniture(1, 6, (35, 5), 0),
+        furniture(1, 5, (20, 25), 0),
+        furniture(2, 3, (4, 6), 90),
+        furniture(2, 5, (14, 1), 0),
+        furniture(3, 4, (12, 1), 0),
+        furniture(3, 1, (0, 9), 0),
+        furniture(4, 2, (8, 8), 0),
+        furniture(4, 2, (24, 8), 0),
+        furniture(4, 2, (16, 24), 0),
+        furniture(4, 5, (16, 16), 0),
+        furniture(5, 3, (5, 4), 0),
+        furniture(6, 6, (10, 2), 0),
+        furniture(6, 6, (38, 2), 0),
+        furniture(6, 3, (24, 12), 0),
+        furniture(7, 1, (0, 0), 0),
+    ];
+
+    WorldBundle {
+        format: FORMAT,
+        world: BundleWorld {
+            name: WORLD_NAME.to_string(),
+            description: Some("A small town, a forest and a harbour to explore.".to_string()),
+            thumbnail_url: Some("/assets/demo/world.png".to_string()),
+            is_public: Some(true),
+        },
+        templates,
+        maps,
+        spaces,
+        map_elements,
+        space_elements,
+    }
+}
+
+fn template(
+    key: i32,
+    name: &str,
+    element_type: ElementType,
+    (width, height): (i32, i32),
+    is_collidable: bool,
+    interaction_data: Option<serde_json::Value>,
+) -> BundleTemplate {
+    BundleTemplate {
+        key,
+        name: name.to_string(),
+        element_type,
+        image_url: format!("/assets/demo/templates/{}.png", slug(name)),
+        model_url: None,
+        width,
+        height,
+        is_collidable: Some(is_collidable),
+        interaction_data,
+        physics_properties: None,
+        animation_data: None,
+        properties_schema: None,
+    }
+}
+
+fn map(key: i32, name: &str, width: i32, height: i32) -> BundleMap {
+    BundleMap {
+        key,
+        name: name.to_string(),
+        width,
+        height,
+        background_url: Some(format!("/assets/demo/maps/{}.png", slug(name))),
+    }
+}
+
+fn space(
+    key: i32,
+    map: i32,
+    name: &str,
+    (width, height): (i32, i32),
+    max_occupancy: i32,
+    is_private: bool,
+) -> BundleSpace {
+    BundleSpace {
+        key,
+        map,
+        name: name.to_string(),
+        description: Some(format!("The {name}.")),
+        width,
+        height,
+        background_url: Some(format!("/assets/demo/spaces/{}.png", slug(name))),
+        thumbnail_url: None,
+        max_occupancy: Some(max_occupancy),
+        is_private: Some(is_private),
+        default_spawn_x: Some(width / 2),
+        default_spawn_y: Some(height - 1),
+    }
+}
+
+fn portal(map: i32, template: i32, (x, y): (i32, i32), target_space: i32) -> BundleMapElement {
+    BundleMapElement {
+        map,
+        template,
+        x,
+        y,
+        z_index: Some(1),
+        target_space: Some(target_space),
+        custom_properties: None,
+    }
+}
+
+fn furniture(space: i32, template: i32, (x, y): (i32, i32), rotation: i32) -> BundleSpaceElement {
+    BundleSpaceElement {
+        space,
+        template,
+        x,
+        y,
+        z_index: Some(0),
+        rotation: Some(rotation),
+        custom_properties: None,
+    }
+}
+
+fn slug(name: &str) -> String {
+    name.to_lowercase().replace(' ', "-")
+}
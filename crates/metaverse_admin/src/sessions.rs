@@ -0,0 +1,71 @@
+use clap::Subcommand;
+use metaverse_core::repo::{AdminRepo, PgAdminRepo, PgSessionRepo, SessionRepo};
+use sqlx::PgPool;
+use std::{io::Write, time::Duration};
+
+use crate::AdminError;
+
+#[derive(Subcommand)]
+pub enum SessionsCommand {
+    /// List active sessions.
+    List {
+        /// Include sessions that have ended.
+        #[arg(long)]
+        all: bool,
+    },
+    /// End sessions. Servers disconnect their clients on the next position
+    /// flush.
+    Kill {
+        #[arg(required = true)]
+        session_ids: Vec<i32>,
+    },
+    /// Delete ended sessions.
+    Prune {
+        /// Only sessions whose last activity is older than this, e.g. `30d`
+        /// or `12h`.
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "30d")]
+        older_than: Duration,
+    },
+}
+
+pub async fn run(
+    pool: &PgPool,
+    command: SessionsCommand,
+    out: &mut dyn Write,
+) -> Result<(), AdminError> {
+    match command {
+        SessionsCommand::List { all } => {
+            let admin = PgAdminRepo::new(pool.clone());
+            writeln!(
+                out,
+                "{:<8}{:<24}{:<8}{:<10}{:<22}LAST ACTIVITY",
+                "ID", "USER", "SPACE", "STATUS", "CONNECTED"
+            )?;
+            for session in admin.sessions(all).await? {
+                writeln!(
+                    out,
+                    "{:<8}{:<24}{:<8}{:<10}{:<22}{}",
+                    session.id,
+                    session.username,
+                    session
+                        .space_id
+                        .map(|id| id.to_string())
+                        .unwrap_or_default(),
+                    session.status,
+                    session.connected_at.unwrap_or_default(),
+                    session.last_activity.unwrap_or_default()
+                )?;
+            }
+        }
+        SessionsCommand::Kill { session_ids } => {
+            PgSessionRepo::new(pool.clone()).close(&session_ids).await?;
+            writeln!(out, "Ended {} sessions", session_ids.len())?;
+        }
+        SessionsCommand::Prune { older_than } => {
+            let admin = PgAdminRepo::new(pool.clone());
+            let deleted = admin.prune_sessions(older_than.as_secs_f64()).await?;
+            writeln!(out, "Deleted {deleted} sessions")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,475 @@
+//! Turns Tiled maps into a bundle of one map and its spaces, following the
+//! conventions in the module docs.
+
+use metaverse_core::{
+    bundle::{
+        BundleMap, BundleMapElement, BundleSpace, BundleSpaceElement, BundleTemplate, BundleWorld,
+        FORMAT, WorldBundle,
+    },
+    element::ElementType,
+    interaction::Interaction,
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{Layer, LayerKind, Object, Properties, TiledError, TiledMap, Tileset};
+
+/// The high bits of a global tile id flip or rotate the tile.
+const GID_MASK: u32 = 0x0FFF_FFFF;
+
+pub struct Conversion {
+    pub bundle: WorldBundle,
+    /// What was left out, and why.
+    pub skipped: Vec<String>,
+}
+
+/// Converts `map` and its `spaces`. Image URLs are `asset_url` joined to their
+/// path from `root`.
+pub fn convert(
+    map: &TiledMap,
+    spaces: &[TiledMap],
+    root: &Path,
+    asset_url: &str,
+) -> Result<Conversion, TiledError> {
+    let mut converter = Converter {
+        root: normalize(root),
+        asset_url: asset_url.trim_end_matches('/'),
+        templates: Vec::new(),
+        by_hash: HashMap::new(),
+        skipped: Vec::new(),
+    };
+
+    let mut space_keys = HashMap::new();
+    for (i, space) in spaces.iter().enumerate() {
+        let name = name(space);
+        if space_keys.insert(name.clone(), i as i32 + 1).is_some() {
+            return Err(TiledError::DuplicateSpace(name));
+        }
+    }
+
+    let mut map_elements = Vec::new();
+    for placed in converter.place(map, true)? {
+        let Placement::Template(template) = placed.template else {
+            converter.skip(map, placed.object, "spawn points belong in spaces");
+            continue;
+        };
+        let mut properties = placed.properties;
+        let target_space = match properties.remove("target_space") {
+            Some(Value::String(name)) => match space_keys.get(&name) {
+                Some(&key) => Some(key),
+                None => {
+                    return Err(TiledError::UnknownSpace {
+                        path: map.path.clone(),
+                        object: placed.object,
+                        name,
+                    });
+                }
+            },
+            Some(other) => {
+                properties.insert("target_space".to_string(), other);
+                None
+            }
+            None => None,
+        };
+        map_elements.push(BundleMapElement {
+            map: 1,
+            template,
+            x: placed.x,
+            y: placed.y,
+            z_index: Some(placed.z_index),
+            target_space,
+            custom_properties: non_empty(properties),
+        });
+    }
+
+    let mut bundle_spaces = Vec::new();
+    let mut space_elements = Vec::new();
+    for (i, space) in spaces.iter().enumerate() {
+        let key = i as i32 + 1;
+        let mut spawn = None;
+        for placed in converter.place(space, false)? {
+            let Placement::Template(template) = placed.template else {
+                spawn = Some((placed.x, placed.y));
+                continue;
+            };
+            space_elements.push(BundleSpaceElement {
+                space: key,
+                template,
+                x: placed.x,
+                y: placed.y,
+                z_index: Some(placed.z_index),
+                rotation: Some(placed.rotation),
+                custom_properties: non_empty(placed.properties),
+            });
+        }
+        bundle_spaces.push(BundleSpace {
+            key,
+            map: 1,
+            name: name(space),
+            description: text(&space.properties, "description").map(str::to_string),
+            width: space.width,
+            height: space.height,
+            background_url: converter.background(space)?,
+            thumbnail_url: text(&space.properties, "thumbnail")
+                .map(|thumbnail| converter.url(space.path.parent(), thumbnail))
+                .transpose()?,
+            max_occupancy: space
+                .properties
+                .get("max_occupancy")
+                .and_then(Value::as_i64)
+                .map(|max| max as i32),
+            is_private: space.properties.get("is_private").and_then(Value::as_bool),
+            default_spawn_x: spawn.map(|(x, _)| x),
+            default_spawn_y: spawn.map(|(_, y)| y),
+        });
+    }
+
+    let bundle_map = BundleMap {
+        key: 1,
+        name: name(map),
+        width: map.width,
+        height: map.height,
+        background_url: converter.background(map)?,
+    };
+    Ok(Conversion {
+        bundle: WorldBundle {
+            format: FORMAT,
+            // Maps are imported into an existing world, which this stands in for.
+            world: BundleWorld {
+                name: bundle_map.name.clone(),
+                description: None,
+                thumbnail_url: None,
+                is_public: None,
+            },
+            templates: converter.templates,
+            maps: vec![bundle_map],
+            spaces: bundle_spaces,
+            map_elements,
+            space_elements,
+        },
+        skipped: converter.skipped,
+    })
+}
+
+struct Converter<'a> {
+    root: PathBuf,
+    asset_url: &'a str,
+    templates: Vec<BundleTemplate>,
+    /// Template keys by content hash, so each distinct tile is one template.
+    by_hash: HashMap<String, i32>,
+    skipped: Vec<String>,
+}
+
+/// A tile, or a spawn point, at a tile position.
+struct Placed {
+    /// The Tiled object id, or 0 for cells of a tile layer.
+    object: u32,
+    x: i32,
+    y: i32,
+    z_index: i32,
+    rotation: i32,
+    properties: Properties,
+    template: Placement,
+}
+
+enum Placement {
+    Template(i32),
+    Spawn,
+}
+
+impl Converter<'_> {
+    /// Everything on the map's layers that becomes an element, plus spawn
+    /// points. Hidden layers are left out, except collision layers. Objects
+    /// are only portals `on_map`.
+    fn place(&mut self, map: &TiledMap, on_map: bool) -> Result<Vec<Placed>, TiledError> {
+        let mut placed = Vec::new();
+        for (z_index, layer) in map.layers.iter().enumerate() {
+            let z_index = z_index as i32;
+            let collision = is_collision(layer);
+            if !layer.visible && !collision {
+                continue;
+            }
+            match &layer.kind {
+                LayerKind::Tiles(gids) if collision => {
+                    for (i, &gid) in gids.iter().enumerate() {
+                        if gid & GID_MASK == 0 {
+                            continue;
+                        }
+                        let key = self.template(map, gid, true, false)?;
+                        placed.push(Placed {
+                            object: 0,
+                            x: i as i32 % map.width,
+                            y: i as i32 / map.width,
+                            z_index,
+                            rotation: 0,
+                            properties: Properties::new(),
+                            template: Placement::Template(key),
+                        });
+                    }
+                }
+                LayerKind::Objects(objects) => {
+                    for object in objects {
+                        if object.class.eq_ignore_ascii_case("spawn") {
+                            placed.push(Placed {
+                                object: object.id,
+                                x: (object.x / f64::from(map.tile_width)).floor() as i32,
+                                y: (object.y / f64::from(map.tile_height)).floor() as i32,
+                                z_index,
+                                rotation: 0,
+                                properties: Properties::new(),
+                                template: Placement::Spawn,
+                            });
+                            continue;
+                        }
+                        let Some(gid) = object.gid else {
+                            self.skip(map, object.id, "it is not a tile");
+                            continue;
+                        };
+                        let portal = on_map && is_portal(object);
+                        let key = self.template(map, gid, collision, portal)?;
+                        // Tile objects are anchored at their bottom-left corner.
+                        let top = object.y - object.height;
+                        placed.push(Placed {
+                            object: object.id,
+                            x: (object.x / f64::from(map.tile_width)).round() as i32,
+                            y: (top / f64::from(map.tile_height)).round() as i32,
+                            z_index,
+                            rotation: object.rotation.round() as i32,
+                            properties: object.properties.clone(),
+                            template: Placement::Template(key),
+                        });
+                    }
+                }
+                LayerKind::Tiles(_) | LayerKind::Image(_) => {}
+            }
+        }
+        Ok(placed)
+    }
+
+    fn skip(&mut self, map: &TiledMap, object: u32, reason: &str) {
+        self.skipped
+            .push(format!("{} object {object}, {reason}", map.path.display()));
+    }
+
+    /// The key of the template for tile `gid` of `map`, adding it if needed.
+    fn template(
+        &mut self,
+        map: &TiledMap,
+        gid: u32,
+        collision: bool,
+        portal: bool,
+    ) -> Result<i32, TiledError> {
+        let gid = gid & GID_MASK;
+        let unknown = || TiledError::UnknownTile {
+            path: map.path.clone(),
+            gid,
+        };
+        let tileset = map
+            .tilesets
+            .iter()
+            .filter(|tileset| tileset.first_gid <= gid)
+            .max_by_key(|tileset| tileset.first_gid)
+            .ok_or_else(unknown)?;
+        let id = gid - tileset.first_gid;
+        let tile = tileset.tiles.get(&id);
+
+        let mut properties = tile.map(|tile| tile.properties.clone()).unwrap_or_default();
+        let named = properties.remove("name");
+        let collidable = properties.remove("collidable").and_then(|c| c.as_bool());
+        let is_collidable =
+            collision || collidable.unwrap_or(false) || tile.is_some_and(|tile| tile.collision);
+
+        let (image_url, pixels) = match tile.and_then(|tile| tile.image.as_deref()) {
+            Some(image) => (
+                self.url(Some(&tileset.dir), image)?,
+                (
+                    tile.and_then(|tile| tile.width)
+                        .unwrap_or(tileset.tile_width),
+                    tile.and_then(|tile| tile.height)
+                        .unwrap_or(tileset.tile_height),
+                ),
+            ),
+            None => (
+                self.atlas_url(tileset, id).ok_or_else(unknown)??,
+                (tileset.tile_width, tileset.tile_height),
+            ),
+        };
+        let name = match named {
+            Some(Value::String(name)) => name,
+            _ => match tile
+                .and_then(|tile| tile.image.as_deref())
+                .and_then(|image| Path::new(image).file_stem())
+            {
+                Some(stem) => stem.to_string_lossy().into_owned(),
+                None => format!("{} {id}", tileset.name),
+            },
+        };
+        // Properties only become interaction_data if they are an interaction,
+        // so imported templates don't fail once someone uses them.
+        let interaction = Interaction::parse(&Value::Object(properties))
+            .ok()
+            .map(|interaction| {
+                serde_json::to_value(interaction).expect("interactions always serialize")
+            });
+        let element_type = tile
+            .and_then(|tile| element_type(&tile.class))
+            .filter(|&element_type| {
+                element_type != ElementType::Interactive || interaction.is_some()
+            })
+            .unwrap_or(if portal {
+                ElementType::Portal
+            } else if is_collidable {
+                ElementType::Static
+            } else {
+                ElementType::Decorative
+            });
+
+        let template = BundleTemplate {
+            // Set by `add`.
+            key: 0,
+            name,
+            element_type,
+            image_url,
+            model_url: None,
+            width: tiles(pixels.0, map.tile_width),
+            height: tiles(pixels.1, map.tile_height),
+            is_collidable: Some(is_collidable),
+            interaction_data: interaction,
+            physics_properties: None,
+            animation_data: None,
+            properties_schema: None,
+        };
+        Ok(self.add(template))
+    }
+
+    fn add(&mut self, mut template: BundleTemplate) -> i32 {
+        let hash = template.content_hash();
+        if let Some(&key) = self.by_hash.get(&hash) {
+            return key;
+        }
+        template.key = self.templates.len() as i32 + 1;
+        self.by_hash.insert(hash, template.key);
+        self.templates.push(template);
+        self.templates.len() as i32
+    }
+
+    /// Tile `id` cut out of the tileset's atlas with a media fragment.
+    fn atlas_url(&self, tileset: &Tileset, id: u32) -> Option<Result<String, TiledError>> {
+        let image = tileset.image.as_deref()?;
+        if tileset.columns == 0 {
+            return None;
+        }
+        let (column, row) = (id % tileset.columns, id / tileset.columns);
+        let (width, height) = (tileset.tile_width as u32, tileset.tile_height as u32);
+        let x = tileset.margin + column * (width + tileset.spacing);
+        let y = tileset.margin + row * (height + tileset.spacing);
+        Some(
+            self.url(Some(&tileset.dir), image)
+                .map(|url| format!("{url}#xywh={x},{y},{width},{height}")),
+        )
+    }
+
+    fn background(&self, map: &TiledMap) -> Result<Option<String>, TiledError> {
+        let image = text(&map.properties, "background").or_else(|| {
+            map.layers.iter().find_map(|layer| match &layer.kind {
+                LayerKind::Image(image) if layer.visible => image.as_deref(),
+                _ => None,
+            })
+        });
+        image
+            .map(|image| self.url(map.path.parent(), image))
+            .transpose()
+    }
+
+    /// The URL of `image`, a path relative to `dir`.
+    fn url(&self, dir: Option<&Path>, image: &str) -> Result<String, TiledError> {
+        let path = normalize(&dir.unwrap_or(Path::new("")).join(image));
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return Err(TiledError::OutsideAssets { path });
+        };
+        let mut url = self.asset_url.to_string();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => {
+                    url.push('/');
+                    url.push_str(&part.to_string_lossy());
+                }
+                _ => return Err(TiledError::OutsideAssets { path }),
+            }
+        }
+        Ok(url)
+    }
+}
+
+/// Resolves `.` and `..` without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn is_portal(object: &Object) -> bool {
+    object
+        .properties
+        .get("target_space")
+        .is_some_and(Value::is_string)
+}
+
+fn is_collision(layer: &Layer) -> bool {
+    layer.name.eq_ignore_ascii_case("collision")
+        || layer.properties.get("collision").and_then(Value::as_bool) == Some(true)
+}
+
+fn name(map: &TiledMap) -> String {
+    text(&map.properties, "name")
+        .map(str::to_string)
+        .or_else(|| {
+            map.path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default()
+}
+
+fn element_type(class: &str) -> Option<ElementType> {
+    [
+        ElementType::Static,
+        ElementType::Interactive,
+        ElementType::Decorative,
+        ElementType::Portal,
+    ]
+    .into_iter()
+    .find(|element_type| format!("{element_type:?}").eq_ignore_ascii_case(class))
+}
+
+fn text<'a>(properties: &'a Properties, name: &str) -> Option<&'a str> {
+    properties.get(name).and_then(Value::as_str)
+}
+
+fn non_empty(properties: Properties) -> Option<Value> {
+    (!properties.is_empty()).then_some(Value::Object(properties))
+}
+
+/// Pixels to whole tiles, rounding up.
+fn tiles(pixels: i32, tile: i32) -> i32 {
+    if tile <= 0 {
+        return 1;
+    }
+    ((pixels + tile - 1) / tile).max(1)
+}
@@ -0,0 +1,251 @@
+//! Tiled's JSON formats, `.tmj` maps and `.tsj` tilesets.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{Layer, LayerKind, Object, Properties, Tile, TiledMap, Tileset, decode_tiles};
+
+#[derive(Deserialize)]
+struct Map {
+    #[serde(default)]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    width: i32,
+    height: i32,
+    tilewidth: i32,
+    tileheight: i32,
+    #[serde(default)]
+    properties: Vec<Property>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+}
+
+#[derive(Deserialize)]
+struct Property {
+    name: String,
+    value: Value,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default = "visible")]
+    visible: bool,
+    #[serde(default)]
+    properties: Vec<Property>,
+    data: Option<Value>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    image: Option<String>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    id: u32,
+    /// Called `type` before Tiled 1.9.
+    #[serde(default, rename = "type")]
+    old_class: String,
+    #[serde(default)]
+    class: String,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    rotation: f64,
+    gid: Option<u32>,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tilewidth: i32,
+    #[serde(default)]
+    tileheight: i32,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+    image: Option<String>,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default, rename = "type")]
+    old_class: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    properties: Vec<Property>,
+    image: Option<String>,
+    imagewidth: Option<i32>,
+    imageheight: Option<i32>,
+    objectgroup: Option<ObjectGroup>,
+}
+
+#[derive(Deserialize)]
+struct ObjectGroup {
+    #[serde(default)]
+    objects: Vec<Value>,
+}
+
+fn visible() -> bool {
+    true
+}
+
+pub fn map(text: &str) -> Result<TiledMap, String> {
+    let map: Map = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    if map.orientation != "orthogonal" {
+        return Err(format!("{:?} maps are not supported", map.orientation));
+    }
+    if map.infinite {
+        return Err("infinite maps are not supported".to_string());
+    }
+    let mut layers = Vec::new();
+    flatten(map.layers, &Properties::new(), true, &mut layers)?;
+    Ok(TiledMap {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        properties: properties(map.properties),
+        layers,
+        tilesets: map.tilesets.into_iter().map(convert_tileset).collect(),
+        ..Default::default()
+    })
+}
+
+pub fn tileset(text: &str) -> Result<Tileset, String> {
+    let tileset: JsonTileset = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    Ok(convert_tileset(tileset))
+}
+
+/// Groups pass their properties and visibility down to their layers.
+fn flatten(
+    json: Vec<JsonLayer>,
+    inherited: &Properties,
+    visible: bool,
+    layers: &mut Vec<Layer>,
+) -> Result<(), String> {
+    for layer in json {
+        let mut merged = inherited.clone();
+        merged.extend(properties(layer.properties));
+        let visible = visible && layer.visible;
+        let kind = match layer.kind.as_str() {
+            "group" => {
+                flatten(layer.layers, &merged, visible, layers)?;
+                continue;
+            }
+            "tilelayer" => LayerKind::Tiles(match layer.data {
+                Some(Value::Array(gids)) => gids
+                    .iter()
+                    .map(|gid| {
+                        gid.as_u64()
+                            .and_then(|gid| u32::try_from(gid).ok())
+                            .ok_or_else(|| format!("bad tile {gid}"))
+                    })
+                    .collect::<Result<_, _>>()?,
+                Some(Value::String(data)) => decode_tiles(
+                    &data,
+                    layer.encoding.as_deref(),
+                    layer.compression.as_deref(),
+                )?,
+                _ => return Err(format!("tile layer {:?} has no data", layer.name)),
+            }),
+            "objectgroup" => LayerKind::Objects(
+                layer
+                    .objects
+                    .into_iter()
+                    .map(|object| Object {
+                        id: object.id,
+                        class: if object.class.is_empty() {
+                            object.old_class
+                        } else {
+                            object.class
+                        },
+                        x: object.x,
+                        y: object.y,
+                        height: object.height,
+                        rotation: object.rotation,
+                        gid: object.gid,
+                        properties: properties(object.properties),
+                    })
+                    .collect(),
+            ),
+            "imagelayer" => LayerKind::Image(layer.image.filter(|image| !image.is_empty())),
+            other => return Err(format!("unknown layer type {other:?}")),
+        };
+        layers.push(Layer {
+            name: layer.name,
+            visible,
+            properties: merged,
+            kind,
+        });
+    }
+    Ok(())
+}
+
+fn convert_tileset(tileset: JsonTileset) -> Tileset {
+    Tileset {
+        first_gid: tileset.firstgid,
+        source: tileset.source,
+        name: tileset.name,
+        tile_width: tileset.tilewidth,
+        tile_height: tileset.tileheight,
+        columns: tileset.columns,
+        margin: tileset.margin,
+        spacing: tileset.spacing,
+        image: tileset.image,
+        tiles: tileset
+            .tiles
+            .into_iter()
+            .map(|tile| {
+                let converted = Tile {
+                    class: if tile.class.is_empty() {
+                        tile.old_class
+                    } else {
+                        tile.class
+                    },
+                    properties: properties(tile.properties),
+                    image: tile.image,
+                    width: tile.imagewidth,
+                    height: tile.imageheight,
+                    collision: tile
+                        .objectgroup
+                        .is_some_and(|group| !group.objects.is_empty()),
+                };
+                (tile.id, converted)
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn properties(properties: Vec<Property>) -> Properties {
+    properties
+        .into_iter()
+        .map(|property| (property.name, property.value))
+        .collect()
+}
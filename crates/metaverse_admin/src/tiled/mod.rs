@@ -0,0 +1,324 @@
+//! `tiled import`: maps and spaces drawn in [Tiled](https://www.mapeditor.org),
+//! from JSON (`.tmj`, `.tsj`) or TMX (`.tmx`, `.tsx`) files.
+//!
+//! One Tiled map becomes the world's new map, and each `--space` file one of
+//! its spaces. Only orthogonal, finite maps are read. Designers mark things up
+//! with these conventions:
+//!
+//! - The map or space is named by a `name` property, or else its file name.
+//!   Spaces also read `description`, `max_occupancy`, `is_private` and
+//!   `thumbnail` properties.
+//! - The background is a `background` file property, or else the first visible
+//!   image layer. Other tile layers are artwork: bake them into the background.
+//! - Every tile object on an object layer becomes an element, on the map or in
+//!   the space, at its top-left tile. Its properties become the element's
+//!   `custom_properties` and later layers draw on top. Hidden layers are left
+//!   out, except collision layers, which are often hidden while drawing.
+//! - Each tile used becomes an element template, named by its `name`
+//!   property. Its other properties become the template's `interaction_data`
+//!   if they are an interaction, like `action` = `show_text` and `text`. Its
+//!   class, if it is an element type, gives the template's type; tiles of
+//!   class `Interactive` without an interaction are imported as if they had
+//!   no class.
+//! - A tile is collidable if it has collision shapes or a `collidable`
+//!   property, or when it is placed on a collision layer: a layer called
+//!   "collision" or with a true `collision` property. On collision tile layers
+//!   every tile becomes an element.
+//! - A tile object on the map with a `target_space` property is a portal into
+//!   the space of that name.
+//! - An object of class `spawn` in a space is where people arrive.
+//!
+//! Images are referred to by URL: `--asset-url` joined to their path from the
+//! map file's directory, which they must be inside of.
+
+use base64::Engine;
+use clap::Subcommand;
+use metaverse_core::{
+    bundle::ImportOptions,
+    repo::{BundleRepo, PgBundleRepo, RepoError},
+};
+use sqlx::PgPool;
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{AdminError, worlds::print_report};
+
+mod convert;
+mod json;
+mod tmx;
+mod xml;
+
+use convert::convert;
+
+#[derive(Subcommand)]
+pub enum TiledCommand {
+    /// Add a map and its spaces, drawn in Tiled, to a world, all or nothing.
+    /// Stops without importing if anything conflicts with what is already
+    /// there.
+    Import {
+        /// The map, as a `.tmj` or `.tmx` file.
+        map: PathBuf,
+        #[arg(long)]
+        world: i32,
+        /// A space on the map, as a `.tmj` or `.tmx` file.
+        #[arg(long = "space")]
+        spaces: Vec<PathBuf>,
+        /// Where the map's directory is served from.
+        #[arg(long, default_value = "/assets")]
+        asset_url: String,
+        /// Report what would be imported and any conflicts, then roll back.
+        #[arg(long)]
+        dry_run: bool,
+        /// Import despite conflicts.
+        #[arg(long, conflicts_with = "dry_run")]
+        force: bool,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TiledError {
+    #[error("could not read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("{} is not a usable Tiled file: {message}", path.display())]
+    Invalid { path: PathBuf, message: String },
+    #[error("{} is outside the map's directory", path.display())]
+    OutsideAssets { path: PathBuf },
+    #[error("{}: tile {gid} is not in any tileset", path.display())]
+    UnknownTile { path: PathBuf, gid: u32 },
+    #[error("{}: object {object} leads to {name:?}, which is not one of the spaces", path.display())]
+    UnknownSpace {
+        path: PathBuf,
+        object: u32,
+        name: String,
+    },
+    #[error("more than one space is named {0:?}")]
+    DuplicateSpace(String),
+}
+
+pub async fn run(
+    pool: &PgPool,
+    command: TiledCommand,
+    out: &mut dyn Write,
+) -> Result<(), AdminError> {
+    match command {
+        TiledCommand::Import {
+            map,
+            world,
+            spaces,
+            asset_url,
+            dry_run,
+            force,
+        } => {
+            let root = map.parent().unwrap_or(Path::new("")).to_path_buf();
+            let map = load(&map)?;
+            let spaces = spaces
+                .iter()
+                .map(|path| load(path))
+                .collect::<Result<Vec<_>, _>>()?;
+            let conversion = convert(&map, &spaces, &root, &asset_url)?;
+            conversion.bundle.validate()?;
+            let options = ImportOptions {
+                dry_run,
+                allow_conflicts: force,
+            };
+            let report = PgBundleRepo::new(pool.clone())
+                .import_maps(world, &conversion.bundle, options)
+                .await
+                .map_err(|err| match err {
+                    RepoError::NotFound => AdminError::UnknownWorld(world),
+                    err => err.into(),
+                })?;
+            print_report(out, &report)?;
+            for skipped in &conversion.skipped {
+                writeln!(out, "Skipped: {skipped}")?;
+            }
+            let name = &conversion.bundle.maps[0].name;
+            match report.world_id {
+                Some(world_id) => writeln!(out, "Imported map {name} into world {world_id}")?,
+                None if dry_run => writeln!(out, "Dry run, nothing was imported")?,
+                None => return Err(AdminError::Conflicts(report.conflicts.len())),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub type Properties = serde_json::Map<String, serde_json::Value>;
+
+/// A Tiled map or space, read from either format.
+#[derive(Debug, Default)]
+pub struct TiledMap {
+    /// The file it was read from.
+    pub path: PathBuf,
+    /// Size in tiles.
+    pub width: i32,
+    pub height: i32,
+    /// Size of a tile in pixels.
+    pub tile_width: i32,
+    pub tile_height: i32,
+    pub properties: Properties,
+    /// In drawing order, with groups flattened.
+    pub layers: Vec<Layer>,
+    pub tilesets: Vec<Tileset>,
+}
+
+#[derive(Debug, Default)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    pub properties: Properties,
+    pub kind: LayerKind,
+}
+
+#[derive(Debug)]
+pub enum LayerKind {
+    /// One global tile id per cell, row by row, 0 for empty.
+    Tiles(Vec<u32>),
+    Objects(Vec<Object>),
+    Image(Option<String>),
+}
+
+impl Default for LayerKind {
+    fn default() -> Self {
+        LayerKind::Objects(Vec::new())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Object {
+    pub id: u32,
+    pub class: String,
+    /// Pixels. Tile objects are anchored at their bottom-left corner.
+    pub x: f64,
+    pub y: f64,
+    pub height: f64,
+    /// Degrees clockwise.
+    pub rotation: f64,
+    pub gid: Option<u32>,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Default)]
+pub struct Tileset {
+    pub first_gid: u32,
+    /// Set until an external tileset has been loaded in its place.
+    pub source: Option<String>,
+    /// The directory its image paths are relative to.
+    pub dir: PathBuf,
+    pub name: String,
+    pub tile_width: i32,
+    pub tile_height: i32,
+    pub columns: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    /// The atlas tiles are cut from, unless every tile has its own image.
+    pub image: Option<String>,
+    /// Tiles that have anything beyond their place in the atlas, by local id.
+    pub tiles: HashMap<u32, Tile>,
+}
+
+#[derive(Debug, Default)]
+pub struct Tile {
+    pub class: String,
+    pub properties: Properties,
+    pub image: Option<String>,
+    /// Pixels, for tiles with their own image.
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Whether it has collision shapes.
+    pub collision: bool,
+}
+
+/// Reads a map or space and the external tilesets it uses.
+pub fn load(path: &Path) -> Result<TiledMap, TiledError> {
+    let invalid = |path: &Path| {
+        let path = path.to_path_buf();
+        move |message| TiledError::Invalid { path, message }
+    };
+    let text = read(path)?;
+    let mut map = match extension(path).as_str() {
+        "tmx" => tmx::map(&text),
+        "tmj" | "json" => json::map(&text),
+        _ => Err("expected a .tmj or .tmx file".to_string()),
+    }
+    .map_err(invalid(path))?;
+    map.path = path.to_path_buf();
+    let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    for tileset in &mut map.tilesets {
+        let Some(source) = tileset.source.take() else {
+            tileset.dir = dir.clone();
+            continue;
+        };
+        let path = dir.join(source);
+        let text = read(&path)?;
+        let mut loaded = match extension(&path).as_str() {
+            "tsx" => tmx::tileset(&text),
+            "tsj" | "json" => json::tileset(&text),
+            _ => Err("expected a .tsj or .tsx tileset".to_string()),
+        }
+        .map_err(invalid(&path))?;
+        loaded.first_gid = tileset.first_gid;
+        loaded.dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        *tileset = loaded;
+    }
+    Ok(map)
+}
+
+fn read(path: &Path) -> Result<String, TiledError> {
+    std::fs::read_to_string(path).map_err(|source| TiledError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Tile layer data in any of the encodings both formats share.
+fn decode_tiles(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, String> {
+    match encoding {
+        Some("csv") => data
+            .split(',')
+            .map(|gid| gid.trim().parse().map_err(|_| format!("bad tile {gid:?}")))
+            .collect(),
+        Some("base64") => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|err| format!("bad base64 tile data: {err}"))?;
+            let bytes = match compression.unwrap_or_default() {
+                "" => bytes,
+                "zlib" => inflate(flate2::read::ZlibDecoder::new(&bytes[..]))?,
+                "gzip" => inflate(flate2::read::GzDecoder::new(&bytes[..]))?,
+                other => return Err(format!("{other} compression is not supported")),
+            };
+            if bytes.len() % 4 != 0 {
+                return Err("tile data is not a whole number of tiles".to_string());
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        other => Err(format!("{other:?} tile encoding is not supported")),
+    }
+}
+
+fn inflate(mut decoder: impl Read) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    decoder
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("bad compressed tile data: {err}"))?;
+    Ok(bytes)
+}
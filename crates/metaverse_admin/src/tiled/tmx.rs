@@ -0,0 +1,218 @@
+//! Tiled's XML formats, `.tmx` maps and `.tsx` tilesets.
+
+use serde_json::Value;
+use std::str::FromStr;
+
+use super::{
+    Layer, LayerKind, Object, Properties, Tile, TiledMap, Tileset, decode_tiles,
+    xml::{self, Element},
+};
+
+pub fn map(text: &str) -> Result<TiledMap, String> {
+    let root = xml::parse(text)?;
+    if root.name != "map" {
+        return Err(format!("expected <map>, found <{}>", root.name));
+    }
+    let orientation = root.attribute("orientation").unwrap_or_default();
+    if orientation != "orthogonal" {
+        return Err(format!("{orientation:?} maps are not supported"));
+    }
+    if root.attribute("infinite") == Some("1") {
+        return Err("infinite maps are not supported".to_string());
+    }
+    let mut layers = Vec::new();
+    flatten(&root, &Properties::new(), true, &mut layers)?;
+    Ok(TiledMap {
+        width: required(&root, "width")?,
+        height: required(&root, "height")?,
+        tile_width: required(&root, "tilewidth")?,
+        tile_height: required(&root, "tileheight")?,
+        properties: properties(&root)?,
+        layers,
+        tilesets: root
+            .children("tileset")
+            .map(convert_tileset)
+            .collect::<Result<_, _>>()?,
+        ..Default::default()
+    })
+}
+
+pub fn tileset(text: &str) -> Result<Tileset, String> {
+    let root = xml::parse(text)?;
+    if root.name != "tileset" {
+        return Err(format!("expected <tileset>, found <{}>", root.name));
+    }
+    convert_tileset(&root)
+}
+
+/// Groups pass their properties and visibility down to their layers.
+fn flatten(
+    parent: &Element,
+    inherited: &Properties,
+    visible: bool,
+    layers: &mut Vec<Layer>,
+) -> Result<(), String> {
+    for element in &parent.children {
+        if !matches!(
+            element.name.as_str(),
+            "layer" | "objectgroup" | "imagelayer" | "group"
+        ) {
+            continue;
+        }
+        let mut merged = inherited.clone();
+        merged.extend(properties(element)?);
+        let visible = visible && element.attribute("visible") != Some("0");
+        let kind = match element.name.as_str() {
+            "group" => {
+                flatten(element, &merged, visible, layers)?;
+                continue;
+            }
+            "layer" => LayerKind::Tiles(tiles(element)?),
+            "objectgroup" => LayerKind::Objects(
+                element
+                    .children("object")
+                    .map(object)
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => LayerKind::Image(
+                element
+                    .child("image")
+                    .and_then(|image| image.attribute("source"))
+                    .filter(|source| !source.is_empty())
+                    .map(str::to_string),
+            ),
+        };
+        layers.push(Layer {
+            name: element.attribute("name").unwrap_or_default().to_string(),
+            visible,
+            properties: merged,
+            kind,
+        });
+    }
+    Ok(())
+}
+
+fn tiles(layer: &Element) -> Result<Vec<u32>, String> {
+    let Some(data) = layer.child("data") else {
+        return Err("tile layer has no data".to_string());
+    };
+    if data.child("chunk").is_some() {
+        return Err("infinite maps are not supported".to_string());
+    }
+    match data.attribute("encoding") {
+        None => data
+            .children("tile")
+            .map(|tile| optional(tile, "gid").map(Option::unwrap_or_default))
+            .collect(),
+        encoding => decode_tiles(&data.text, encoding, data.attribute("compression")),
+    }
+}
+
+fn object(element: &Element) -> Result<Object, String> {
+    Ok(Object {
+        id: required(element, "id")?,
+        // Called `type` before Tiled 1.9.
+        class: element
+            .attribute("class")
+            .or(element.attribute("type"))
+            .unwrap_or_default()
+            .to_string(),
+        x: optional(element, "x")?.unwrap_or_default(),
+        y: optional(element, "y")?.unwrap_or_default(),
+        height: optional(element, "height")?.unwrap_or_default(),
+        rotation: optional(element, "rotation")?.unwrap_or_default(),
+        gid: optional(element, "gid")?,
+        properties: properties(element)?,
+    })
+}
+
+fn convert_tileset(element: &Element) -> Result<Tileset, String> {
+    let mut tiles = std::collections::HashMap::new();
+    for tile in element.children("tile") {
+        let image = tile.child("image");
+        let converted = Tile {
+            class: tile
+                .attribute("class")
+                .or(tile.attribute("type"))
+                .unwrap_or_default()
+                .to_string(),
+            properties: properties(tile)?,
+            image: image
+                .and_then(|image| image.attribute("source"))
+                .map(str::to_string),
+            width: image
+                .map(|image| optional(image, "width"))
+                .transpose()?
+                .flatten(),
+            height: image
+                .map(|image| optional(image, "height"))
+                .transpose()?
+                .flatten(),
+            collision: tile
+                .child("objectgroup")
+                .is_some_and(|group| group.child("object").is_some()),
+        };
+        tiles.insert(required(tile, "id")?, converted);
+    }
+    Ok(Tileset {
+        first_gid: optional(element, "firstgid")?.unwrap_or_default(),
+        source: element.attribute("source").map(str::to_string),
+        name: element.attribute("name").unwrap_or_default().to_string(),
+        tile_width: optional(element, "tilewidth")?.unwrap_or_default(),
+        tile_height: optional(element, "tileheight")?.unwrap_or_default(),
+        columns: optional(element, "columns")?.unwrap_or_default(),
+        margin: optional(element, "margin")?.unwrap_or_default(),
+        spacing: optional(element, "spacing")?.unwrap_or_default(),
+        image: element
+            .child("image")
+            .and_then(|image| image.attribute("source"))
+            .map(str::to_string),
+        tiles,
+        ..Default::default()
+    })
+}
+
+/// The element's `<properties>`, typed as Tiled typed them.
+fn properties(element: &Element) -> Result<Properties, String> {
+    let mut typed = Properties::new();
+    let Some(list) = element.child("properties") else {
+        return Ok(typed);
+    };
+    for property in list.children("property") {
+        let name = property
+            .attribute("name")
+            .ok_or("property without a name")?;
+        // Multi-line strings are written as text instead of an attribute.
+        let raw = property.attribute("value").unwrap_or(&property.text);
+        let value = match property.attribute("type").unwrap_or("string") {
+            "int" | "object" => Value::from(parse::<i64>(name, raw)?),
+            "float" => Value::from(parse::<f64>(name, raw)?),
+            "bool" => Value::from(parse::<bool>(name, raw)?),
+            "class" => Value::Object(properties(property)?),
+            _ => Value::from(raw),
+        };
+        typed.insert(name.to_string(), value);
+    }
+    Ok(typed)
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("property {name} has a bad value {value:?}"))
+}
+
+fn optional<T: FromStr>(element: &Element, name: &str) -> Result<Option<T>, String> {
+    element
+        .attribute(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("<{}> has a bad {name} {value:?}", element.name))
+        })
+        .transpose()
+}
+
+fn required<T: FromStr>(element: &Element, name: &str) -> Result<T, String> {
+    optional(element, name)?.ok_or_else(|| format!("<{}> has no {name}", element.name))
+}
@@ -0,0 +1,208 @@
+//! Just enough XML for TMX and TSX files: elements, attributes, text and the
+//! predefined entities. Comments, processing instructions and doctypes are
+//! skipped.
+
+#[derive(Debug, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// Parses a document, returning its root element.
+pub fn parse(input: &str) -> Result<Element, String> {
+    let mut reader = Reader { input, pos: 0 };
+    reader.skip_misc()?;
+    let root = reader.element()?;
+    reader.skip_misc()?;
+    if reader.pos < input.len() {
+        return Err(reader.error("unexpected content after the root element"));
+    }
+    Ok(root)
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.input[..self.pos].matches('\n').count() + 1;
+        format!("line {line}: {message}")
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {token:?}")))
+        }
+    }
+
+    /// Moves past `end`, returning what came before it.
+    fn until(&mut self, end: &str) -> Result<&str, String> {
+        let Some(len) = self.rest().find(end) else {
+            return Err(self.error(&format!("missing {end:?}")));
+        };
+        let skipped = &self.input[self.pos..self.pos + len];
+        self.pos += len + end.len();
+        Ok(skipped)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    /// Whitespace, comments, processing instructions and doctypes.
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.eat("<!--") {
+                self.until("-->")?;
+            } else if self.eat("<?") {
+                self.until("?>")?;
+            } else if self.eat("<!") {
+                self.until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = self.rest()[..len].to_string();
+        self.pos += len;
+        Ok(name)
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        self.expect("<")?;
+        let mut element = Element {
+            name: self.name()?,
+            ..Default::default()
+        };
+        loop {
+            self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok(element);
+            }
+            if self.eat(">") {
+                break;
+            }
+            let name = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = if self.eat("\"") {
+                "\""
+            } else if self.eat("'") {
+                "'"
+            } else {
+                return Err(self.error("expected a quoted attribute value"));
+            };
+            let value = self.until(quote)?;
+            let value = unescape(value).map_err(|message| self.error(&message))?;
+            element.attributes.push((name, value));
+        }
+
+        loop {
+            if self.eat("</") {
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("</{name}> does not close <{}>", element.name)));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            } else if self.eat("<!--") {
+                self.until("-->")?;
+            } else if self.eat("<![CDATA[") {
+                let text = self.until("]]>")?;
+                element.text.push_str(text);
+            } else if self.eat("<?") {
+                self.until("?>")?;
+            } else if self.rest().starts_with('<') {
+                element.children.push(self.element()?);
+            } else if self.rest().is_empty() {
+                return Err(self.error(&format!("<{}> is never closed", element.name)));
+            } else {
+                let len = self.rest().find('<').unwrap_or(self.rest().len());
+                let text = &self.input[self.pos..self.pos + len];
+                let text = unescape(text).map_err(|message| self.error(&message))?;
+                element.text.push_str(&text);
+                self.pos += len;
+            }
+        }
+    }
+}
+
+fn unescape(text: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find(';') else {
+            return Err("unterminated entity".to_string());
+        };
+        let entity = &rest[start + 1..start + len];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => out.push(c),
+            None => return Err(format!("unknown entity &{entity};")),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
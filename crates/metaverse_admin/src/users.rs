@@ -0,0 +1,115 @@
+use clap::Subcommand;
+use metaverse_core::{
+    common::Role,
+    repo::{AdminRepo, PgAdminRepo, RepoError},
+};
+use metaverse_server::common::hash_password;
+use sqlx::PgPool;
+use std::io::{BufRead, Write};
+
+use crate::AdminError;
+
+#[derive(Subcommand)]
+pub enum UsersCommand {
+    /// List every user.
+    List,
+    /// Make a user an admin.
+    Promote { username: String },
+    /// Make an admin a regular user.
+    Demote { username: String },
+    /// Stop a user from signing in, refuse the tokens they hold and end
+    /// their realtime sessions.
+    Disable { username: String },
+    /// Let a disabled user sign in again.
+    Enable { username: String },
+    /// Set a new password, prompting for it twice.
+    ResetPassword {
+        username: String,
+        /// Read the password from the first line of stdin instead.
+        #[arg(long)]
+        password_stdin: bool,
+    },
+}
+
+pub async fn run(
+    pool: &PgPool,
+    command: UsersCommand,
+    out: &mut dyn Write,
+    input: &mut dyn BufRead,
+) -> Result<(), AdminError> {
+    let admin = PgAdminRepo::new(pool.clone());
+    match command {
+        UsersCommand::List => {
+            writeln!(
+                out,
+                "{:<8}{:<24}{:<32}{:<8}{:<8}DISABLED",
+                "ID", "USERNAME", "EMAIL", "ROLE", "ONLINE"
+            )?;
+            for user in admin.users().await? {
+                writeln!(
+                    out,
+                    "{:<8}{:<24}{:<32}{:<8}{:<8}{}",
+                    user.id,
+                    user.username,
+                    user.email,
+                    user.role.as_str(),
+                    yes_no(user.is_online),
+                    yes_no(user.disabled)
+                )?;
+            }
+        }
+        UsersCommand::Promote { username } => {
+            known(&username, admin.set_role(&username, Role::Admin).await)?;
+            writeln!(out, "{username} is now an admin")?;
+        }
+        UsersCommand::Demote { username } => {
+            known(&username, admin.set_role(&username, Role::User).await)?;
+            writeln!(out, "{username} is now a regular user")?;
+        }
+        UsersCommand::Disable { username } => {
+            known(&username, admin.set_disabled(&username, true).await)?;
+            writeln!(out, "Disabled {username}")?;
+        }
+        UsersCommand::Enable { username } => {
+            known(&username, admin.set_disabled(&username, false).await)?;
+            writeln!(out, "Enabled {username}")?;
+        }
+        UsersCommand::ResetPassword {
+            username,
+            password_stdin,
+        } => {
+            let password = if password_stdin {
+                let mut line = String::new();
+                input.read_line(&mut line)?;
+                line.trim_end_matches(['\r', '\n']).to_string()
+            } else {
+                let password = rpassword::prompt_password("New password: ")?;
+                if rpassword::prompt_password("Repeat it: ")? != password {
+                    return Err(AdminError::PasswordMismatch);
+                }
+                password
+            };
+            if password.is_empty() {
+                return Err(AdminError::EmptyPassword);
+            }
+            let password_hash = hash_password(&password)?;
+            known(
+                &username,
+                admin.set_password_hash(&username, &password_hash).await,
+            )?;
+            writeln!(out, "Reset the password of {username}")?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn known<T>(username: &str, result: Result<T, RepoError>) -> Result<T, AdminError> {
+    result.map_err(|err| match err {
+        RepoError::NotFound => AdminError::UnknownUser(username.to_string()),
+        err => err.into(),
+    })
+}
+
+pub(crate) fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
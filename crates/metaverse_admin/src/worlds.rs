@@ -0,0 +1,111 @@
+use clap::Subcommand;
+use metaverse_core::{
+    bundle::{ImportOptions, ImportReport, WorldBundle},
+    repo::{BundleRepo, PgBundleRepo, PgUserRepo, RepoError, UserRepo},
+};
+use sqlx::PgPool;
+use std::{io::Write, path::PathBuf};
+
+use crate::{AdminError, read_json, users::known};
+
+#[derive(Subcommand)]
+pub enum WorldsCommand {
+    /// Write a world, its maps, spaces, elements and their templates as a
+    /// JSON bundle.
+    Export {
+        world_id: i32,
+        /// Where to write the bundle instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Create a new world from a bundle, all or nothing. Templates that
+    /// already exist are reused. Stops without importing if anything
+    /// conflicts with what is already there.
+    Import {
+        file: PathBuf,
+        /// User recorded as the world's creator.
+        #[arg(long)]
+        creator: String,
+        /// Name the world this instead of the name in the bundle.
+        #[arg(long)]
+        name: Option<String>,
+        /// Report what would be imported and any conflicts, then roll back.
+        #[arg(long)]
+        dry_run: bool,
+        /// Import despite conflicts.
+        #[arg(long, conflicts_with = "dry_run")]
+        force: bool,
+    },
+}
+
+pub async fn run(
+    pool: &PgPool,
+    command: WorldsCommand,
+    out: &mut dyn Write,
+) -> Result<(), AdminError> {
+    let bundles = PgBundleRepo::new(pool.clone());
+    match command {
+        WorldsCommand::Export { world_id, output } => {
+            let bundle = bundles.export(world_id).await.map_err(|err| match err {
+                RepoError::NotFound => AdminError::UnknownWorld(world_id),
+                err => err.into(),
+            })?;
+            let json = serde_json::to_string_pretty(&bundle).expect("bundles always serialize");
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json + "\n")?;
+                    writeln!(out, "Exported world {world_id} to {}", path.display())?;
+                }
+                None => writeln!(out, "{json}")?,
+            }
+        }
+        WorldsCommand::Import {
+            file,
+            creator,
+            name,
+            dry_run,
+            force,
+        } => {
+            let mut bundle: WorldBundle = read_json(&file)?;
+            bundle.validate()?;
+            if let Some(name) = name {
+                bundle.world.name = name;
+            }
+            let users = PgUserRepo::new(pool.clone());
+            let creator_id = known(&creator, users.credentials(&creator).await)?.id;
+            let options = ImportOptions {
+                dry_run,
+                allow_conflicts: force,
+            };
+            let report = bundles.import(&bundle, creator_id, options).await?;
+            print_report(out, &report)?;
+            match report.world_id {
+                Some(world_id) => writeln!(out, "Imported world {world_id} {}", bundle.world.name)?,
+                None if dry_run => writeln!(out, "Dry run, nothing was imported")?,
+                None => return Err(AdminError::Conflicts(report.conflicts.len())),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn print_report(out: &mut dyn Write, report: &ImportReport) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "Templates: {} new, {} reused",
+        report.templates_created,
+        report.templates_reused.len()
+    )?;
+    for (key, template_id) in &report.templates_reused {
+        writeln!(out, "  template {key} is template {template_id}")?;
+    }
+    writeln!(
+        out,
+        "Maps: {}, spaces: {}, map elements: {}, space elements: {}",
+        report.maps, report.spaces, report.map_elements, report.space_elements
+    )?;
+    for conflict in &report.conflicts {
+        writeln!(out, "Conflict: {conflict}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,870 @@
+use clap::Parser;
+use metaverse_admin::{AdminError, Command, seed};
+use metaverse_core::{
+    bundle::{BundleError, BundleTemplate, WorldBundle},
+    common::Role,
+    element::{
+        CreateElementTemplatePayload, CreateMapElementsPayload, CreateSpaceElementsPayload,
+        ElementType, TemplateError,
+    },
+    maps::CreateMapPayload,
+    repo::{
+        BundleRepo, ElementRepo, MapRepo, NewUser, PgBundleRepo, PgElementRepo, PgMapRepo,
+        PgSessionRepo, PgSpaceRepo, PgUserRepo, PgWorldRepo, SessionRepo, SpaceRepo, UserRepo,
+        WorldRepo,
+    },
+    space::CreateSpacePayload,
+    worlds::CreateWorldPayload,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::{io::Cursor, path::PathBuf};
+
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Runs `metaverse-admin` with `args`, returning what it printed.
+async fn admin(pool: &PgPool, args: &[&str]) -> Result<String, AdminError> {
+    admin_with_input(pool, args, "").await
+}
+
+async fn admin_with_input(pool: &PgPool, args: &[&str], input: &str) -> Result<String, AdminError> {
+    let cli = Cli::try_parse_from(std::iter::once("metaverse-admin").chain(args.iter().copied()))
+        .unwrap();
+    let mut out = Vec::new();
+    metaverse_admin::run(pool, cli.command, &mut out, &mut Cursor::new(input)).await?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+async fn create_user(pool: &PgPool, username: &str) -> i32 {
+    PgUserRepo::new(pool.clone())
+        .create(NewUser {
+            username: username.to_string(),
+            email: format!("{username}@example.com"),
+            password_hash: "unused".to_string(),
+            avatar_id: None,
+            role: Role::User,
+        })
+        .await
+        .unwrap()
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("metaverse-admin-{}-{name}", std::process::id()))
+}
+
+fn template(name: &str, element_type: ElementType) -> CreateElementTemplatePayload {
+    CreateElementTemplatePayload {
+        name: name.to_string(),
+        element_type,
+        image_url: format!("https://cdn.example.com/{name}.png"),
+        model_url: String::new(),
+        image_asset_id: None,
+        model_asset_id: None,
+        width: 1,
+        height: 1,
+        is_collidable: true,
+        interaction_data: json!({}),
+        physics_properties: json!({ "mass": 5 }),
+        animation_data: None,
+        properties_schema: None,
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn manages_users(pool: PgPool) {
+    create_user(&pool, "alice").await;
+    let users = PgUserRepo::new(pool.clone());
+
+    admin(&pool, &["users", "promote", "alice"]).await.unwrap();
+    admin(&pool, &["users", "disable", "alice"]).await.unwrap();
+    let listed = admin(&pool, &["users", "list"]).await.unwrap();
+
+    let credentials = users.credentials("alice").await.unwrap();
+    assert_eq!(credentials.role, Role::Admin);
+    assert!(credentials.disabled);
+    assert!(listed.contains("alice@example.com"), "{listed}");
+
+    admin(&pool, &["users", "enable", "alice"]).await.unwrap();
+    admin_with_input(
+        &pool,
+        &["users", "reset-password", "alice", "--password-stdin"],
+        "hunter2\n",
+    )
+    .await
+    .unwrap();
+
+    let credentials = users.credentials("alice").await.unwrap();
+    assert!(!credentials.disabled);
+    assert!(bcrypt::verify("hunter2", &credentials.password_hash).unwrap());
+    assert!(matches!(
+        admin(&pool, &["users", "promote", "mallory"]).await,
+        Err(AdminError::UnknownUser(name)) if name == "mallory"
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn disabling_a_user_ends_their_sessions(pool: PgPool) {
+    let user_id = create_user(&pool, "alice").await;
+    let creator = create_user(&pool, "root").await;
+    let world_id = create_world(&pool, creator).await;
+    let map_id = create_map(&pool, world_id).await;
+    let space_id = create_space(&pool, map_id, "Kitchen").await;
+    let sessions = PgSessionRepo::new(pool.clone());
+    let session_id = sessions.open(user_id, space_id, 0, 0).await.unwrap();
+
+    admin(&pool, &["users", "disable", "alice"]).await.unwrap();
+
+    assert_eq!(sessions.closed(&[session_id]).await.unwrap(), [session_id]);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn lists_kills_and_prunes_sessions(pool: PgPool) {
+    let user_id = create_user(&pool, "alice").await;
+    let world_id = create_world(&pool, user_id).await;
+    let map_id = create_map(&pool, world_id).await;
+    let space_id = create_space(&pool, map_id, "Kitchen").await;
+    let sessions = PgSessionRepo::new(pool.clone());
+    let stale = sessions.open(user_id, space_id, 0, 0).await.unwrap();
+    let live = sessions.open(user_id, space_id, 0, 0).await.unwrap();
+    sessions.close(&[stale]).await.unwrap();
+    sqlx::query(
+        "UPDATE user_sessions SET last_activity = now() - interval '40 days' WHERE id = $1",
+    )
+    .bind(stale)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let active = admin(&pool, &["sessions", "list"]).await.unwrap();
+    let all = admin(&pool, &["sessions", "list", "--all"]).await.unwrap();
+
+    assert_eq!(active.lines().count(), 2, "{active}");
+    assert!(active.contains("Active"), "{active}");
+    assert_eq!(all.lines().count(), 3, "{all}");
+
+    let pruned = admin(&pool, &["sessions", "prune"]).await.unwrap();
+    assert_eq!(pruned.trim(), "Deleted 1 sessions");
+
+    admin(&pool, &["sessions", "kill", &live.to_string()])
+        .await
+        .unwrap();
+    assert_eq!(sessions.closed(&[live]).await.unwrap(), [live]);
+    // Recently ended sessions are kept.
+    let pruned = admin(&pool, &["sessions", "prune", "--older-than", "1h"])
+        .await
+        .unwrap();
+    assert_eq!(pruned.trim(), "Deleted 0 sessions");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn imports_avatars_and_templates(pool: PgPool) {
+    let avatars = temp_file("avatars.json");
+    let templates = temp_file("templates.json");
+    std::fs::write(
+        &avatars,
+        json!([
+            { "name": "Fox", "image_url": "https://cdn.example.com/fox.png" },
+            { "name": "Owl", "image_url": "https://cdn.example.com/owl.png" },
+        ])
+        .to_string(),
+    )
+    .unwrap();
+    std::fs::write(
+        &templates,
+        serde_json::to_string(&[template("tree", ElementType::Static)]).unwrap(),
+    )
+    .unwrap();
+
+    admin(&pool, &["avatars", "import", avatars.to_str().unwrap()])
+        .await
+        .unwrap();
+    let output = admin(&pool, &["templates", "import", templates.to_str().unwrap()])
+        .await
+        .unwrap();
+    std::fs::remove_file(&avatars).unwrap();
+    std::fs::remove_file(&templates).unwrap();
+
+    let names: Vec<String> = PgUserRepo::new(pool.clone())
+        .avatars()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|avatar| avatar.name)
+        .collect();
+    assert_eq!(names, ["Fox", "Owl"]);
+    assert!(output.starts_with("Created template"), "{output}");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn template_imports_are_all_or_nothing(pool: PgPool) {
+    let file = temp_file("bad-templates.json");
+    let mut bouncy = template("ball", ElementType::Static);
+    bouncy.physics_properties = json!({ "restitution": 2 });
+    let mut unknown_asset = template("rock", ElementType::Static);
+    unknown_asset.image_asset_id = Some(999);
+
+    std::fs::write(
+        &file,
+        serde_json::to_string(&[template("tree", ElementType::Static), bouncy]).unwrap(),
+    )
+    .unwrap();
+    let invalid = admin(&pool, &["templates", "import", file.to_str().unwrap()]).await;
+    std::fs::write(
+        &file,
+        serde_json::to_string(&[template("tree", ElementType::Static), unknown_asset]).unwrap(),
+    )
+    .unwrap();
+    let failed = admin(&pool, &["templates", "import", file.to_str().unwrap()]).await;
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(
+        matches!(&invalid, Err(AdminError::InvalidTemplate { name, .. }) if name == "ball"),
+        "{invalid:?}"
+    );
+    assert!(matches!(failed, Err(AdminError::Repo(_))), "{failed:?}");
+    let templates: i64 = sqlx::query_scalar("SELECT count(*) FROM element_templates")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(templates, 0);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn round_trips_worlds_through_bundles(pool: PgPool) {
+    let creator = create_user(&pool, "root").await;
+    let world_id = create_world(&pool, creator).await;
+    let map_id = create_map(&pool, world_id).await;
+    let kitchen = create_space(&pool, map_id, "Kitchen").await;
+    let garden = create_space(&pool, map_id, "Garden").await;
+    let elements = PgElementRepo::new(pool.clone());
+    let tree = elements
+        .create_template(&template("tree", ElementType::Static))
+        .await
+        .unwrap();
+    let door = elements
+        .create_template(&template("door", ElementType::Portal))
+        .await
+        .unwrap();
+    // Not used by the world, so not exported.
+    elements
+        .create_template(&template("rock", ElementType::Decorative))
+        .await
+        .unwrap();
+    elements
+        .create_space_element(&CreateSpaceElementsPayload {
+            space_id: garden,
+            template_id: tree,
+            x: 3,
+            y: 4,
+            z_index: 1,
+            rotation: 90,
+            custom_properties: json!({ "fruit": "apple" }),
+        })
+        .await
+        .unwrap();
+    elements
+        .create_map_element(&CreateMapElementsPayload {
+            map_id,
+            template_id: door,
+            x: 7,
+            y: 8,
+            z_index: 0,
+            target_space_id: kitchen,
+            custom_properties: json!({}),
+        })
+        .await
+        .unwrap();
+    let file = temp_file("world.json");
+
+    admin(
+        &pool,
+        &[
+            "worlds",
+            "export",
+            &world_id.to_string(),
+            "-o",
+            file.to_str().unwrap(),
+        ],
+    )
+    .await
+    .unwrap();
+    let output = admin(
+        &pool,
+        &[
+            "worlds",
+            "import",
+            file.to_str().unwrap(),
+            "--creator",
+            "root",
+            "--name",
+            "Lobby copy",
+        ],
+    )
+    .await
+    .unwrap();
+    let mut exported: WorldBundle =
+        serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(output.contains("Templates: 0 new, 2 reused"), "{output}");
+    let imported_id = world_id_in(&output);
+    assert_ne!(imported_id, world_id);
+    let reexported: WorldBundle = serde_json::from_str(
+        &admin(&pool, &["worlds", "export", &imported_id.to_string()])
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(exported.templates.len(), 2);
+    assert_eq!(exported.map_elements[0].target_space, Some(kitchen));
+    exported.world.name = "Lobby copy".to_string();
+    assert_eq!(without_keys(exported), without_keys(reexported));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn reports_conflicts_and_only_imports_when_forced(pool: PgPool) {
+    let creator = create_user(&pool, "root").await;
+    create_world(&pool, creator).await;
+    PgElementRepo::new(pool.clone())
+        .create_template(&template("tree", ElementType::Static))
+        .await
+        .unwrap();
+    let mut bundle = seed::demo_world();
+    bundle.world.name = "Lobby".to_string();
+    bundle.templates[1].name = "tree".to_string();
+    let file = temp_file("conflicts.json");
+    std::fs::write(&file, serde_json::to_string(&bundle).unwrap()).unwrap();
+    let import = |extra: &'static [&'static str]| {
+        let pool = pool.clone();
+        let file = file.clone();
+        async move {
+            let mut args = vec![
+                "worlds",
+                "import",
+                file.to_str().unwrap(),
+                "--creator",
+                "root",
+            ];
+            args.extend_from_slice(extra);
+            admin(&pool, &args).await
+        }
+    };
+    let worlds = || async {
+        sqlx::query_scalar::<_, i64>("SELECT count(*) FROM worlds")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    let dry_run = import(&["--dry-run"]).await.unwrap();
+    let refused = import(&[]).await;
+    assert_eq!(worlds().await, 1);
+    let forced = import(&["--force"]).await.unwrap();
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(
+        dry_run.contains(r#"Conflict: a world named "Lobby" already exists"#),
+        "{dry_run}"
+    );
+    assert!(
+        dry_run.contains(r#"Conflict: template 2 "tree" differs from existing template"#),
+        "{dry_run}"
+    );
+    assert!(dry_run.contains("Maps: 3, spaces: 7"), "{dry_run}");
+    assert!(
+        dry_run.ends_with("Dry run, nothing was imported\n"),
+        "{dry_run}"
+    );
+    assert!(matches!(refused, Err(AdminError::Conflicts(2))));
+    world_id_in(&forced);
+    assert_eq!(worlds().await, 2);
+}
+
+#[test]
+fn template_hashes_ignore_keys_and_field_order() {
+    let template = &seed::demo_world().templates[2];
+    let renumbered = BundleTemplate {
+        key: 99,
+        ..template.clone()
+    };
+    let reordered: BundleTemplate = serde_json::from_str(
+        r#"{
+            "interaction_data": { "action": "start_call" },
+            "key": 3, "name": "Bench", "element_type": "Interactive",
+            "image_url": "/assets/demo/templates/bench.png", "model_url": null,
+            "width": 2, "height": 1, "is_collidable": true,
+            "physics_properties": null, "animation_data": null
+        }"#,
+    )
+    .unwrap();
+    let moved = BundleTemplate {
+        width: 3,
+        ..template.clone()
+    };
+
+    assert_eq!(renumbered.content_hash(), template.content_hash());
+    assert_eq!(reordered.content_hash(), template.content_hash());
+    assert_ne!(moved.content_hash(), template.content_hash());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn rejects_bundles_with_dangling_references(pool: PgPool) {
+    create_user(&pool, "root").await;
+    let file = temp_file("dangling.json");
+    std::fs::write(
+        &file,
+        json!({
+            "format": 1,
+            "world": { "name": "Broken", "description": null, "thumbnail_url": null, "is_public": true },
+            "templates": [],
+            "maps": [],
+            "spaces": [{
+                "key": 1, "map": 9, "name": "Nowhere", "description": null, "width": 5, "height": 5,
+                "background_url": null, "thumbnail_url": null, "max_occupancy": 0, "is_private": false,
+                "default_spawn_x": null, "default_spawn_y": null
+            }],
+            "map_elements": [],
+            "space_elements": []
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let result = admin(
+        &pool,
+        &[
+            "worlds",
+            "import",
+            file.to_str().unwrap(),
+            "--creator",
+            "root",
+        ],
+    )
+    .await;
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(matches!(result, Err(AdminError::Bundle(_))));
+    let worlds: i64 = sqlx::query_scalar("SELECT count(*) FROM worlds")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(worlds, 0);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn rejects_bundles_with_properties_against_their_schema(pool: PgPool) {
+    create_user(&pool, "root").await;
+    let file = temp_file("schema.json");
+    std::fs::write(
+        &file,
+        json!({
+            "format": 1,
+            "world": { "name": "Broken", "description": null, "thumbnail_url": null, "is_public": true },
+            "templates": [{
+                "key": 1, "name": "Door", "element_type": "Static", "image_url": "/door.png",
+                "model_url": null, "width": 1, "height": 1, "is_collidable": true,
+                "interaction_data": null, "physics_properties": null, "animation_data": null,
+                "properties_schema": {
+                    "type": "object",
+                    "properties": { "locked": { "type": "boolean" } },
+                    "required": ["locked"]
+                }
+            }],
+            "maps": [{ "key": 1, "name": "Town", "width": 10, "height": 10, "background_url": null }],
+            "spaces": [],
+            "map_elements": [{
+                "map": 1, "template": 1, "x": 1, "y": 1, "z_index": 0, "target_space": null,
+                "custom_properties": { "locked": "yes" }
+            }],
+            "space_elements": []
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let result = admin(
+        &pool,
+        &[
+            "worlds",
+            "import",
+            file.to_str().unwrap(),
+            "--creator",
+            "root",
+        ],
+    )
+    .await;
+    std::fs::remove_file(&file).unwrap();
+
+    let Err(AdminError::Bundle(BundleError::InvalidData { from, source })) = result else {
+        panic!("expected invalid data, got {result:?}");
+    };
+    assert_eq!(from, "map element");
+    assert_eq!(source.field, "custom_properties");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn rejects_bundles_with_interactive_templates_that_cannot_work(pool: PgPool) {
+    create_user(&pool, "root").await;
+    let file = temp_file("interaction.json");
+    let mut results = Vec::new();
+    for interaction_data in [
+        json!({ "action": "drink" }),
+        json!({ "action": "set_state" }),
+    ] {
+        std::fs::write(
+            &file,
+            json!({
+                "format": 1,
+                "world": { "name": "Broken", "description": null, "thumbnail_url": null, "is_public": true },
+                "templates": [{
+                    "key": 1, "name": "Well", "element_type": "Interactive", "image_url": "/well.png",
+                    "model_url": null, "width": 1, "height": 1, "is_collidable": true,
+                    "interaction_data": interaction_data, "physics_properties": null,
+                    "animation_data": null
+                }],
+                "maps": [],
+                "spaces": [],
+                "map_elements": [],
+                "space_elements": []
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let result = admin(
+            &pool,
+            &[
+                "worlds",
+                "import",
+                file.to_str().unwrap(),
+                "--creator",
+                "root",
+            ],
+        )
+        .await;
+        match result {
+            Err(AdminError::Bundle(BundleError::InvalidTemplate { key: 1, source })) => {
+                results.push(source)
+            }
+            other => panic!("expected an invalid template, got {other:?}"),
+        }
+    }
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(matches!(results[0], TemplateError::InvalidInteraction(_)));
+    assert_eq!(results[1], TemplateError::UncheckedState);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn imports_maps_and_spaces_from_tiled(pool: PgPool) {
+    let creator = create_user(&pool, "root").await;
+    let world_id = create_world(&pool, creator).await;
+    let town = tiled_fixture("town.tmx");
+    let cafe = tiled_fixture("cafe.tmj");
+    let world = world_id.to_string();
+
+    let output = admin(
+        &pool,
+        &[
+            "tiled", "import", &town, "--world", &world, "--space", &cafe,
+        ],
+    )
+    .await
+    .unwrap();
+    let bundle = PgBundleRepo::new(pool.clone())
+        .export(world_id)
+        .await
+        .unwrap();
+
+    assert!(output.contains("Templates: 4 new, 0 reused"), "{output}");
+    assert!(output.contains("Maps: 1, spaces: 1, map elements: 6, space elements: 17"));
+    assert!(
+        output.contains("town.tmx object 3, it is not a tile"),
+        "{output}"
+    );
+    assert!(output.contains("town.tmx object 4, spawn points belong in spaces"));
+    assert!(output.ends_with(&format!(
+        "Imported map Town & Harbour into world {world_id}\n"
+    )));
+
+    let template = |key: i32| bundle.templates.iter().find(|t| t.key == key).unwrap();
+    let named = |name: &str| bundle.templates.iter().find(|t| t.name == name).unwrap();
+    let map = &bundle.maps[0];
+    assert_eq!(
+        (map.name.as_str(), map.width, map.height),
+        ("Town & Harbour", 20, 15)
+    );
+    assert_eq!(
+        map.background_url.as_deref(),
+        Some("/assets/backgrounds/town.png")
+    );
+
+    let wall = named("Stone wall");
+    assert_eq!(wall.element_type, ElementType::Static);
+    assert_eq!(wall.is_collidable, Some(true));
+    assert_eq!(wall.image_url, "/assets/tiles/town.png#xywh=1,1,32,32");
+    let walls: Vec<_> = bundle
+        .map_elements
+        .iter()
+        .filter(|e| e.template == wall.key)
+        .map(|e| (e.x, e.y))
+        .collect();
+    assert_eq!(walls, [(0, 0), (1, 0), (2, 0), (7, 5)]);
+
+    let fountain = bundle
+        .map_elements
+        .iter()
+        .find(|e| template(e.template).name == "fountain")
+        .unwrap();
+    assert_eq!((fountain.x, fountain.y, fountain.z_index), (2, 2, Some(3)));
+    assert_eq!(
+        fountain.custom_properties,
+        Some(json!({ "label": "Town fountain" }))
+    );
+    let fountain = template(fountain.template);
+    assert_eq!(fountain.element_type, ElementType::Interactive);
+    assert_eq!(fountain.image_url, "/assets/props/fountain.png");
+    assert_eq!((fountain.width, fountain.height), (2, 2));
+    assert_eq!(fountain.is_collidable, Some(true));
+    // Only what makes up the interaction is kept.
+    assert_eq!(
+        fountain.interaction_data,
+        Some(json!({ "action": "show_text", "text": "Fresh water" }))
+    );
+
+    let space = &bundle.spaces[0];
+    let door = bundle
+        .map_elements
+        .iter()
+        .find(|e| e.target_space.is_some())
+        .unwrap();
+    assert_eq!(door.target_space, Some(space.key));
+    assert_eq!((door.x, door.y), (10, 2));
+    assert_eq!(door.custom_properties, Some(json!({ "locked": false })));
+    let door = template(door.template);
+    assert_eq!(door.name, "Door");
+    // Its class says Interactive, but a sound is not an interaction.
+    assert_eq!(door.element_type, ElementType::Portal);
+    assert_eq!(door.image_url, "/assets/tiles/town.png#xywh=35,35,32,32");
+    assert_eq!(door.interaction_data, None);
+
+    assert_eq!(space.name, "Cafe");
+    assert_eq!(space.description.as_deref(), Some("Coffee by the square."));
+    assert_eq!((space.width, space.height), (16, 12));
+    assert_eq!(
+        space.background_url.as_deref(),
+        Some("/assets/backgrounds/cafe.png")
+    );
+    assert_eq!(
+        (space.max_occupancy, space.is_private),
+        (Some(12), Some(false))
+    );
+    assert_eq!(
+        (space.default_spawn_x, space.default_spawn_y),
+        (Some(8), Some(11))
+    );
+    // The cafe's JSON tileset is the town's TSX one, so its walls are the same.
+    let cafe_walls = bundle
+        .space_elements
+        .iter()
+        .filter(|e| e.template == wall.key)
+        .count();
+    assert_eq!(cafe_walls, 16);
+    let table = bundle
+        .space_elements
+        .iter()
+        .find(|e| e.template != wall.key)
+        .unwrap();
+    assert_eq!((table.x, table.y, table.rotation), (4, 5, Some(90)));
+    assert_eq!(table.custom_properties, Some(json!({ "seats": 4 })));
+    let table = template(table.template);
+    assert_eq!(table.name, "town 3");
+    assert_eq!(table.element_type, ElementType::Decorative);
+    assert_eq!(table.image_url, "/assets/tiles/town.png#xywh=103,1,32,32");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn tiled_imports_check_portals_and_existing_maps(pool: PgPool) {
+    let creator = create_user(&pool, "root").await;
+    let world_id = create_world(&pool, creator).await;
+    let town = tiled_fixture("town.tmx");
+    let cafe = tiled_fixture("cafe.tmj");
+    let world = world_id.to_string();
+
+    let without_cafe = admin(&pool, &["tiled", "import", &town, "--world", &world]).await;
+    let unknown_world = admin(
+        &pool,
+        &["tiled", "import", &town, "--world", "999", "--space", &cafe],
+    )
+    .await;
+    let args = [
+        "tiled", "import", &town, "--world", &world, "--space", &cafe,
+    ];
+    admin(&pool, &args).await.unwrap();
+    let again = admin(&pool, &[&args[..], &["--dry-run"]].concat())
+        .await
+        .unwrap();
+
+    let err = without_cafe.unwrap_err();
+    assert!(matches!(err, AdminError::Tiled(_)));
+    assert!(
+        err.to_string()
+            .ends_with(r#"object 2 leads to "Cafe", which is not one of the spaces"#),
+        "{err}"
+    );
+    assert!(matches!(unknown_world, Err(AdminError::UnknownWorld(999))));
+    assert!(again.contains("Templates: 0 new, 4 reused"), "{again}");
+    assert!(
+        again.contains(r#"Conflict: the world already has a map named "Town & Harbour""#),
+        "{again}"
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn seeds_the_same_demo_world_once(pool: PgPool) {
+    let first = admin(&pool, &["seed", "--password", "demo"]).await.unwrap();
+    let second = admin(&pool, &["seed"]).await.unwrap();
+
+    assert_eq!(second.trim(), "Demo World already exists");
+    let world_id: i32 = first
+        .lines()
+        .last()
+        .unwrap()
+        .split_whitespace()
+        .nth(2)
+        .unwrap()
+        .parse()
+        .unwrap();
+    let seeded: WorldBundle = serde_json::from_str(
+        &admin(&pool, &["worlds", "export", &world_id.to_string()])
+            .await
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(without_keys(seeded), without_keys(seed::demo_world()));
+
+    let demo = seed::demo_world();
+    for element_type in [
+        ElementType::Static,
+        ElementType::Interactive,
+        ElementType::Decorative,
+        ElementType::Portal,
+    ] {
+        assert!(
+            demo.templates
+                .iter()
+                .any(|t| t.element_type == element_type),
+            "no {element_type:?} template"
+        );
+    }
+    assert!(
+        demo.spaces.iter().all(|s| demo
+            .map_elements
+            .iter()
+            .any(|e| e.target_space == Some(s.key))),
+        "every space should be reachable through a portal"
+    );
+
+    let users = PgUserRepo::new(pool.clone());
+    let admin_user = users.credentials("demo_admin").await.unwrap();
+    assert_eq!(admin_user.role, Role::Admin);
+    assert!(bcrypt::verify("demo", &admin_user.password_hash).unwrap());
+    assert_eq!(users.avatars().await.unwrap().len(), 4);
+}
+
+fn tiled_fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/tiled/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// The id of the world an import reported creating.
+fn world_id_in(output: &str) -> i32 {
+    let last = output.lines().last().unwrap();
+    assert!(last.starts_with("Imported world"), "{output}");
+    last.split_whitespace().nth(2).unwrap().parse().unwrap()
+}
+
+/// Keys are database ids, so they differ between the original and the copy;
+/// keys are renumbered in order of appearance so bundles can be compared.
+fn without_keys(mut bundle: WorldBundle) -> WorldBundle {
+    let renumber = |keys: Vec<i32>| -> std::collections::HashMap<i32, i32> {
+        keys.into_iter().zip(1..).collect()
+    };
+    let templates = renumber(bundle.templates.iter().map(|t| t.key).collect());
+    let maps = renumber(bundle.maps.iter().map(|m| m.key).collect());
+    let spaces = renumber(bundle.spaces.iter().map(|s| s.key).collect());
+    for template in &mut bundle.templates {
+        template.key = templates[&template.key];
+    }
+    for map in &mut bundle.maps {
+        map.key = maps[&map.key];
+    }
+    for space in &mut bundle.spaces {
+        space.key = spaces[&space.key];
+        space.map = maps[&space.map];
+    }
+    for element in &mut bundle.map_elements {
+        element.map = maps[&element.map];
+        element.template = templates[&element.template];
+        element.target_space = element.target_space.map(|key| spaces[&key]);
+    }
+    for element in &mut bundle.space_elements {
+        element.space = spaces[&element.space];
+        element.template = templates[&element.template];
+    }
+    bundle
+}
+
+async fn create_world(pool: &PgPool, creator_id: i32) -> i32 {
+    PgWorldRepo::new(pool.clone())
+        .create(
+            creator_id,
+            &CreateWorldPayload {
+                name: "Lobby".to_string(),
+                description: "Where everyone starts".to_string(),
+                thumbnail_url: "https://cdn.example.com/lobby.png".to_string(),
+                thumbnail_asset_id: None,
+                is_public: true,
+            },
+        )
+        .await
+        .unwrap()
+}
+
+async fn create_map(pool: &PgPool, world_id: i32) -> i32 {
+    PgMapRepo::new(pool.clone())
+        .create(&CreateMapPayload {
+            world_id,
+            name: "Ground floor".to_string(),
+            width: 100,
+            height: 100,
+            background_url: "https://cdn.example.com/ground.png".to_string(),
+            background_asset_id: None,
+        })
+        .await
+        .unwrap()
+}
+
+async fn create_space(pool: &PgPool, map_id: i32, name: &str) -> i32 {
+    PgSpaceRepo::new(pool.clone())
+        .create(&CreateSpacePayload {
+            map_id,
+            name: name.to_string(),
+            description: String::new(),
+            width: 20,
+            height: 10,
+            background_url: String::new(),
+            thumbnail_url: String::new(),
+            background_asset_id: None,
+            thumbnail_asset_id: None,
+            max_occupancy: 0,
+            is_private: false,
+            default_spawn_x: 2,
+            default_spawn_y: 3,
+        })
+        .await
+        .unwrap()
+}
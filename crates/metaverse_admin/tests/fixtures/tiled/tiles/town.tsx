@@ -0,0 +1,15 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="town" tilewidth="32" tileheight="32" spacing="2" margin="1" tilecount="8" columns="4">
+ <image source="town.png" width="135" height="69"/>
+ <tile id="0">
+  <properties>
+   <property name="name" value="Stone wall"/>
+  </properties>
+ </tile>
+ <tile id="5" class="Interactive">
+  <properties>
+   <property name="name" value="Door"/>
+   <property name="sound" type="file" value="door.ogg"/>
+  </properties>
+ </tile>
+</tileset>
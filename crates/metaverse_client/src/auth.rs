@@ -0,0 +1,53 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use metaverse_core::common::SignInPayload;
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+
+/// Tokens this close to expiring are replaced before they are sent.
+const REFRESH_MARGIN_SECS: u64 = 60;
+
+pub(crate) struct Session {
+    pub(crate) credentials: SignInPayload,
+    pub(crate) token: String,
+    expires_at: u64,
+}
+
+#[derive(Deserialize)]
+struct ExpiryClaim {
+    exp: u64,
+}
+
+impl Session {
+    pub(crate) fn new(credentials: SignInPayload, token: String) -> Result<Self> {
+        let expires_at = token_expiry(&token)?;
+        Ok(Self {
+            credentials,
+            token,
+            expires_at,
+        })
+    }
+
+    pub(crate) fn needs_refresh(&self) -> bool {
+        now() + REFRESH_MARGIN_SECS >= self.expires_at
+    }
+}
+
+// The server has no refresh endpoint, so the client only needs `exp` to know
+// when to sign in again. The signature is the server's business.
+fn token_expiry(token: &str) -> Result<u64> {
+    let payload = token.split('.').nth(1).ok_or(Error::MalformedToken)?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| Error::MalformedToken)?;
+    let claim: ExpiryClaim = serde_json::from_slice(&payload).map_err(|_| Error::MalformedToken)?;
+    Ok(claim.exp)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,25 @@
+use reqwest::StatusCode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("server responded with {0}")]
+    Status(StatusCode),
+    #[error("not signed in")]
+    NotSignedIn,
+    #[error("server issued a token that could not be decoded")]
+    MalformedToken,
+    #[error("realtime connection failed: {0}")]
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("server sent a message that could not be decoded: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        Error::WebSocket(Box::new(err))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
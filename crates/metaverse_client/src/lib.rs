@@ -0,0 +1,428 @@
+//! Typed client for the metaverse HTTP API and its realtime socket.
+//!
+//! Requests and responses are the same `metaverse_core` types the server
+//! handlers use, so a payload change on either side fails to compile here.
+//!
+//! ```no_run
+//! # async fn run() -> metaverse_client::Result<()> {
+//! let client = metaverse_client::Client::new("http://localhost:3000/api/v1");
+//! client.signin("alice", "hunter2").await?;
+//! for world in client.worlds().await? {
+//!     println!("{}: {}", world.id, world.name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod auth;
+mod error;
+mod realtime;
+
+pub use error::{Error, Result};
+pub use metaverse_core as types;
+pub use realtime::Realtime;
+
+use auth::Session;
+use metaverse_core::{
+    asset::{Asset, AssetReference, CreatorStorage, DeleteAssetPayload, GetAssetPayload},
+    avatar::{
+        AvatarComposition, AvatarPartsResponse, CreateAvatarPartPayload, GetAvatarPayload,
+        SetAvatarPayload, WornPart,
+    },
+    common::{SignInPayload, SignInResponse, SignUpPayload},
+    element::{
+        CreateElementTemplatePayload, CreateMapElementsPayload, CreateSpaceElementsPayload,
+        PinRevisionPayload, RevisionPreview, TemplateRevision, UpdateElementTemplatePayload,
+    },
+    maps::{CreateMapPayload, CreateMapResponse, GetMapPayload, GetMapResponse},
+    realtime::ClientMessage,
+    space::{CreateSpacePayload, DeleteSpacePayload, GetSpacePayload, GetSpaceResponse},
+    user::{
+        AvatarResponseBody, CreateAvatarPayload, GetUserMetadataRequestPayload,
+        GetUserMetadataResponse, UpdateAvatarPayload,
+    },
+    worlds::{CreateWorldPayload, World},
+};
+use reqwest::{
+    Method, Response, StatusCode,
+    header::AUTHORIZATION,
+    multipart::{Form, Part},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::{net::TcpStream, sync::Mutex};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{Error as WsError, client::IntoClientRequest},
+};
+
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    session: Mutex<Option<Session>>,
+}
+
+impl Client {
+    /// `base_url` is the API root, e.g. `http://localhost:3000/api/v1`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            session: Mutex::new(None),
+        }
+    }
+
+    pub async fn signup(&self, payload: &SignUpPayload) -> Result<()> {
+        let response = self
+            .http
+            .post(self.url("/common/signup"))
+            .json(payload)
+            .send()
+            .await?;
+        // signup reports conflicts with a non-error status, so check it exactly.
+        match response.status() {
+            StatusCode::CREATED => Ok(()),
+            status => Err(Error::Status(status)),
+        }
+    }
+
+    /// Signs in and keeps the credentials so expired tokens can be replaced
+    /// without the caller noticing.
+    pub async fn signin(&self, username: &str, password: &str) -> Result<()> {
+        let credentials = SignInPayload {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        let session = self.fetch_session(credentials).await?;
+        *self.session.lock().await = Some(session);
+        Ok(())
+    }
+
+    pub async fn signout(&self) {
+        *self.session.lock().await = None;
+    }
+
+    /// The current bearer token, signing in again first if it is about to expire.
+    pub async fn token(&self) -> Result<String> {
+        let mut session = self.session.lock().await;
+        let current = session.as_mut().ok_or(Error::NotSignedIn)?;
+        if current.needs_refresh() {
+            *current = self.fetch_session(current.credentials.clone()).await?;
+        }
+        Ok(current.token.clone())
+    }
+
+    pub async fn create_avatar(&self, payload: &CreateAvatarPayload) -> Result<()> {
+        self.send(Method::POST, "/common/create_avatar", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn update_avatar(&self, payload: &UpdateAvatarPayload) -> Result<()> {
+        self.send(Method::POST, "/user/metadata", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn avatars(&self) -> Result<AvatarResponseBody> {
+        self.fetch(Method::GET, "/user/avatars", None::<&()>).await
+    }
+
+    pub async fn metadata_bulk(&self, ids: Vec<i32>) -> Result<GetUserMetadataResponse> {
+        let payload = GetUserMetadataRequestPayload { ids };
+        self.fetch(Method::POST, "/user/metadata/bulk", Some(&payload))
+            .await
+    }
+
+    pub async fn create_avatar_part(&self, payload: &CreateAvatarPartPayload) -> Result<()> {
+        self.send(Method::POST, "/common/create_avatar_part", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn avatar_parts(&self) -> Result<AvatarPartsResponse> {
+        self.fetch(Method::GET, "/user/avatar_parts", None::<&()>)
+            .await
+    }
+
+    /// Replaces the signed-in user's avatar with `parts`.
+    pub async fn set_avatar(&self, parts: Vec<WornPart>) -> Result<()> {
+        let payload = SetAvatarPayload { parts };
+        self.send(Method::POST, "/user/set_avatar", Some(&payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn avatar(&self, user_id: i32) -> Result<AvatarComposition> {
+        let payload = GetAvatarPayload { user_id };
+        self.fetch(Method::POST, "/user/get_avatar", Some(&payload))
+            .await
+    }
+
+    pub async fn create_world(&self, payload: &CreateWorldPayload) -> Result<()> {
+        self.send(Method::POST, "/worlds/create", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn worlds(&self) -> Result<Vec<World>> {
+        self.fetch(Method::GET, "/worlds/get_worlds", None::<&()>)
+            .await
+    }
+
+    pub async fn create_map(&self, payload: &CreateMapPayload) -> Result<CreateMapResponse> {
+        self.fetch(Method::POST, "/map/create", Some(payload)).await
+    }
+
+    pub async fn map(&self, map_id: i32) -> Result<GetMapResponse> {
+        let payload = GetMapPayload { map_id };
+        self.fetch(Method::POST, "/map/get_map", Some(&payload))
+            .await
+    }
+
+    pub async fn create_space(&self, payload: &CreateSpacePayload) -> Result<()> {
+        self.send(Method::POST, "/space/create", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn space(&self, space_id: i32) -> Result<GetSpaceResponse> {
+        let payload = GetSpacePayload { space_id };
+        self.fetch(Method::POST, "/space/get_space", Some(&payload))
+            .await
+    }
+
+    pub async fn delete_space(&self, space_id: i32) -> Result<()> {
+        let payload = DeleteSpacePayload { space_id };
+        self.send(Method::POST, "/space/delete_space", Some(&payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn create_element_template(
+        &self,
+        payload: &CreateElementTemplatePayload,
+    ) -> Result<()> {
+        self.send(Method::POST, "/element/create_new_element", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    /// Saves a new revision of a template. Placed elements keep theirs until
+    /// pinned to it.
+    pub async fn update_element_template(
+        &self,
+        payload: &UpdateElementTemplatePayload,
+    ) -> Result<TemplateRevision> {
+        self.fetch(Method::POST, "/element/update_element", Some(payload))
+            .await
+    }
+
+    pub async fn preview_template_revision(
+        &self,
+        payload: &PinRevisionPayload,
+    ) -> Result<RevisionPreview> {
+        self.fetch(
+            Method::POST,
+            "/element/preview_template_revision",
+            Some(payload),
+        )
+        .await
+    }
+
+    pub async fn pin_template_revision(
+        &self,
+        payload: &PinRevisionPayload,
+    ) -> Result<RevisionPreview> {
+        self.fetch(
+            Method::POST,
+            "/element/pin_template_revision",
+            Some(payload),
+        )
+        .await
+    }
+
+    pub async fn create_space_element(&self, payload: &CreateSpaceElementsPayload) -> Result<()> {
+        self.send(Method::POST, "/element/create_space_element", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn create_map_element(&self, payload: &CreateMapElementsPayload) -> Result<()> {
+        self.send(Method::POST, "/element/create_map_element", Some(payload))
+            .await
+            .map(drop)
+    }
+
+    /// Uploads a file, returning the asset it became. Uploading the same file
+    /// twice returns the first asset. `name` defaults to `file_name`.
+    pub async fn upload_asset(
+        &self,
+        file_name: &str,
+        content: Vec<u8>,
+        name: Option<&str>,
+    ) -> Result<Asset> {
+        let form = || {
+            let file = Part::bytes(content.clone()).file_name(file_name.to_string());
+            let form = Form::new().part("file", file);
+            match name {
+                Some(name) => form.text("name", name.to_string()),
+                None => form,
+            }
+        };
+        let upload = |token: String| {
+            self.http
+                .post(self.url("/assets/upload"))
+                .bearer_auth(token)
+                .multipart(form())
+                .send()
+        };
+        let mut response = upload(self.token().await?).await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            response = upload(self.refresh().await?).await?;
+        }
+        Ok(checked(response)?.json().await?)
+    }
+
+    pub async fn asset(&self, asset_id: i32) -> Result<Asset> {
+        let payload = GetAssetPayload { asset_id };
+        self.fetch(Method::POST, "/assets/get_asset", Some(&payload))
+            .await
+    }
+
+    /// Everything that uses the asset, and so stops it being deleted.
+    pub async fn asset_references(&self, asset_id: i32) -> Result<Vec<AssetReference>> {
+        let payload = GetAssetPayload { asset_id };
+        self.fetch(Method::POST, "/assets/get_asset_references", Some(&payload))
+            .await
+    }
+
+    pub async fn delete_asset(&self, asset_id: i32) -> Result<()> {
+        let payload = DeleteAssetPayload { asset_id };
+        self.send(Method::POST, "/assets/delete_asset", Some(&payload))
+            .await
+            .map(drop)
+    }
+
+    pub async fn storage_report(&self) -> Result<Vec<CreatorStorage>> {
+        self.fetch(Method::GET, "/assets/storage_report", None::<&()>)
+            .await
+    }
+
+    /// Opens a realtime connection and joins `space_id`. The server's reply
+    /// to the join is the first message the connection yields.
+    pub async fn connect_realtime(&self, space_id: i32) -> Result<Realtime> {
+        let url = self.url("/realtime/ws").replacen("http", "ws", 1);
+        let token = self.token().await?;
+        let socket = match connect_socket(&url, &token).await {
+            // As with other requests, a rejected token gets one fresh signin.
+            Err(Error::WebSocket(err)) if rejected_token(&err) => {
+                let token = self.refresh().await?;
+                connect_socket(&url, &token).await?
+            }
+            socket => socket?,
+        };
+        let mut realtime = Realtime::new(socket);
+        realtime.send(&ClientMessage::Join { space_id }).await?;
+        Ok(realtime)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn fetch_session(&self, credentials: SignInPayload) -> Result<Session> {
+        let response = self
+            .http
+            .post(self.url("/common/signin"))
+            .json(&credentials)
+            .send()
+            .await?;
+        let SignInResponse { token } = checked(response)?.json().await?;
+        Session::new(credentials, token)
+    }
+
+    async fn fetch<B, T>(&self, method: Method, path: &str, body: Option<&B>) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        Ok(self.send(method, path, body).await?.json().await?)
+    }
+
+    // Sends an authenticated request. A 401 means the server no longer accepts
+    // our token, so sign in again and retry once before giving up.
+    async fn send<B>(&self, method: Method, path: &str, body: Option<&B>) -> Result<Response>
+    where
+        B: Serialize + ?Sized,
+    {
+        let token = self.token().await?;
+        let response = self.request(method.clone(), path, body, &token).await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return checked(response);
+        }
+
+        let token = self.refresh().await?;
+        checked(self.request(method, path, body, &token).await?)
+    }
+
+    /// Signs in again with the stored credentials, returning the new token.
+    async fn refresh(&self) -> Result<String> {
+        let mut session = self.session.lock().await;
+        let credentials = session
+            .as_ref()
+            .ok_or(Error::NotSignedIn)?
+            .credentials
+            .clone();
+        let refreshed = self.fetch_session(credentials).await?;
+        let token = refreshed.token.clone();
+        *session = Some(refreshed);
+        Ok(token)
+    }
+
+    async fn request<B>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+        token: &str,
+    ) -> Result<Response>
+    where
+        B: Serialize + ?Sized,
+    {
+        let mut request = self.http.request(method, self.url(path)).bearer_auth(token);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        Ok(request.send().await?)
+    }
+}
+
+async fn connect_socket(
+    url: &str,
+    token: &str,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+    let mut request = url.into_client_request()?;
+    let authorization = format!("Bearer {token}")
+        .parse()
+        .map_err(|_| Error::MalformedToken)?;
+    request.headers_mut().insert(AUTHORIZATION, authorization);
+    let (socket, _) = connect_async(request).await?;
+    Ok(socket)
+}
+
+fn rejected_token(err: &WsError) -> bool {
+    matches!(err, WsError::Http(response) if response.status() == StatusCode::UNAUTHORIZED)
+}
+
+fn checked(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(Error::Status(status))
+    }
+}
@@ -0,0 +1,56 @@
+use futures_util::{SinkExt, Stream, StreamExt};
+use metaverse_core::realtime::{ClientMessage, ServerMessage};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
+
+use crate::error::{Error, Result};
+
+/// A realtime connection to one space. Yields what the server sends until
+/// the connection closes.
+pub struct Realtime {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl Realtime {
+    pub(crate) fn new(socket: WebSocketStream<MaybeTlsStream<TcpStream>>) -> Self {
+        Self { socket }
+    }
+
+    pub async fn send(&mut self, message: &ClientMessage) -> Result<()> {
+        let text = serde_json::to_string(message)?;
+        self.socket.send(Message::Text(text.into())).await?;
+        Ok(())
+    }
+
+    pub async fn close(mut self) -> Result<()> {
+        self.socket.close(None).await?;
+        Ok(())
+    }
+}
+
+impl Stream for Realtime {
+    type Item = Result<ServerMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let message = match self.socket.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(message))) => message,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            // Pings are answered by the socket itself; only text carries messages.
+            match message {
+                Message::Text(text) => {
+                    return Poll::Ready(Some(serde_json::from_str(&text).map_err(Error::from)));
+                }
+                Message::Close(_) => return Poll::Ready(None),
+                _ => continue,
+            }
+        }
+    }
+}
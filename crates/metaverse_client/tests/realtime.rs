@@ -0,0 +1,165 @@
+use axum::{
+    Json, Router,
+    extract::{
+        State, WebSocketUpgrade,
+        ws::{Message, WebSocket},
+    },
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    routing::{any, post},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use futures_util::StreamExt;
+use metaverse_client::{
+    Client,
+    types::realtime::{ClientMessage, PresentUser, ServerMessage},
+};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// A stand-in for the server, handing out tokens like the one in
+// token_refresh.rs. The socket only upgrades with the most recent token,
+// answers `join` with `joined` and echoes moves back.
+#[derive(Clone)]
+struct Fake {
+    signins: Arc<AtomicUsize>,
+    lifetime: i64,
+}
+
+fn token(serial: usize, lifetime: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let claims = serde_json::json!({ "sub": "1", "exp": now + lifetime, "role": "User" });
+    format!(
+        "{}.{}.token-{serial}",
+        URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    )
+}
+
+async fn signin(State(fake): State<Fake>) -> Json<serde_json::Value> {
+    let serial = fake.signins.fetch_add(1, Ordering::SeqCst) + 1;
+    Json(serde_json::json!({ "token": token(serial, fake.lifetime) }))
+}
+
+async fn connect(
+    State(fake): State<Fake>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let latest = format!("token-{}", fake.signins.load(Ordering::SeqCst));
+    let authorization = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if authorization.ends_with(&latest) {
+        Ok(ws.on_upgrade(relay))
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+async fn relay(mut socket: WebSocket) {
+    while let Some(Ok(Message::Text(text))) = socket.recv().await {
+        let reply = match serde_json::from_str(&text).unwrap() {
+            ClientMessage::Join { space_id } => ServerMessage::Joined {
+                space_id,
+                you: user(2, 3),
+                users: Vec::new(),
+                elements: Vec::new(),
+            },
+            ClientMessage::Move { x, y, .. } => ServerMessage::UserMoved(user(x, y)),
+            _ => continue,
+        };
+        let text = serde_json::to_string(&reply).unwrap();
+        socket.send(Message::Text(text.into())).await.unwrap();
+    }
+}
+
+fn user(x: i32, y: i32) -> PresentUser {
+    PresentUser {
+        user_id: 1,
+        x,
+        y,
+        rotation: 0,
+    }
+}
+
+async fn serve(lifetime: i64) -> (Client, Fake) {
+    let fake = Fake {
+        signins: Arc::new(AtomicUsize::new(0)),
+        lifetime,
+    };
+    let app = Router::new()
+        .route("/api/v1/common/signin", post(signin))
+        .route("/api/v1/realtime/ws", any(connect))
+        .with_state(fake.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (Client::new(format!("http://{address}/api/v1")), fake)
+}
+
+async fn next(realtime: &mut metaverse_client::Realtime) -> ServerMessage {
+    tokio::time::timeout(Duration::from_secs(5), realtime.next())
+        .await
+        .expect("timed out waiting for a message")
+        .expect("connection closed")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn joins_the_space_and_relays_messages() {
+    let (client, fake) = serve(30).await;
+    client.signin("alice", "secret").await.unwrap();
+
+    let mut realtime = client.connect_realtime(7).await.unwrap();
+    let joined = next(&mut realtime).await;
+    realtime
+        .send(&ClientMessage::Move {
+            x: 4,
+            y: 5,
+            rotation: 0,
+        })
+        .await
+        .unwrap();
+    let moved = next(&mut realtime).await;
+
+    // The token was about to expire, so the socket got a fresh one.
+    assert_eq!(fake.signins.load(Ordering::SeqCst), 2);
+    assert!(matches!(joined, ServerMessage::Joined { space_id: 7, .. }));
+    assert_eq!(moved, ServerMessage::UserMoved(user(4, 5)));
+}
+
+#[tokio::test]
+async fn reconnects_once_after_a_rejected_token() {
+    let (client, fake) = serve(3600).await;
+    client.signin("alice", "secret").await.unwrap();
+    fake.signins.fetch_add(1, Ordering::SeqCst);
+
+    let mut realtime = client.connect_realtime(7).await.unwrap();
+
+    assert!(matches!(
+        next(&mut realtime).await,
+        ServerMessage::Joined { space_id: 7, .. }
+    ));
+    assert_eq!(fake.signins.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn requires_signin() {
+    let (client, _) = serve(3600).await;
+
+    assert!(matches!(
+        client.connect_realtime(7).await,
+        Err(metaverse_client::Error::NotSignedIn)
+    ));
+}
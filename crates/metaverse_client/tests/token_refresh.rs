@@ -0,0 +1,106 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use metaverse_client::{Client, types::worlds::World};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// A stand-in for the server: every signin hands out a token that expires
+// `lifetime` seconds from now and is named after the signin count, and
+// get_worlds only accepts the most recent one.
+#[derive(Clone)]
+struct Fake {
+    signins: Arc<AtomicUsize>,
+    lifetime: i64,
+}
+
+fn token(serial: usize, lifetime: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let claims = serde_json::json!({ "sub": "1", "exp": now + lifetime, "role": "User" });
+    format!(
+        "{}.{}.token-{serial}",
+        URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    )
+}
+
+async fn signin(State(fake): State<Fake>) -> Json<serde_json::Value> {
+    let serial = fake.signins.fetch_add(1, Ordering::SeqCst) + 1;
+    Json(serde_json::json!({ "token": token(serial, fake.lifetime) }))
+}
+
+async fn get_worlds(
+    State(fake): State<Fake>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<World>>, StatusCode> {
+    let latest = format!("token-{}", fake.signins.load(Ordering::SeqCst));
+    let authorization = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if authorization.ends_with(&latest) {
+        Ok(Json(Vec::new()))
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+async fn serve(lifetime: i64) -> (Client, Fake) {
+    let fake = Fake {
+        signins: Arc::new(AtomicUsize::new(0)),
+        lifetime,
+    };
+    let app = Router::new()
+        .route("/api/v1/common/signin", post(signin))
+        .route("/api/v1/worlds/get_worlds", get(get_worlds))
+        .with_state(fake.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (Client::new(format!("http://{address}/api/v1")), fake)
+}
+
+#[tokio::test]
+async fn signs_in_again_before_the_token_expires() {
+    let (client, fake) = serve(30).await;
+    client.signin("alice", "secret").await.unwrap();
+
+    client.worlds().await.unwrap();
+
+    assert_eq!(fake.signins.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn retries_once_after_a_rejected_token() {
+    let (client, fake) = serve(3600).await;
+    client.signin("alice", "secret").await.unwrap();
+    // Someone else signing in as the same user invalidates our token.
+    fake.signins.fetch_add(1, Ordering::SeqCst);
+
+    client.worlds().await.unwrap();
+
+    assert_eq!(fake.signins.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn requires_signin() {
+    let (client, _) = serve(3600).await;
+
+    assert!(matches!(
+        client.worlds().await,
+        Err(metaverse_client::Error::NotSignedIn)
+    ));
+}
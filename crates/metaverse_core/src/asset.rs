@@ -0,0 +1,248 @@
+//! Uploaded files: images, 3D models and audio. Each is stored once, keyed by
+//! the SHA-256 of its content, and entities refer to it by id.
+//!
+//! What a file is comes from its content, not its name or the type the
+//! uploader declared: [`Format::sniff`] reads the magic bytes of the formats
+//! the clients can load and refuses everything else. Images are also resized
+//! to each [`ImageVariant`], so lists can show small copies.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "varchar", rename_all = "lowercase")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AssetType {
+    Image,
+    Model,
+    Audio,
+}
+
+impl AssetType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AssetType::Image => "image",
+            AssetType::Model => "model",
+            AssetType::Audio => "audio",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Asset {
+    pub id: i32,
+    pub name: String,
+    pub asset_type: AssetType,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    /// SHA-256 of the content, as lowercase hex.
+    pub hash: String,
+    pub url: String,
+    /// Set for images.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variants: Option<ImageVariants>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetAssetPayload {
+    pub asset_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeleteAssetPayload {
+    pub asset_id: i32,
+}
+
+/// The kinds of rows that can use an asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "text", rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Referrer {
+    World,
+    Map,
+    Space,
+    Avatar,
+    AvatarPart,
+    ElementTemplate,
+    /// A past revision of an element template, kept for elements pinned to it.
+    TemplateRevision,
+    Message,
+}
+
+/// One use of an asset. While any remain, the asset can't be deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AssetReference {
+    pub referrer: Referrer,
+    pub referrer_id: i32,
+    /// The URL field holding the asset, e.g. `thumbnail_url`.
+    pub field: String,
+}
+
+/// How much storage one creator's uploads take, resized copies included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreatorStorage {
+    pub creator_id: i32,
+    pub username: String,
+    pub assets: i64,
+    pub bytes: i64,
+    /// Of `assets`, those nothing uses.
+    pub orphaned_assets: i64,
+    pub orphaned_bytes: i64,
+}
+
+/// The sizes uploaded images are stored at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageVariant {
+    Thumbnail,
+    Medium,
+    Full,
+}
+
+impl ImageVariant {
+    pub const ALL: [ImageVariant; 3] = [
+        ImageVariant::Thumbnail,
+        ImageVariant::Medium,
+        ImageVariant::Full,
+    ];
+
+    /// Longest side in pixels. Smaller images are not enlarged.
+    pub fn max_side(self) -> u32 {
+        match self {
+            ImageVariant::Thumbnail => 128,
+            ImageVariant::Medium => 512,
+            ImageVariant::Full => 2048,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImageVariant::Thumbnail => "thumbnail",
+            ImageVariant::Medium => "medium",
+            ImageVariant::Full => "full",
+        }
+    }
+}
+
+/// Where to download an uploaded image at each [`ImageVariant`] size.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImageVariants {
+    pub thumbnail: String,
+    pub medium: String,
+    pub full: String,
+}
+
+// Queries select variants as a JSON object of URLs by variant name.
+#[cfg(feature = "postgres")]
+impl sqlx::Type<sqlx::Postgres> for ImageVariants {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        <sqlx::types::Json<Self> as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+        <sqlx::types::Json<Self> as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for ImageVariants {
+    fn decode(value: sqlx::postgres::PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        <sqlx::types::Json<Self> as sqlx::Decode<sqlx::Postgres>>::decode(value).map(|json| json.0)
+    }
+}
+
+/// A file format uploads may be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+    pub asset_type: AssetType,
+    pub mime_type: &'static str,
+    pub extension: &'static str,
+    /// Other names clients send for the same type.
+    aliases: &'static [&'static str],
+}
+
+const PNG: Format = Format::new(AssetType::Image, "image/png", "png", &[]);
+const JPEG: Format = Format::new(AssetType::Image, "image/jpeg", "jpg", &["image/jpg"]);
+const GIF: Format = Format::new(AssetType::Image, "image/gif", "gif", &[]);
+const WEBP: Format = Format::new(AssetType::Image, "image/webp", "webp", &[]);
+const GLB: Format = Format::new(AssetType::Model, "model/gltf-binary", "glb", &[]);
+const OGG: Format = Format::new(AssetType::Audio, "audio/ogg", "ogg", &[]);
+const MP3: Format = Format::new(AssetType::Audio, "audio/mpeg", "mp3", &["audio/mp3"]);
+const WAV: Format = Format::new(
+    AssetType::Audio,
+    "audio/wav",
+    "wav",
+    &["audio/x-wav", "audio/wave"],
+);
+
+impl Format {
+    const fn new(
+        asset_type: AssetType,
+        mime_type: &'static str,
+        extension: &'static str,
+        aliases: &'static [&'static str],
+    ) -> Self {
+        Self {
+            asset_type,
+            mime_type,
+            extension,
+            aliases,
+        }
+    }
+
+    /// The format `content` is in, if it is one uploads may be in.
+    pub fn sniff(content: &[u8]) -> Option<Format> {
+        let riff = |kind: &[u8]| content.starts_with(b"RIFF") && content.get(8..12) == Some(kind);
+        let format = match content {
+            [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => PNG,
+            [0xff, 0xd8, 0xff, ..] => JPEG,
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => GIF,
+            _ if riff(b"WEBP") => WEBP,
+            _ if riff(b"WAVE") => WAV,
+            [b'g', b'l', b'T', b'F', ..] => GLB,
+            [b'O', b'g', b'g', b'S', ..] => OGG,
+            // A tagged file, or a bare frame sync.
+            [b'I', b'D', b'3', ..] => MP3,
+            [0xff, second, ..] if second & 0xe0 == 0xe0 => MP3,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// Whether a declared content type names this format. Parameters such
+    /// as `; charset=...` are ignored.
+    pub fn matches(&self, content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        essence == self.mime_type || self.aliases.contains(&essence.as_str())
+    }
+}
+
+/// SHA-256 of `content`, as lowercase hex.
+pub fn content_hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -0,0 +1,177 @@
+//! Composable avatars. Admins publish [`AvatarPart`]s, each filling one
+//! [`AvatarSlot`]; users pick a part per slot, plus a few accessories, and
+//! may tint each one. Clients draw the resulting [`AvatarComposition`] back
+//! to front.
+
+use serde::{Deserialize, Serialize};
+
+use crate::asset::ImageVariants;
+
+/// Most accessories one avatar can wear.
+pub const MAX_ACCESSORIES: usize = 4;
+
+/// What a part is. Slots are listed in drawing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "varchar", rename_all = "lowercase")
+)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AvatarSlot {
+    Body,
+    Outfit,
+    Hair,
+    Accessory,
+}
+
+impl AvatarSlot {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AvatarSlot::Body => "body",
+            AvatarSlot::Outfit => "outfit",
+            AvatarSlot::Hair => "hair",
+            AvatarSlot::Accessory => "accessory",
+        }
+    }
+
+    /// How many parts in this slot one avatar can wear.
+    pub fn capacity(self) -> usize {
+        match self {
+            AvatarSlot::Accessory => MAX_ACCESSORIES,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AvatarPart {
+    pub id: i32,
+    pub slot: AvatarSlot,
+    pub name: String,
+    pub image_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_variants: Option<ImageVariants>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateAvatarPartPayload {
+    pub slot: AvatarSlot,
+    pub name: String,
+    /// Replaced by the URL of `image_asset_id`, if set.
+    #[serde(default)]
+    pub image_url: String,
+    #[serde(default)]
+    pub image_asset_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AvatarPartsResponse {
+    pub parts: Vec<AvatarPart>,
+}
+
+/// A part a user wears.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WornPart {
+    pub part_id: i32,
+    /// A colour like `#a0522d` the part is multiplied by. Untinted if unset.
+    #[serde(default)]
+    pub tint: Option<String>,
+}
+
+/// Replaces everything the user wears. Accessories are drawn in the order
+/// given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetAvatarPayload {
+    pub parts: Vec<WornPart>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetAvatarPayload {
+    pub user_id: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AvatarLayer {
+    pub part_id: i32,
+    pub slot: AvatarSlot,
+    pub name: String,
+    pub image_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_variants: Option<ImageVariants>,
+    pub tint: Option<String>,
+}
+
+/// Everything a user wears, back to front. Empty for users who haven't put
+/// an avatar together.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AvatarComposition {
+    pub layers: Vec<AvatarLayer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CompositionError {
+    #[error("part {0} does not exist")]
+    UnknownPart(i32),
+    #[error("part {0} is worn more than once")]
+    DuplicatePart(i32),
+    #[error("an avatar needs a body")]
+    NoBody,
+    #[error("an avatar can wear at most {} {} parts", .0.capacity(), .0.as_str())]
+    SlotFull(AvatarSlot),
+    #[error("tint {0:?} is not a colour like #a0522d")]
+    InvalidTint(String),
+}
+
+/// Checks that `worn` names parts from `parts`, has a body and fits every
+/// slot. Returns it with tints in lowercase, the form they are stored in.
+pub fn check_composition(
+    worn: &[WornPart],
+    parts: &[AvatarPart],
+) -> Result<Vec<WornPart>, CompositionError> {
+    let mut checked: Vec<WornPart> = Vec::with_capacity(worn.len());
+    let mut slots = Vec::with_capacity(worn.len());
+    for choice in worn {
+        let part = parts
+            .iter()
+            .find(|part| part.id == choice.part_id)
+            .ok_or(CompositionError::UnknownPart(choice.part_id))?;
+        if checked.iter().any(|c| c.part_id == choice.part_id) {
+            return Err(CompositionError::DuplicatePart(choice.part_id));
+        }
+        slots.push(part.slot);
+        if slots.iter().filter(|slot| **slot == part.slot).count() > part.slot.capacity() {
+            return Err(CompositionError::SlotFull(part.slot));
+        }
+        let tint = match &choice.tint {
+            Some(tint) if is_tint(tint) => Some(tint.to_ascii_lowercase()),
+            Some(tint) => return Err(CompositionError::InvalidTint(tint.clone())),
+            None => None,
+        };
+        checked.push(WornPart {
+            part_id: choice.part_id,
+            tint,
+        });
+    }
+    if !slots.contains(&AvatarSlot::Body) {
+        return Err(CompositionError::NoBody);
+    }
+    Ok(checked)
+}
+
+fn is_tint(tint: &str) -> bool {
+    tint.len() == 7
+        && tint
+            .strip_prefix('#')
+            .is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
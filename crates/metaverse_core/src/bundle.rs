@@ -0,0 +1,388 @@
+//! The world bundle: a world with everything placed in it, as one JSON
+//! document. `metaverse-admin worlds export` writes bundles and
+//! `worlds import` reads them, so worlds can move between deployments and be
+//! kept in git.
+//!
+//! # Format
+//!
+//! ```json
+//! {
+//!   "format": 1,
+//!   "world": { "name": "Lobby", "description": null, "thumbnail_url": null, "is_public": true },
+//!   "templates": [{
+//!     "key": 7, "name": "Door", "element_type": "Portal", "image_url": "/door.png",
+//!     "model_url": null, "width": 1, "height": 1, "is_collidable": false,
+//!     "interaction_data": null, "physics_properties": null, "animation_data": null
+//!   }],
+//!   "maps": [{ "key": 1, "name": "Town", "width": 64, "height": 48, "background_url": null }],
+//!   "spaces": [{
+//!     "key": 3, "map": 1, "name": "Cafe", "description": null, "width": 16, "height": 12,
+//!     "background_url": null, "thumbnail_url": null, "max_occupancy": 0, "is_private": false,
+//!     "default_spawn_x": 8, "default_spawn_y": 11
+//!   }],
+//!   "map_elements": [{
+//!     "map": 1, "template": 7, "x": 10, "y": 4, "z_index": 0, "target_space": 3,
+//!     "custom_properties": null
+//!   }],
+//!   "space_elements": []
+//! }
+//! ```
+//!
+//! - `format` is [`FORMAT`]; bundles in any other format are refused.
+//! - Rows mirror their tables, except that each carries a `key` instead of its
+//!   database id and refers to other rows by key: spaces name their `map`,
+//!   elements their `template` and their `map` or `space`, and portals their
+//!   `target_space`. Keys only need to be unique within their section; export
+//!   uses the database ids and import assigns fresh ones.
+//! - `templates` holds exactly the template revisions the elements pinned,
+//!   keyed by revision id. Portals leading outside the world are exported
+//!   without a `target_space`.
+//! - Templates may also carry a `properties_schema`, which their elements'
+//!   `custom_properties` must match; see [`crate::schema`]. Interactive
+//!   templates need a valid [`crate::interaction::Interaction`].
+//! - Images and models stay where their URLs point; bundles don't carry them.
+//!
+//! # Import
+//!
+//! Import runs in one transaction. A template whose [content
+//! hash](BundleTemplate::content_hash) matches an existing template is reused
+//! rather than copied. Anything that would surprise an operator, such as a
+//! world of the same name, is reported as a [`Conflict`]; import only goes
+//! ahead with conflicts when asked to, and a dry run always rolls back.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, fmt};
+
+use crate::element::{self, ElementType, TemplateError};
+use crate::schema::{self, SchemaError};
+
+/// The bundle format this build reads and writes.
+pub const FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldBundle {
+    pub format: u32,
+    pub world: BundleWorld,
+    /// Every template the world's elements use.
+    pub templates: Vec<BundleTemplate>,
+    pub maps: Vec<BundleMap>,
+    pub spaces: Vec<BundleSpace>,
+    pub map_elements: Vec<BundleMapElement>,
+    pub space_elements: Vec<BundleSpaceElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleWorld {
+    pub name: String,
+    pub description: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub is_public: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleTemplate {
+    pub key: i32,
+    pub name: String,
+    pub element_type: ElementType,
+    pub image_url: String,
+    pub model_url: Option<String>,
+    pub width: i32,
+    pub height: i32,
+    pub is_collidable: Option<bool>,
+    pub interaction_data: Option<serde_json::Value>,
+    pub physics_properties: Option<serde_json::Value>,
+    pub animation_data: Option<serde_json::Value>,
+    /// Left out when there is none, so older bundles hash the same.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties_schema: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleMap {
+    pub key: i32,
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub background_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleSpace {
+    pub key: i32,
+    pub map: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub width: i32,
+    pub height: i32,
+    pub background_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub max_occupancy: Option<i32>,
+    pub is_private: Option<bool>,
+    pub default_spawn_x: Option<i32>,
+    pub default_spawn_y: Option<i32>,
+}
+
+/// An element placed on a map. Portals lead to `target_space`, which is
+/// always one of the bundle's spaces.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleMapElement {
+    pub map: i32,
+    pub template: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z_index: Option<i32>,
+    pub target_space: Option<i32>,
+    pub custom_properties: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleSpaceElement {
+    pub space: i32,
+    pub template: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z_index: Option<i32>,
+    pub rotation: Option<i32>,
+    pub custom_properties: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BundleError {
+    #[error("bundle format {0} is not supported, expected {FORMAT}")]
+    UnsupportedFormat(u32),
+    #[error("{kind} key {key} is used more than once")]
+    DuplicateKey { kind: &'static str, key: i32 },
+    #[error("{from} refers to {kind} {key}, which is not in the bundle")]
+    UnknownKey {
+        from: &'static str,
+        kind: &'static str,
+        key: i32,
+    },
+    #[error("{from}: {source}")]
+    InvalidData {
+        from: &'static str,
+        source: SchemaError,
+    },
+    #[error("template {key}: {source}")]
+    InvalidTemplate { key: i32, source: TemplateError },
+}
+
+impl WorldBundle {
+    /// Checks that keys are unique, every reference resolves and the JSON on
+    /// templates and elements matches its schemas, so import can't fail
+    /// halfway on a malformed file.
+    pub fn validate(&self) -> Result<(), BundleError> {
+        if self.format != FORMAT {
+            return Err(BundleError::UnsupportedFormat(self.format));
+        }
+        let templates = keys("template", self.templates.iter().map(|t| t.key))?;
+        let maps = keys("map", self.maps.iter().map(|m| m.key))?;
+        let spaces = keys("space", self.spaces.iter().map(|s| s.key))?;
+
+        for template in &self.templates {
+            template
+                .validate()
+                .map_err(|source| BundleError::InvalidTemplate {
+                    key: template.key,
+                    source,
+                })?;
+        }
+        for space in &self.spaces {
+            resolve("space", "map", &maps, space.map)?;
+        }
+        for element in &self.map_elements {
+            resolve("map element", "map", &maps, element.map)?;
+            resolve("map element", "template", &templates, element.template)?;
+            if let Some(target) = element.target_space {
+                resolve("map element", "space", &spaces, target)?;
+            }
+            self.check_properties(
+                "map element",
+                element.template,
+                element.custom_properties.as_ref(),
+            )?;
+        }
+        for element in &self.space_elements {
+            resolve("space element", "space", &spaces, element.space)?;
+            resolve("space element", "template", &templates, element.template)?;
+            self.check_properties(
+                "space element",
+                element.template,
+                element.custom_properties.as_ref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Checks an element's `custom_properties` against its template's
+    /// `properties_schema`.
+    fn check_properties(
+        &self,
+        from: &'static str,
+        template: i32,
+        custom_properties: Option<&serde_json::Value>,
+    ) -> Result<(), BundleError> {
+        let properties_schema = self
+            .templates
+            .iter()
+            .find(|t| t.key == template)
+            .and_then(|t| t.properties_schema.as_ref());
+        schema::check_properties(
+            properties_schema,
+            custom_properties.unwrap_or(&serde_json::Value::Null),
+        )
+        .map_err(|source| BundleError::InvalidData { from, source })
+    }
+}
+
+impl BundleTemplate {
+    /// See [`element::check_template`].
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        element::check_template(
+            self.element_type,
+            self.interaction_data.as_ref(),
+            self.physics_properties.as_ref(),
+            self.animation_data.as_ref(),
+            self.properties_schema.as_ref(),
+        )
+    }
+
+    /// SHA-256 over everything but the key, as lowercase hex. Templates with
+    /// the same hash look and behave the same, whatever their ids.
+    pub fn content_hash(&self) -> String {
+        // A missing flag means the column default.
+        let normalized = BundleTemplate {
+            is_collidable: Some(self.is_collidable.unwrap_or(false)),
+            ..self.clone()
+        };
+        let mut content = serde_json::to_value(normalized).expect("templates always serialize");
+        if let Some(fields) = content.as_object_mut() {
+            fields.remove("key");
+        }
+        let mut canonical = String::new();
+        write_canonical(&content, &mut canonical);
+        Sha256::digest(canonical.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// JSON with object keys sorted at every level, so equal values hash the
+/// same however their keys were ordered.
+fn write_canonical(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            out.push('{');
+            for (i, (name, value)) in fields.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::Value::from(name.as_str()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// What stopped, or would stop, an import going ahead on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// Another world already has the bundle's world name.
+    WorldExists { name: String, world_id: i32 },
+    /// The world already has a map with the bundle map's name.
+    MapExists { name: String, map_id: i32 },
+    /// A template has the bundle template's name but different content, so
+    /// the bundle's is imported alongside it.
+    TemplateDiffers {
+        key: i32,
+        name: String,
+        existing_id: i32,
+    },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::WorldExists { name, world_id } => {
+                write!(f, "a world named {name:?} already exists (id {world_id})")
+            }
+            Conflict::MapExists { name, map_id } => {
+                write!(
+                    f,
+                    "the world already has a map named {name:?} (id {map_id})"
+                )
+            }
+            Conflict::TemplateDiffers {
+                key,
+                name,
+                existing_id,
+            } => write!(
+                f,
+                "template {key} {name:?} differs from existing template {existing_id} of the same name"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Check everything, then roll back.
+    pub dry_run: bool,
+    /// Import even if there are conflicts.
+    pub allow_conflicts: bool,
+}
+
+/// What an import did, or would have done on a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// The world imported into, if the import was committed.
+    pub world_id: Option<i32>,
+    pub templates_created: usize,
+    /// Bundle template keys and the existing templates used in their place.
+    pub templates_reused: Vec<(i32, i32)>,
+    pub maps: usize,
+    pub spaces: usize,
+    pub map_elements: usize,
+    pub space_elements: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+fn keys(kind: &'static str, keys: impl Iterator<Item = i32>) -> Result<HashSet<i32>, BundleError> {
+    let mut seen = HashSet::new();
+    for key in keys {
+        if !seen.insert(key) {
+            return Err(BundleError::DuplicateKey { kind, key });
+        }
+    }
+    Ok(seen)
+}
+
+fn resolve(
+    from: &'static str,
+    kind: &'static str,
+    keys: &HashSet<i32>,
+    key: i32,
+) -> Result<(), BundleError> {
+    if keys.contains(&key) {
+        Ok(())
+    } else {
+        Err(BundleError::UnknownKey { from, kind, key })
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Deserialize;
+use sqlx::{
+    PgConnection, PgPool,
+    migrate::{MigrateError, Migrator},
+    postgres::PgPoolOptions,
+};
+use std::time::Duration;
+use tracing::{Instrument, info};
+
+use crate::repo::query_span;
+
+/// The migrations in the workspace's `migrations/` directory, embedded at
+/// compile time. Each has an `.up.sql` and a `.down.sql` so it can be
+/// reverted.
+pub static MIGRATOR: Migrator = sqlx::migrate!("../../migrations");
+
+/// Advisory lock held while migrating, so replicas starting together take
+/// turns and only the first one has anything to do.
+const MIGRATION_LOCK: i64 = 0x6d65_7461_7665_7273;
+
+/// Connection pool settings, the `[database]` section of the server config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a request waits for a free connection before failing.
+    pub acquire_timeout_secs: u64,
+    /// Idle connections above `min_connections` are closed after this long.
+    pub idle_timeout_secs: u64,
+    /// Apply pending migrations when the server starts.
+    pub migrate_on_start: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+            migrate_on_start: false,
+        }
+    }
+}
+
+pub async fn connect(config: &DatabaseConfig) -> Result<PgPool, sqlx::Error> {
+    PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .idle_timeout(Duration::from_secs(config.idle_timeout_secs))
+        .connect(&config.url)
+        .await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the embedded file has changed since.
+    Changed,
+    /// Applied by a build that had a migration this one doesn't.
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Changed => "changed",
+            MigrationState::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Every embedded migration, plus any the database has that this build
+/// doesn't, in version order.
+pub async fn migration_status(
+    conn: &mut PgConnection,
+) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    // Not a checked query: sqlx creates this table on the first migration
+    // run, so it may not exist.
+    let applied: Vec<(i64, String, Vec<u8>)> = match sqlx::query_as(
+        "SELECT version, description, checksum FROM _sqlx_migrations WHERE success",
+    )
+    .fetch_all(&mut *conn)
+    .instrument(query_span("migrations.applied"))
+    .await
+    {
+        Ok(applied) => applied,
+        Err(sqlx::Error::Database(err)) if err.code().as_deref() == Some("42P01") => Vec::new(),
+        Err(err) => return Err(err),
+    };
+
+    let mut status: Vec<MigrationStatus> = MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| {
+            let state = match applied.iter().find(|(version, ..)| *version == m.version) {
+                None => MigrationState::Pending,
+                Some((.., checksum)) if *checksum == *m.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Changed,
+            };
+            MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+    for (version, description, _) in &applied {
+        if !status.iter().any(|m| m.version == *version) {
+            status.push(MigrationStatus {
+                version: *version,
+                description: description.clone(),
+                state: MigrationState::Unknown,
+            });
+        }
+    }
+    status.sort_by_key(|m| m.version);
+    Ok(status)
+}
+
+/// Applies every pending migration and returns the versions it applied.
+pub async fn migrate_up(pool: &PgPool) -> Result<Vec<i64>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    lock(&mut conn).await?;
+    let result = async {
+        let pending: Vec<i64> = migration_status(&mut conn)
+            .await?
+            .into_iter()
+            .filter(|m| m.state == MigrationState::Pending)
+            .map(|m| m.version)
+            .collect();
+        MIGRATOR.run(&mut *conn).await?;
+        Ok(pending)
+    }
+    .await;
+    unlock(&mut conn).await?;
+    result
+}
+
+/// Reverts every applied migration newer than `target`, or only the latest
+/// one when `target` is `None`. Returns the versions it reverted, newest
+/// first.
+pub async fn migrate_down(pool: &PgPool, target: Option<i64>) -> Result<Vec<i64>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    lock(&mut conn).await?;
+    let result = async {
+        let mut applied: Vec<i64> = migration_status(&mut conn)
+            .await?
+            .into_iter()
+            .filter(|m| m.state != MigrationState::Pending)
+            .map(|m| m.version)
+            .collect();
+        applied.reverse();
+        let target = target.unwrap_or_else(|| applied.get(1).copied().unwrap_or(0));
+        MIGRATOR.undo(&mut *conn, target).await?;
+        Ok(applied.into_iter().filter(|v| *v > target).collect())
+    }
+    .await;
+    unlock(&mut conn).await?;
+    result
+}
+
+async fn lock(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+        .bind(MIGRATION_LOCK)
+        .fetch_one(&mut *conn)
+        .await?;
+    if !locked {
+        info!("Another instance is migrating the database, waiting for it");
+        sqlx::query("SELECT pg_advisory_lock($1)")
+            .bind(MIGRATION_LOCK)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn unlock(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::interaction::Interaction;
+use crate::schema::{self, SchemaError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
+#[cfg_attr(feature = "postgres", sqlx(type_name = "element_type_enum"))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ElementType {
+    Static,
+    Interactive,
+    Decorative,
+    Portal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateElementTemplatePayload {
+    pub name: String,
+    pub element_type: ElementType,
+    /// Replaced by the URL of `image_asset_id`, if set.
+    #[serde(default)]
+    pub image_url: String,
+    #[serde(default)]
+    pub image_asset_id: Option<i32>,
+    /// Replaced by the URL of `model_asset_id`, if set.
+    #[serde(default)]
+    pub model_url: String,
+    #[serde(default)]
+    pub model_asset_id: Option<i32>,
+    pub width: i32,
+    pub height: i32,
+    pub is_collidable: bool,
+    pub interaction_data: serde_json::Value,
+    /// Must match [`crate::schema::physics_schema`].
+    pub physics_properties: serde_json::Value,
+    /// Must match [`crate::schema::animation_schema`].
+    #[serde(default)]
+    pub animation_data: Option<serde_json::Value>,
+    /// A JSON Schema for the `custom_properties` of elements placed from
+    /// this template.
+    #[serde(default)]
+    pub properties_schema: Option<serde_json::Value>,
+}
+
+impl CreateElementTemplatePayload {
+    /// See [`check_template`].
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        check_template(
+            self.element_type,
+            Some(&self.interaction_data),
+            Some(&self.physics_properties),
+            self.animation_data.as_ref(),
+            self.properties_schema.as_ref(),
+        )
+    }
+}
+
+/// Why a template was refused.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TemplateError {
+    #[error("interaction_data is not a valid interaction: {0}")]
+    InvalidInteraction(String),
+    #[error("set_state interactions need a properties_schema to check states against")]
+    UncheckedState,
+    #[error(transparent)]
+    InvalidData(#[from] SchemaError),
+}
+
+/// Checks a template however it arrives, over HTTP or in an import, so that
+/// nothing stored fails once it is used: interactive templates need a valid
+/// [`Interaction`], `set_state` ones a `properties_schema` too, and the JSON
+/// has to match [`schema::check_template`].
+pub fn check_template(
+    element_type: ElementType,
+    interaction_data: Option<&Value>,
+    physics_properties: Option<&Value>,
+    animation_data: Option<&Value>,
+    properties_schema: Option<&Value>,
+) -> Result<(), TemplateError> {
+    if element_type == ElementType::Interactive {
+        let interaction = Interaction::parse(interaction_data.unwrap_or(&Value::Null))
+            .map_err(|err| TemplateError::InvalidInteraction(err.to_string()))?;
+        if interaction.action.takes_state() && properties_schema.is_none_or(Value::is_null) {
+            return Err(TemplateError::UncheckedState);
+        }
+    }
+    schema::check_template(physics_properties, animation_data, properties_schema)?;
+    Ok(())
+}
+
+/// Replaces a template with a new revision. Elements already placed keep the
+/// revision they pinned until moved with [`PinRevisionPayload`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UpdateElementTemplatePayload {
+    pub template_id: i32,
+    #[serde(flatten)]
+    pub template: CreateElementTemplatePayload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TemplateRevision {
+    pub template_id: i32,
+    pub revision: i32,
+}
+
+/// Moves a template's elements to one of its revisions, forward or back.
+/// Pinning and previewing need exactly one of `space_id` and `map_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PinRevisionPayload {
+    pub template_id: i32,
+    pub revision: i32,
+    #[serde(default)]
+    pub space_id: Option<i32>,
+    #[serde(default)]
+    pub map_id: Option<i32>,
+}
+
+/// The elements a [`PinRevisionPayload`] moves: those not already at the
+/// revision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RevisionPreview {
+    pub template_id: i32,
+    pub revision: i32,
+    pub elements: Vec<PinnedElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PinnedElement {
+    pub element_id: i32,
+    /// Set for space elements.
+    pub space_id: Option<i32>,
+    /// Set for map elements.
+    pub map_id: Option<i32>,
+    /// The revision it is at now.
+    pub revision: i32,
+    /// Why its `custom_properties` don't match the revision's
+    /// `properties_schema`. Pinning is refused while any element has some.
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateMapElementsPayload {
+    pub map_id: i32,
+    pub template_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z_index: i32,
+    pub target_space_id: i32,
+    pub custom_properties: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateSpaceElementsPayload {
+    pub space_id: i32,
+    pub template_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z_index: i32,
+    pub rotation: i32,
+    pub custom_properties: serde_json::Value,
+}
+
+/// A placed space element with the parts of its pinned template revision that
+/// decide how it can be used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedElement {
+    pub id: i32,
+    pub space_id: i32,
+    pub x: i32,
+    pub y: i32,
+    /// Footprint in tiles, from the template.
+    pub width: i32,
+    pub height: i32,
+    pub element_type: ElementType,
+    pub interaction_data: Option<serde_json::Value>,
+    /// What `set_state` interactions must send.
+    pub properties_schema: Option<serde_json::Value>,
+    /// Shared state, `None` until an interaction first changes it.
+    pub state: Option<serde_json::Value>,
+    pub state_version: i32,
+}
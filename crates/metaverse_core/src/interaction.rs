@@ -0,0 +1,73 @@
+//! What interactive elements do when someone uses them. An `Interactive`
+//! template's `interaction_data` is one [`Interaction`], tagged by `action`,
+//! e.g. `{"action":"teleport","x":3,"y":4}` or
+//! `{"action":"show_text","text":"Closed on Sundays","range":3}`.
+
+use serde::{Deserialize, Serialize, de::Error as _};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Interaction {
+    #[serde(flatten)]
+    pub action: Action,
+    /// How many tiles away from the element someone may use it from, if not
+    /// the server's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Open a page for the user who used it. Only `http` and `https` URLs
+    /// are accepted, since clients open it without asking.
+    OpenUrl { url: String },
+    /// Show text to the user who used it.
+    ShowText {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        text: String,
+    },
+    /// Move the user who used it to a spot in the same space.
+    Teleport { x: i32, y: i32 },
+    /// Switch the element on or off for everyone in the space.
+    Toggle,
+    /// Replace the element's state, for everyone in the space, with the
+    /// `state` the user sends, e.g. what is drawn on a whiteboard. The state
+    /// must match the template's `properties_schema`, which templates with
+    /// this action need to have.
+    SetState,
+    /// Start a call everyone in the space can join. Elements with the same
+    /// `room` share a call; without one each element has its own.
+    StartCall {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        room: Option<String>,
+    },
+}
+
+impl Interaction {
+    /// Reads a template's `interaction_data`.
+    pub fn parse(data: &serde_json::Value) -> Result<Self, serde_json::Error> {
+        let interaction = Interaction::deserialize(data)?;
+        if let Action::OpenUrl { url } = &interaction.action
+            && !is_web_url(url)
+        {
+            return Err(serde_json::Error::custom(format!(
+                "url {url:?} is not an http or https URL"
+            )));
+        }
+        Ok(interaction)
+    }
+}
+
+impl Action {
+    /// Whether using the element needs a `state` from the user.
+    pub fn takes_state(&self) -> bool {
+        matches!(self, Action::SetState)
+    }
+}
+
+fn is_web_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
@@ -0,0 +1,23 @@
+//! Domain types and database access shared by the metaverse server, its
+//! tooling and its clients.
+//!
+//! Every payload the HTTP API accepts or returns lives here, so the server
+//! handlers and `metaverse_client` can't disagree about a field. Database
+//! access sits behind the `postgres` feature so clients don't pull in sqlx.
+
+pub mod asset;
+pub mod avatar;
+pub mod bundle;
+pub mod common;
+#[cfg(feature = "postgres")]
+pub mod db;
+pub mod element;
+pub mod interaction;
+pub mod maps;
+pub mod realtime;
+#[cfg(feature = "postgres")]
+pub mod repo;
+pub mod schema;
+pub mod space;
+pub mod user;
+pub mod worlds;
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateMapPayload {
+    pub world_id: i32,
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    /// Replaced by the URL of `background_asset_id`, if set.
+    #[serde(default)]
+    pub background_url: String,
+    #[serde(default)]
+    pub background_asset_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateMapResponse {
+    pub map_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetMapPayload {
+    pub map_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetMapResponse {
+    pub map_id: i32,
+    pub world_id: i32,
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub background_url: Option<String>,
+}
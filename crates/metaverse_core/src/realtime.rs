@@ -0,0 +1,93 @@
+//! Messages exchanged over the realtime WebSocket, encoded as JSON text frames
+//! tagged by `type`, e.g. `{"type":"move","x":3,"y":4,"rotation":90}`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Enter a space, leaving the current one first.
+    Join {
+        space_id: i32,
+    },
+    Move {
+        x: i32,
+        y: i32,
+        rotation: i32,
+    },
+    /// Use an interactive element in the current space; see
+    /// [`crate::interaction`].
+    Interact {
+        element_id: i32,
+        /// The element state version the client last saw. Changes to stateful
+        /// elements are refused if it has moved on since.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<i32>,
+        /// The new state, for elements whose action is `set_state`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        state: Option<serde_json::Value>,
+    },
+    Leave,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Reply to `join`: where this user spawned, who is already there and
+    /// which elements have been switched from how they started.
+    Joined {
+        space_id: i32,
+        you: PresentUser,
+        users: Vec<PresentUser>,
+        elements: Vec<ElementState>,
+    },
+    UserJoined(PresentUser),
+    UserMoved(PresentUser),
+    UserLeft {
+        user_id: i32,
+    },
+    /// Sent only to the user who used the element.
+    OpenUrl {
+        element_id: i32,
+        url: String,
+    },
+    /// Sent only to the user who used the element.
+    ShowText {
+        element_id: i32,
+        title: Option<String>,
+        text: String,
+    },
+    ElementChanged(ElementState),
+    CallStarted {
+        element_id: i32,
+        room: String,
+        user_id: i32,
+    },
+    Error {
+        message: String,
+    },
+    /// The server is going away. Positions have been saved; reconnect and
+    /// join again to continue.
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PresentUser {
+    pub user_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub rotation: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ElementState {
+    pub element_id: i32,
+    /// `{"on": bool}` for toggles, whatever the user sent for `set_state`.
+    pub state: serde_json::Value,
+    /// How many times the state has changed.
+    pub version: i32,
+}
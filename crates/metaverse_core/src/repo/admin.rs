@@ -0,0 +1,150 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tracing::Instrument;
+
+use super::{RepoResult, query_span};
+use crate::common::Role;
+
+/// A row of `metaverse-admin users list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSummary {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    pub role: Role,
+    pub is_online: bool,
+    pub disabled: bool,
+}
+
+/// A row of `metaverse-admin sessions list`. Times are ISO 8601 in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionSummary {
+    pub id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub space_id: Option<i32>,
+    pub status: String,
+    pub connected_at: Option<String>,
+    pub last_activity: Option<String>,
+}
+
+/// Operator tasks behind `metaverse-admin`. The API never calls these.
+#[async_trait]
+pub trait AdminRepo: Send + Sync {
+    async fn users(&self) -> RepoResult<Vec<UserSummary>>;
+    async fn set_role(&self, username: &str, role: Role) -> RepoResult<()>;
+    /// Disabling also closes the user's active sessions; servers disconnect
+    /// them on their next flush.
+    async fn set_disabled(&self, username: &str, disabled: bool) -> RepoResult<()>;
+    async fn set_password_hash(&self, username: &str, password_hash: &str) -> RepoResult<()>;
+    /// Active sessions, or every session with `all`, newest first.
+    async fn sessions(&self, all: bool) -> RepoResult<Vec<SessionSummary>>;
+    /// Deletes sessions that ended more than `older_than_secs` ago. Returns
+    /// how many were deleted.
+    async fn prune_sessions(&self, older_than_secs: f64) -> RepoResult<u64>;
+}
+
+pub struct PgAdminRepo {
+    pool: PgPool,
+}
+
+impl PgAdminRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AdminRepo for PgAdminRepo {
+    async fn users(&self) -> RepoResult<Vec<UserSummary>> {
+        let users = sqlx::query_as!(
+            UserSummary,
+            r#"SELECT id, username, email, role AS "role: Role", COALESCE(is_online, FALSE) AS "is_online!", disabled_at IS NOT NULL AS "disabled!" FROM users ORDER BY id"#
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("admin.users"))
+        .await?;
+        Ok(users)
+    }
+
+    async fn set_role(&self, username: &str, role: Role) -> RepoResult<()> {
+        // RETURNING turns an unknown username into `RepoError::NotFound`.
+        sqlx::query_scalar!(
+            "UPDATE users SET role = $1 WHERE username = $2 RETURNING id",
+            role as Role,
+            username
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("admin.set_role"))
+        .await?;
+        Ok(())
+    }
+
+    async fn set_disabled(&self, username: &str, disabled: bool) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let user_id = sqlx::query_scalar!(
+            "UPDATE users SET disabled_at = CASE WHEN $1 THEN COALESCE(disabled_at, CURRENT_TIMESTAMP) END WHERE username = $2 RETURNING id",
+            disabled,
+            username
+        )
+        .fetch_one(&mut *tx)
+        .instrument(query_span("admin.set_disabled"))
+        .await?;
+        if disabled {
+            sqlx::query!(
+                "UPDATE user_sessions SET status = 'Inactive', last_activity = CURRENT_TIMESTAMP WHERE user_id = $1 AND status = 'Active'",
+                user_id
+            )
+            .execute(&mut *tx)
+            .instrument(query_span("admin.set_disabled.close_sessions"))
+            .await?;
+            sqlx::query!("UPDATE users SET is_online = FALSE WHERE id = $1", user_id)
+                .execute(&mut *tx)
+                .instrument(query_span("admin.set_disabled.set_offline"))
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn set_password_hash(&self, username: &str, password_hash: &str) -> RepoResult<()> {
+        sqlx::query_scalar!(
+            "UPDATE users SET password_hash = $1 WHERE username = $2 RETURNING id",
+            password_hash,
+            username
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("admin.set_password_hash"))
+        .await?;
+        Ok(())
+    }
+
+    async fn sessions(&self, all: bool) -> RepoResult<Vec<SessionSummary>> {
+        let sessions = sqlx::query_as!(
+            SessionSummary,
+            r#"SELECT s.id, s.user_id, u.username, s.space_id,
+                COALESCE(s.status::text, 'Active') AS "status!",
+                to_char(s.connected_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') AS connected_at,
+                to_char(s.last_activity AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') AS last_activity
+            FROM user_sessions s JOIN users u ON u.id = s.user_id
+            WHERE $1 OR s.status = 'Active'
+            ORDER BY s.id DESC"#,
+            all
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("admin.sessions"))
+        .await?;
+        Ok(sessions)
+    }
+
+    async fn prune_sessions(&self, older_than_secs: f64) -> RepoResult<u64> {
+        let deleted = sqlx::query!(
+            "DELETE FROM user_sessions WHERE status IS DISTINCT FROM 'Active' AND last_activity < CURRENT_TIMESTAMP - make_interval(secs => $1)",
+            older_than_secs
+        )
+        .execute(&self.pool)
+        .instrument(query_span("admin.prune_sessions"))
+        .await?;
+        Ok(deleted.rows_affected())
+    }
+}
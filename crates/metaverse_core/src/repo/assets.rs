@@ -0,0 +1,224 @@
+use async_trait::async_trait;
+use sqlx::{PgExecutor, PgPool};
+use std::time::Duration;
+use tracing::Instrument;
+
+use super::{RepoError, RepoResult, query_span};
+use crate::asset::{
+    Asset, AssetReference, AssetType, CreatorStorage, ImageVariant, ImageVariants, Referrer,
+};
+
+pub struct NewAsset {
+    pub name: String,
+    pub asset_type: AssetType,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub hash: String,
+    /// Where the storage backend keeps it.
+    pub storage_key: String,
+    pub url: String,
+    /// Resized copies, for images.
+    pub variants: Vec<NewVariant>,
+}
+
+pub struct NewVariant {
+    pub variant: ImageVariant,
+    pub width: i32,
+    pub height: i32,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+    pub url: String,
+}
+
+#[async_trait]
+pub trait AssetRepo: Send + Sync {
+    /// Fails with `Conflict` if an asset with the same hash exists.
+    async fn create(&self, creator_id: i32, asset: &NewAsset) -> RepoResult<Asset>;
+    async fn get(&self, id: i32) -> RepoResult<Asset>;
+    async fn by_hash(&self, hash: &str) -> RepoResult<Option<Asset>>;
+    /// Everything using the asset.
+    async fn references(&self, id: i32) -> RepoResult<Vec<AssetReference>>;
+    /// Deletes the asset and returns the storage keys of its files, which are
+    /// left for the caller to remove. Fails with `Conflict` while anything
+    /// uses it. With a `creator_id`, only deletes that creator's assets.
+    async fn delete(&self, id: i32, creator_id: Option<i32>) -> RepoResult<Vec<String>>;
+    /// Assets nothing uses that were uploaded more than `grace` ago.
+    async fn orphans(&self, grace: Duration) -> RepoResult<Vec<i32>>;
+    async fn storage_by_creator(&self) -> RepoResult<Vec<CreatorStorage>>;
+}
+
+pub struct PgAssetRepo {
+    pool: PgPool,
+}
+
+impl PgAssetRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AssetRepo for PgAssetRepo {
+    async fn create(&self, creator_id: i32, asset: &NewAsset) -> RepoResult<Asset> {
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query_scalar!(
+            "INSERT INTO assets (name, type, mime_type, size_bytes, hash, storage_key, url, creator_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+            asset.name,
+            asset.asset_type.as_str(),
+            asset.mime_type,
+            asset.size_bytes,
+            asset.hash,
+            asset.storage_key,
+            asset.url,
+            creator_id
+        )
+        .fetch_one(&mut *tx)
+        .instrument(query_span("assets.create"))
+        .await?;
+        for variant in &asset.variants {
+            sqlx::query!(
+                "INSERT INTO asset_variants (asset_id, variant, width, height, mime_type, size_bytes, storage_key, url) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                id,
+                variant.variant.as_str(),
+                variant.width,
+                variant.height,
+                variant.mime_type,
+                variant.size_bytes,
+                variant.storage_key,
+                variant.url
+            )
+            .execute(&mut *tx)
+            .instrument(query_span("assets.create_variant"))
+            .await?;
+        }
+        let created = fetch(&mut *tx, id).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn get(&self, id: i32) -> RepoResult<Asset> {
+        fetch(&self.pool, id).await
+    }
+
+    async fn by_hash(&self, hash: &str) -> RepoResult<Option<Asset>> {
+        let asset = sqlx::query_as!(
+            Asset,
+            r#"SELECT a.id, a.name, a.type AS "asset_type: AssetType", a.mime_type, a.size_bytes, a.hash AS "hash!", a.url,
+                v.urls AS "variants?: ImageVariants"
+            FROM assets a LEFT JOIN image_variants v ON v.asset_id = a.id
+            WHERE a.hash = $1"#,
+            hash
+        )
+        .fetch_optional(&self.pool)
+        .instrument(query_span("assets.by_hash"))
+        .await?;
+        Ok(asset)
+    }
+
+    async fn references(&self, id: i32) -> RepoResult<Vec<AssetReference>> {
+        let references = sqlx::query_as!(
+            AssetReference,
+            r#"SELECT referrer AS "referrer!: Referrer", referrer_id AS "referrer_id!", field AS "field!"
+            FROM asset_references WHERE asset_id = $1
+            ORDER BY referrer, referrer_id, field"#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("assets.references"))
+        .await?;
+        if references.is_empty() {
+            let exists = sqlx::query_scalar!(
+                r#"SELECT EXISTS (SELECT 1 FROM assets WHERE id = $1) AS "exists!""#,
+                id
+            )
+            .fetch_one(&self.pool)
+            .instrument(query_span("assets.exists"))
+            .await?;
+            if !exists {
+                return Err(RepoError::NotFound);
+            }
+        }
+        Ok(references)
+    }
+
+    async fn delete(&self, id: i32, creator_id: Option<i32>) -> RepoResult<Vec<String>> {
+        let mut tx = self.pool.begin().await?;
+        let mut keys = sqlx::query_scalar!(
+            "SELECT storage_key FROM asset_variants WHERE asset_id = $1",
+            id
+        )
+        .fetch_all(&mut *tx)
+        .instrument(query_span("assets.variant_keys"))
+        .await?;
+        // The foreign keys of whatever uses the asset refuse this.
+        let key = sqlx::query_scalar!(
+            "DELETE FROM assets WHERE id = $1 AND ($2::INTEGER IS NULL OR creator_id = $2) RETURNING storage_key",
+            id,
+            creator_id
+        )
+        .fetch_optional(&mut *tx)
+        .instrument(query_span("assets.delete"))
+        .await?
+        .ok_or(RepoError::NotFound)?;
+        tx.commit().await?;
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    async fn orphans(&self, grace: Duration) -> RepoResult<Vec<i32>> {
+        let ids = sqlx::query_scalar!(
+            "SELECT id FROM assets a
+            WHERE a.created_at < now() - make_interval(secs => $1)
+                AND NOT EXISTS (SELECT 1 FROM asset_references r WHERE r.asset_id = a.id)
+            ORDER BY id",
+            grace.as_secs_f64()
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("assets.orphans"))
+        .await?;
+        Ok(ids)
+    }
+
+    async fn storage_by_creator(&self) -> RepoResult<Vec<CreatorStorage>> {
+        // An image's files are its variants; anything else is one file.
+        let usage = sqlx::query_as!(
+            CreatorStorage,
+            r#"WITH sizes AS (
+                SELECT a.creator_id,
+                    COALESCE((SELECT SUM(v.size_bytes) FROM asset_variants v WHERE v.asset_id = a.id), a.size_bytes) AS bytes,
+                    NOT EXISTS (SELECT 1 FROM asset_references r WHERE r.asset_id = a.id) AS orphaned
+                FROM assets a
+            )
+            SELECT u.id AS creator_id, u.username,
+                COUNT(*) AS "assets!",
+                SUM(s.bytes)::BIGINT AS "bytes!",
+                COUNT(*) FILTER (WHERE s.orphaned) AS "orphaned_assets!",
+                COALESCE(SUM(s.bytes) FILTER (WHERE s.orphaned), 0)::BIGINT AS "orphaned_bytes!"
+            FROM sizes s JOIN users u ON u.id = s.creator_id
+            GROUP BY u.id, u.username
+            ORDER BY 4 DESC, u.id"#
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("assets.storage_by_creator"))
+        .await?;
+        Ok(usage)
+    }
+}
+
+async fn fetch(executor: impl PgExecutor<'_>, id: i32) -> RepoResult<Asset> {
+    let asset = sqlx::query_as!(
+        Asset,
+        r#"SELECT a.id, a.name, a.type AS "asset_type: AssetType", a.mime_type, a.size_bytes, a.hash AS "hash!", a.url,
+            v.urls AS "variants?: ImageVariants"
+        FROM assets a LEFT JOIN image_variants v ON v.asset_id = a.id
+        WHERE a.id = $1"#,
+        id
+    )
+    .fetch_one(executor)
+    .instrument(query_span("assets.get"))
+    .await?;
+    Ok(asset)
+}
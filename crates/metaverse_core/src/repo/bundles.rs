@@ -0,0 +1,371 @@
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use tracing::Instrument;
+
+use super::{RepoError, RepoResult, query_span};
+use crate::bundle::{
+    BundleMap, BundleMapElement, BundleSpace, BundleSpaceElement, BundleTemplate, BundleWorld,
+    Conflict, FORMAT, ImportOptions, ImportReport, WorldBundle,
+};
+use crate::element::ElementType;
+
+/// Copies whole worlds in and out of the database; see [`crate::bundle`].
+#[async_trait]
+pub trait BundleRepo: Send + Sync {
+    /// Portals leading to spaces outside the world are exported without a
+    /// target.
+    async fn export(&self, world_id: i32) -> RepoResult<WorldBundle>;
+    /// Creates the bundle's world, owned by `creator_id`, in one transaction.
+    /// It is only committed if `options` allow it; see [`crate::bundle`]. The
+    /// bundle must have passed [`WorldBundle::validate`].
+    async fn import(
+        &self,
+        bundle: &WorldBundle,
+        creator_id: i32,
+        options: ImportOptions,
+    ) -> RepoResult<ImportReport>;
+    /// Adds the bundle's maps, spaces and elements to an existing world, in
+    /// one transaction and on the same terms as [`BundleRepo::import`].
+    /// `bundle.world` is ignored.
+    async fn import_maps(
+        &self,
+        world_id: i32,
+        bundle: &WorldBundle,
+        options: ImportOptions,
+    ) -> RepoResult<ImportReport>;
+}
+
+pub struct PgBundleRepo {
+    pool: PgPool,
+}
+
+impl PgBundleRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl BundleRepo for PgBundleRepo {
+    async fn export(&self, world_id: i32) -> RepoResult<WorldBundle> {
+        let mut tx = self.pool.begin().await?;
+        let world = sqlx::query_as!(
+            BundleWorld,
+            "SELECT name, description, thumbnail_url, is_public FROM worlds WHERE id = $1",
+            world_id
+        )
+        .fetch_one(&mut *tx)
+        .instrument(query_span("bundles.export.world"))
+        .await?;
+        let maps = sqlx::query_as!(
+            BundleMap,
+            "SELECT id AS key, name, width, height, background_url FROM maps WHERE world_id = $1 ORDER BY id",
+            world_id
+        )
+        .fetch_all(&mut *tx)
+        .instrument(query_span("bundles.export.maps"))
+        .await?;
+        let spaces = sqlx::query_as!(
+            BundleSpace,
+            "SELECT s.id AS key, s.map_id AS map, s.name, s.description, s.width, s.height, s.background_url, s.thumbnail_url, s.max_occupancy, s.is_private, s.default_spawn_x, s.default_spawn_y
+            FROM spaces s JOIN maps m ON m.id = s.map_id
+            WHERE m.world_id = $1 ORDER BY s.id",
+            world_id
+        )
+        .fetch_all(&mut *tx)
+        .instrument(query_span("bundles.export.spaces"))
+        .await?;
+        let map_elements = sqlx::query_as!(
+            BundleMapElement,
+            r#"SELECT e.map_id AS map, r.id AS template, e.x, e.y, e.z_index,
+                (SELECT t.id FROM spaces t JOIN maps tm ON tm.id = t.map_id
+                 WHERE t.id = e.target_space_id AND tm.world_id = $1) AS "target_space?",
+                e.custom_properties
+            FROM map_elements e JOIN maps m ON m.id = e.map_id
+            JOIN element_template_revisions r
+                ON r.template_id = e.template_id AND r.revision = e.template_revision
+            WHERE m.world_id = $1 ORDER BY e.id"#,
+            world_id
+        )
+        .fetch_all(&mut *tx)
+        .instrument(query_span("bundles.export.map_elements"))
+        .await?;
+        let space_elements = sqlx::query_as!(
+            BundleSpaceElement,
+            "SELECT e.space_id AS space, r.id AS template, e.x, e.y, e.z_index, e.rotation, e.custom_properties
+            FROM space_elements e JOIN spaces s ON s.id = e.space_id JOIN maps m ON m.id = s.map_id
+            JOIN element_template_revisions r
+                ON r.template_id = e.template_id AND r.revision = e.template_revision
+            WHERE m.world_id = $1 ORDER BY e.id",
+            world_id
+        )
+        .fetch_all(&mut *tx)
+        .instrument(query_span("bundles.export.space_elements"))
+        .await?;
+        // Elements are exported with the template revision they pinned.
+        let template_ids: Vec<i32> = map_elements
+            .iter()
+            .map(|e| e.template)
+            .chain(space_elements.iter().map(|e| e.template))
+            .collect();
+        let templates = sqlx::query_as!(
+            BundleTemplate,
+            r#"SELECT id AS key, name, type AS "element_type: ElementType", image_url, model_url, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema
+            FROM element_template_revisions WHERE id = ANY($1) ORDER BY id"#,
+            &template_ids
+        )
+        .fetch_all(&mut *tx)
+        .instrument(query_span("bundles.export.templates"))
+        .await?;
+        tx.commit().await?;
+
+        Ok(WorldBundle {
+            format: FORMAT,
+            world,
+            templates,
+            maps,
+            spaces,
+            map_elements,
+            space_elements,
+        })
+    }
+
+    async fn import(
+        &self,
+        bundle: &WorldBundle,
+        creator_id: i32,
+        options: ImportOptions,
+    ) -> RepoResult<ImportReport> {
+        let mut report = ImportReport::default();
+        let mut tx = self.pool.begin().await?;
+        let world = &bundle.world;
+        let same_name = sqlx::query_scalar!(
+            "SELECT id FROM worlds WHERE name = $1 ORDER BY id LIMIT 1",
+            world.name
+        )
+        .fetch_optional(&mut *tx)
+        .instrument(query_span("bundles.import.same_name"))
+        .await?;
+        if let Some(world_id) = same_name {
+            report.conflicts.push(Conflict::WorldExists {
+                name: world.name.clone(),
+                world_id,
+            });
+        }
+        let world_id = sqlx::query_scalar!(
+            "INSERT INTO worlds (name, description, thumbnail_url, creator_id, is_public) VALUES ($1, $2, $3, $4, COALESCE($5, TRUE)) RETURNING id",
+            world.name,
+            world.description,
+            world.thumbnail_url,
+            creator_id,
+            world.is_public
+        )
+        .fetch_one(&mut *tx)
+        .instrument(query_span("bundles.import.world"))
+        .await?;
+        insert_contents(&mut tx, world_id, bundle, &mut report).await?;
+        finish(tx, world_id, options, report).await
+    }
+
+    async fn import_maps(
+        &self,
+        world_id: i32,
+        bundle: &WorldBundle,
+        options: ImportOptions,
+    ) -> RepoResult<ImportReport> {
+        let mut report = ImportReport::default();
+        let mut tx = self.pool.begin().await?;
+        let existing = sqlx::query!(
+            r#"SELECT m.id AS "id?", m.name AS "name?" FROM worlds w LEFT JOIN maps m ON m.world_id = w.id WHERE w.id = $1 ORDER BY m.id"#,
+            world_id
+        )
+        .fetch_all(&mut *tx)
+        .instrument(query_span("bundles.import_maps.existing_maps"))
+        .await?;
+        if existing.is_empty() {
+            return Err(RepoError::NotFound);
+        }
+        for map in &bundle.maps {
+            let same_name = existing.iter().find(|m| m.name.as_ref() == Some(&map.name));
+            if let Some(map_id) = same_name.and_then(|m| m.id) {
+                report.conflicts.push(Conflict::MapExists {
+                    name: map.name.clone(),
+                    map_id,
+                });
+            }
+        }
+        insert_contents(&mut tx, world_id, bundle, &mut report).await?;
+        finish(tx, world_id, options, report).await
+    }
+}
+
+/// Inserts everything in `bundle` but the world itself into `world_id`.
+async fn insert_contents(
+    tx: &mut Transaction<'_, Postgres>,
+    world_id: i32,
+    bundle: &WorldBundle,
+    report: &mut ImportReport,
+) -> RepoResult<()> {
+    // Validation guarantees every key resolves; this only guards against
+    // callers that skipped it.
+    fn id(ids: &HashMap<i32, i32>, key: i32) -> RepoResult<i32> {
+        ids.get(&key).copied().ok_or(RepoError::NotFound)
+    }
+
+    // Existing templates that might match, by hash; names rarely change
+    // without the content changing too.
+    let names: Vec<String> = bundle.templates.iter().map(|t| t.name.clone()).collect();
+    let existing = sqlx::query_as!(
+        BundleTemplate,
+        r#"SELECT id AS key, name, type AS "element_type: ElementType", image_url, model_url, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema
+        FROM element_templates WHERE name = ANY($1) ORDER BY id"#,
+        &names
+    )
+    .fetch_all(&mut **tx)
+    .instrument(query_span("bundles.import.existing_templates"))
+    .await?;
+    let mut by_hash: HashMap<String, i32> = HashMap::new();
+    for template in &existing {
+        by_hash
+            .entry(template.content_hash())
+            .or_insert(template.key);
+    }
+
+    let mut templates = HashMap::new();
+    for template in &bundle.templates {
+        let hash = template.content_hash();
+        if let Some(&template_id) = by_hash.get(&hash) {
+            templates.insert(template.key, template_id);
+            report.templates_reused.push((template.key, template_id));
+            continue;
+        }
+        if let Some(other) = existing.iter().find(|t| t.name == template.name) {
+            report.conflicts.push(Conflict::TemplateDiffers {
+                key: template.key,
+                name: template.name.clone(),
+                existing_id: other.key,
+            });
+        }
+        let template_id = sqlx::query_scalar!(
+            "INSERT INTO element_templates (name, type, image_url, model_url, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema) VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, FALSE), $8, $9, $10, $11) RETURNING id",
+            template.name,
+            template.element_type as ElementType,
+            template.image_url,
+            template.model_url,
+            template.width,
+            template.height,
+            template.is_collidable,
+            template.interaction_data,
+            template.physics_properties,
+            template.animation_data,
+            template.properties_schema
+        )
+        .fetch_one(&mut **tx)
+        .instrument(query_span("bundles.import.template"))
+        .await?;
+        templates.insert(template.key, template_id);
+        // Duplicates within the bundle share the first copy.
+        by_hash.insert(hash, template_id);
+        report.templates_created += 1;
+    }
+
+    let mut maps = HashMap::new();
+    for map in &bundle.maps {
+        let map_id = sqlx::query_scalar!(
+            "INSERT INTO maps (world_id, name, width, height, background_url) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            world_id,
+            map.name,
+            map.width,
+            map.height,
+            map.background_url
+        )
+        .fetch_one(&mut **tx)
+        .instrument(query_span("bundles.import.map"))
+        .await?;
+        maps.insert(map.key, map_id);
+    }
+    report.maps = maps.len();
+
+    let mut spaces = HashMap::new();
+    for space in &bundle.spaces {
+        let space_id = sqlx::query_scalar!(
+            "INSERT INTO spaces (map_id, name, description, width, height, background_url, thumbnail_url, max_occupancy, is_private, default_spawn_x, default_spawn_y) VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, 0), COALESCE($9, FALSE), $10, $11) RETURNING id",
+            id(&maps, space.map)?,
+            space.name,
+            space.description,
+            space.width,
+            space.height,
+            space.background_url,
+            space.thumbnail_url,
+            space.max_occupancy,
+            space.is_private,
+            space.default_spawn_x,
+            space.default_spawn_y
+        )
+        .fetch_one(&mut **tx)
+        .instrument(query_span("bundles.import.space"))
+        .await?;
+        spaces.insert(space.key, space_id);
+    }
+    report.spaces = spaces.len();
+
+    for element in &bundle.space_elements {
+        sqlx::query!(
+            "INSERT INTO space_elements (space_id, template_id, x, y, z_index, rotation, custom_properties) VALUES ($1, $2, $3, $4, COALESCE($5, 0), COALESCE($6, 0), $7)",
+            id(&spaces, element.space)?,
+            id(&templates, element.template)?,
+            element.x,
+            element.y,
+            element.z_index,
+            element.rotation,
+            element.custom_properties
+        )
+        .execute(&mut **tx)
+        .instrument(query_span("bundles.import.space_element"))
+        .await?;
+    }
+    report.space_elements = bundle.space_elements.len();
+
+    // After every space exists, since portals may lead to another map.
+    for element in &bundle.map_elements {
+        let target_space_id = element
+            .target_space
+            .map(|key| id(&spaces, key))
+            .transpose()?;
+        sqlx::query!(
+            "INSERT INTO map_elements (map_id, template_id, x, y, z_index, target_space_id, custom_properties) VALUES ($1, $2, $3, $4, COALESCE($5, 0), $6, $7)",
+            id(&maps, element.map)?,
+            id(&templates, element.template)?,
+            element.x,
+            element.y,
+            element.z_index,
+            target_space_id,
+            element.custom_properties
+        )
+        .execute(&mut **tx)
+        .instrument(query_span("bundles.import.map_element"))
+        .await?;
+    }
+    report.map_elements = bundle.map_elements.len();
+
+    Ok(())
+}
+
+/// Commits unless `options` say otherwise; see [`crate::bundle`].
+async fn finish(
+    tx: Transaction<'_, Postgres>,
+    world_id: i32,
+    options: ImportOptions,
+    mut report: ImportReport,
+) -> RepoResult<ImportReport> {
+    // Everything ran, so a dry run also catches what the database itself
+    // would refuse.
+    if options.dry_run || (!report.conflicts.is_empty() && !options.allow_conflicts) {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        report.world_id = Some(world_id);
+    }
+    Ok(report)
+}
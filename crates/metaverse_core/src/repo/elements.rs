@@ -0,0 +1,327 @@
+use async_trait::async_trait;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use tracing::Instrument;
+
+use super::{RepoError, RepoResult, query_span};
+use crate::element::{
+    CreateElementTemplatePayload, CreateMapElementsPayload, CreateSpaceElementsPayload,
+    ElementType, PinRevisionPayload, PinnedElement, PlacedElement, RevisionPreview,
+};
+use crate::realtime::ElementState;
+use crate::schema;
+
+#[async_trait]
+pub trait ElementRepo: Send + Sync {
+    async fn create_template(&self, template: &CreateElementTemplatePayload) -> RepoResult<i32>;
+    /// Saves a new revision of the template, returning its number. Placed
+    /// elements stay on the revision they pinned.
+    async fn update_template(
+        &self,
+        template_id: i32,
+        template: &CreateElementTemplatePayload,
+    ) -> RepoResult<i32>;
+    /// Creates every template, or none if any fails.
+    async fn create_templates(
+        &self,
+        templates: &[CreateElementTemplatePayload],
+    ) -> RepoResult<Vec<i32>>;
+    /// What [`ElementRepo::pin_revision`] would move. `NotFound` if the
+    /// template has no such revision.
+    async fn preview_revision(&self, pin: &PinRevisionPayload) -> RepoResult<RevisionPreview>;
+    /// Moves the elements in `pin`'s space or on its map to its revision, all
+    /// or none. `Conflict` if any of their properties don't fit it.
+    async fn pin_revision(&self, pin: &PinRevisionPayload) -> RepoResult<RevisionPreview>;
+    async fn create_space_element(&self, element: &CreateSpaceElementsPayload) -> RepoResult<i32>;
+    async fn create_map_element(&self, element: &CreateMapElementsPayload) -> RepoResult<i32>;
+    /// The JSON Schema a template's instances' `custom_properties` must
+    /// match, if it declares one.
+    async fn properties_schema(&self, template_id: i32) -> RepoResult<Option<serde_json::Value>>;
+    async fn space_element(&self, element_id: i32) -> RepoResult<PlacedElement>;
+    /// Elements in the space whose state has been changed, by id.
+    async fn element_states(&self, space_id: i32) -> RepoResult<Vec<ElementState>>;
+    /// Replaces an element's state if it is still at `version`, returning the
+    /// new version. `Conflict` if someone else changed it first.
+    async fn set_element_state(
+        &self,
+        element_id: i32,
+        version: i32,
+        state: &serde_json::Value,
+    ) -> RepoResult<i32>;
+}
+
+pub struct PgElementRepo {
+    pool: PgPool,
+}
+
+impl PgElementRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ElementRepo for PgElementRepo {
+    async fn create_template(&self, template: &CreateElementTemplatePayload) -> RepoResult<i32> {
+        let mut conn = self.pool.acquire().await?;
+        insert_template(&mut conn, template).await
+    }
+
+    async fn update_template(
+        &self,
+        template_id: i32,
+        template: &CreateElementTemplatePayload,
+    ) -> RepoResult<i32> {
+        // A trigger bumps the revision and records it.
+        let revision = sqlx::query_scalar!(
+            "UPDATE element_templates SET name = $2, type = $3, image_url = $4, model_url = $5, image_asset_id = $6, model_asset_id = $7, width = $8, height = $9, is_collidable = $10, interaction_data = $11, physics_properties = $12, animation_data = $13, properties_schema = $14 WHERE id = $1 RETURNING revision",
+            template_id,
+            template.name,
+            template.element_type as ElementType,
+            template.image_url,
+            template.model_url,
+            template.image_asset_id,
+            template.model_asset_id,
+            template.width,
+            template.height,
+            template.is_collidable,
+            template.interaction_data,
+            template.physics_properties,
+            template.animation_data,
+            template.properties_schema
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("elements.update_template"))
+        .await?;
+        Ok(revision)
+    }
+
+    async fn create_templates(
+        &self,
+        templates: &[CreateElementTemplatePayload],
+    ) -> RepoResult<Vec<i32>> {
+        let mut tx = self.pool.begin().await?;
+        let mut ids = Vec::with_capacity(templates.len());
+        for template in templates {
+            ids.push(insert_template(&mut tx, template).await?);
+        }
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    async fn preview_revision(&self, pin: &PinRevisionPayload) -> RepoResult<RevisionPreview> {
+        let mut tx = self.pool.begin().await?;
+        preview(&mut tx, pin).await
+    }
+
+    async fn pin_revision(&self, pin: &PinRevisionPayload) -> RepoResult<RevisionPreview> {
+        let mut tx = self.pool.begin().await?;
+        let preview = preview(&mut tx, pin).await?;
+        if preview
+            .elements
+            .iter()
+            .any(|element| !element.problems.is_empty())
+        {
+            return Err(RepoError::Conflict);
+        }
+        if let Some(space_id) = pin.space_id {
+            sqlx::query!(
+                "UPDATE space_elements SET template_revision = $2 WHERE template_id = $1 AND space_id = $3",
+                pin.template_id,
+                pin.revision,
+                space_id
+            )
+            .execute(&mut *tx)
+            .instrument(query_span("elements.pin_revision.space"))
+            .await?;
+        }
+        if let Some(map_id) = pin.map_id {
+            sqlx::query!(
+                "UPDATE map_elements SET template_revision = $2 WHERE template_id = $1 AND map_id = $3",
+                pin.template_id,
+                pin.revision,
+                map_id
+            )
+            .execute(&mut *tx)
+            .instrument(query_span("elements.pin_revision.map"))
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(preview)
+    }
+
+    async fn create_space_element(&self, element: &CreateSpaceElementsPayload) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO space_elements (space_id, template_id, x, y, z_index, rotation, custom_properties) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+            element.space_id,
+            element.template_id,
+            element.x,
+            element.y,
+            element.z_index,
+            element.rotation,
+            element.custom_properties
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("elements.create_space_element"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn create_map_element(&self, element: &CreateMapElementsPayload) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO map_elements (map_id, template_id, x, y, z_index, target_space_id, custom_properties) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+            element.map_id,
+            element.template_id,
+            element.x,
+            element.y,
+            element.z_index,
+            element.target_space_id,
+            element.custom_properties
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("elements.create_map_element"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn properties_schema(&self, template_id: i32) -> RepoResult<Option<serde_json::Value>> {
+        let schema = sqlx::query_scalar!(
+            "SELECT properties_schema FROM element_templates WHERE id = $1",
+            template_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("elements.properties_schema"))
+        .await?;
+        Ok(schema)
+    }
+
+    async fn space_element(&self, element_id: i32) -> RepoResult<PlacedElement> {
+        let element = sqlx::query_as!(
+            PlacedElement,
+            r#"SELECT e.id, e.space_id, e.x, e.y, t.width, t.height,
+                t.type AS "element_type: ElementType", t.interaction_data,
+                t.properties_schema, e.state, e.state_version
+            FROM space_elements e JOIN element_template_revisions t
+                ON t.template_id = e.template_id AND t.revision = e.template_revision
+            WHERE e.id = $1"#,
+            element_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("elements.space_element"))
+        .await?;
+        Ok(element)
+    }
+
+    async fn element_states(&self, space_id: i32) -> RepoResult<Vec<ElementState>> {
+        let states = sqlx::query_as!(
+            ElementState,
+            r#"SELECT id AS element_id, state AS "state!", state_version AS version
+            FROM space_elements WHERE space_id = $1 AND state IS NOT NULL ORDER BY id"#,
+            space_id
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("elements.element_states"))
+        .await?;
+        Ok(states)
+    }
+
+    async fn set_element_state(
+        &self,
+        element_id: i32,
+        version: i32,
+        state: &serde_json::Value,
+    ) -> RepoResult<i32> {
+        sqlx::query_scalar!(
+            "UPDATE space_elements SET state = $3, state_version = state_version + 1 WHERE id = $1 AND state_version = $2 RETURNING state_version",
+            element_id,
+            version,
+            state
+        )
+        .fetch_optional(&self.pool)
+        .instrument(query_span("elements.set_element_state"))
+        .await?
+        .ok_or(RepoError::Conflict)
+    }
+}
+
+/// The elements `pin` would move.
+async fn preview(
+    tx: &mut Transaction<'_, Postgres>,
+    pin: &PinRevisionPayload,
+) -> RepoResult<RevisionPreview> {
+    let properties_schema = sqlx::query_scalar!(
+        "SELECT properties_schema FROM element_template_revisions WHERE template_id = $1 AND revision = $2",
+        pin.template_id,
+        pin.revision
+    )
+    .fetch_one(&mut **tx)
+    .instrument(query_span("elements.preview.revision"))
+    .await?;
+    let rows = sqlx::query!(
+        r#"SELECT id AS "element_id!", space_id, NULL::integer AS map_id, template_revision AS "revision!", custom_properties
+        FROM space_elements
+        WHERE template_id = $1 AND template_revision <> $2
+            AND ($3::integer IS NULL OR space_id = $3) AND $4::integer IS NULL
+        UNION ALL
+        SELECT id, NULL, map_id, template_revision, custom_properties
+        FROM map_elements
+        WHERE template_id = $1 AND template_revision <> $2
+            AND ($4::integer IS NULL OR map_id = $4) AND $3::integer IS NULL
+        ORDER BY 1"#,
+        pin.template_id,
+        pin.revision,
+        pin.space_id,
+        pin.map_id
+    )
+    .fetch_all(&mut **tx)
+    .instrument(query_span("elements.preview.elements"))
+    .await?;
+    let elements = rows
+        .into_iter()
+        .map(|row| PinnedElement {
+            element_id: row.element_id,
+            space_id: row.space_id,
+            map_id: row.map_id,
+            revision: row.revision,
+            problems: schema::check_properties(
+                properties_schema.as_ref(),
+                row.custom_properties
+                    .as_ref()
+                    .unwrap_or(&serde_json::Value::Null),
+            )
+            .err()
+            .map(|err| err.problems)
+            .unwrap_or_default(),
+        })
+        .collect();
+    Ok(RevisionPreview {
+        template_id: pin.template_id,
+        revision: pin.revision,
+        elements,
+    })
+}
+
+async fn insert_template(
+    conn: &mut PgConnection,
+    template: &CreateElementTemplatePayload,
+) -> RepoResult<i32> {
+    let id = sqlx::query_scalar!(
+        "INSERT INTO element_templates (name, type, image_url, model_url, image_asset_id, model_asset_id, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id",
+        template.name,
+        template.element_type as ElementType,
+        template.image_url,
+        template.model_url,
+        template.image_asset_id,
+        template.model_asset_id,
+        template.width,
+        template.height,
+        template.is_collidable,
+        template.interaction_data,
+        template.physics_properties,
+        template.animation_data,
+        template.properties_schema
+    )
+    .fetch_one(&mut *conn)
+    .instrument(query_span("elements.create_template"))
+    .await?;
+    Ok(id)
+}
@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tracing::Instrument;
+
+use super::{RepoResult, query_span};
+use crate::db::{MigrationState, migration_status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Open connections, idle or in use.
+    pub size: u32,
+    pub idle: u32,
+    pub max: u32,
+}
+
+/// Checks used by the readiness probe and metrics.
+#[async_trait]
+pub trait HealthRepo: Send + Sync {
+    async fn ping(&self) -> RepoResult<()>;
+    /// Versions of embedded migrations the database hasn't applied, or has
+    /// applied with different contents.
+    async fn pending_migrations(&self) -> RepoResult<Vec<i64>>;
+    fn pool_stats(&self) -> PoolStats;
+}
+
+pub struct PgHealthRepo {
+    pool: PgPool,
+}
+
+impl PgHealthRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HealthRepo for PgHealthRepo {
+    async fn ping(&self) -> RepoResult<()> {
+        sqlx::query!("SELECT 1 AS ok")
+            .fetch_one(&self.pool)
+            .instrument(query_span("health.ping"))
+            .await?;
+        Ok(())
+    }
+
+    async fn pending_migrations(&self) -> RepoResult<Vec<i64>> {
+        let mut conn = self.pool.acquire().await?;
+        Ok(migration_status(&mut conn)
+            .await?
+            .into_iter()
+            .filter(|m| matches!(m.state, MigrationState::Pending | MigrationState::Changed))
+            .map(|m| m.version)
+            .collect())
+    }
+
+    fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max: self.pool.options().get_max_connections(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tracing::Instrument;
+
+use super::{RepoResult, query_span};
+use crate::maps::{CreateMapPayload, GetMapResponse};
+
+#[async_trait]
+pub trait MapRepo: Send + Sync {
+    async fn create(&self, map: &CreateMapPayload) -> RepoResult<i32>;
+    async fn get(&self, map_id: i32) -> RepoResult<GetMapResponse>;
+}
+
+pub struct PgMapRepo {
+    pool: PgPool,
+}
+
+impl PgMapRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl MapRepo for PgMapRepo {
+    async fn create(&self, map: &CreateMapPayload) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO maps (world_id, name, width, height, background_url, background_asset_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            map.world_id,
+            map.name,
+            map.width,
+            map.height,
+            map.background_url,
+            map.background_asset_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("maps.create"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn get(&self, map_id: i32) -> RepoResult<GetMapResponse> {
+        let map = sqlx::query_as!(
+            GetMapResponse,
+            "SELECT id AS map_id, world_id, name, width, height, background_url FROM maps WHERE id = $1",
+            map_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("maps.get"))
+        .await?;
+        Ok(map)
+    }
+}
@@ -0,0 +1,68 @@
+//! Data access for the API's tables.
+//!
+//! Handlers talk to the traits so they can be exercised against in-memory
+//! fakes; the `Pg*` implementations are what the server runs with and use
+//! compile-time checked queries.
+
+mod admin;
+mod assets;
+mod bundles;
+mod elements;
+mod health;
+mod maps;
+mod sessions;
+mod spaces;
+mod users;
+mod worlds;
+
+pub use admin::{AdminRepo, PgAdminRepo, SessionSummary, UserSummary};
+pub use assets::{AssetRepo, NewAsset, NewVariant, PgAssetRepo};
+pub use bundles::{BundleRepo, PgBundleRepo};
+pub use elements::{ElementRepo, PgElementRepo};
+pub use health::{HealthRepo, PgHealthRepo, PoolStats};
+pub use maps::{MapRepo, PgMapRepo};
+pub use sessions::{PgSessionRepo, SessionPosition, SessionRepo};
+pub use spaces::{PgSpaceRepo, SpaceRepo};
+pub use users::{NewUser, PgUserRepo, UserAccess, UserCredentials, UserRepo};
+pub use worlds::{PgWorldRepo, WorldRepo};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepoError {
+    #[error("row not found")]
+    NotFound,
+    /// A unique or foreign key constraint rejected the write, or it was based
+    /// on a version that has since changed.
+    #[error("write conflicts with existing rows")]
+    Conflict,
+    #[error(transparent)]
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RepoError {
+    fn from(error: sqlx::Error) -> Self {
+        match &error {
+            sqlx::Error::RowNotFound => RepoError::NotFound,
+            sqlx::Error::Database(db)
+                if db.is_unique_violation() || db.is_foreign_key_violation() =>
+            {
+                RepoError::Conflict
+            }
+            _ => RepoError::Database(error),
+        }
+    }
+}
+
+pub type RepoResult<T> = Result<T, RepoError>;
+
+/// Span around a single query, named after the statement it runs (e.g.
+/// `worlds.list`). Bound values are never recorded, so traces can't leak
+/// passwords or personal data.
+pub(crate) fn query_span(statement: &'static str) -> tracing::Span {
+    tracing::info_span!(
+        "db.query",
+        otel.name = statement,
+        otel.kind = "client",
+        db.system = "postgresql",
+        db.statement.name = statement,
+    )
+}
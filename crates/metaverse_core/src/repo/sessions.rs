@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tracing::Instrument;
+
+use super::{RepoResult, query_span};
+
+/// Latest known position of an open session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionPosition {
+    pub session_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub rotation: i32,
+}
+
+/// `user_sessions` rows for realtime connections. Saving a position also
+/// updates the user's `last_*` columns through the `sync_user_position`
+/// trigger.
+#[async_trait]
+pub trait SessionRepo: Send + Sync {
+    /// Starts an active session in a space and marks the user online.
+    async fn open(&self, user_id: i32, space_id: i32, x: i32, y: i32) -> RepoResult<i32>;
+    async fn save_positions(&self, positions: &[SessionPosition]) -> RepoResult<()>;
+    /// Marks sessions inactive. Users left without an active session go offline.
+    async fn close(&self, session_ids: &[i32]) -> RepoResult<()>;
+    /// Those of `session_ids` that are no longer active, e.g. because
+    /// `metaverse-admin sessions kill` closed them from outside the server.
+    async fn closed(&self, session_ids: &[i32]) -> RepoResult<Vec<i32>>;
+}
+
+pub struct PgSessionRepo {
+    pool: PgPool,
+}
+
+impl PgSessionRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SessionRepo for PgSessionRepo {
+    async fn open(&self, user_id: i32, space_id: i32, x: i32, y: i32) -> RepoResult<i32> {
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query_scalar!(
+            "INSERT INTO user_sessions (user_id, space_id, x, y, status) VALUES ($1, $2, $3, $4, 'Active') RETURNING id",
+            user_id,
+            space_id,
+            x,
+            y
+        )
+        .fetch_one(&mut *tx)
+        .instrument(query_span("sessions.open.insert"))
+        .await?;
+        sqlx::query!("UPDATE users SET is_online = TRUE WHERE id = $1", user_id)
+            .execute(&mut *tx)
+            .instrument(query_span("sessions.open.set_online"))
+            .await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    async fn save_positions(&self, positions: &[SessionPosition]) -> RepoResult<()> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<i32> = positions.iter().map(|p| p.session_id).collect();
+        let xs: Vec<i32> = positions.iter().map(|p| p.x).collect();
+        let ys: Vec<i32> = positions.iter().map(|p| p.y).collect();
+        let rotations: Vec<i32> = positions.iter().map(|p| p.rotation).collect();
+        sqlx::query!(
+            "UPDATE user_sessions AS s
+            SET x = p.x, y = p.y, rotation = p.rotation, last_activity = CURRENT_TIMESTAMP
+            FROM UNNEST($1::int4[], $2::int4[], $3::int4[], $4::int4[]) AS p(id, x, y, rotation)
+            WHERE s.id = p.id",
+            &ids,
+            &xs,
+            &ys,
+            &rotations
+        )
+        .execute(&self.pool)
+        .instrument(query_span("sessions.save_positions"))
+        .await?;
+        Ok(())
+    }
+
+    async fn close(&self, session_ids: &[i32]) -> RepoResult<()> {
+        if session_ids.is_empty() {
+            return Ok(());
+        }
+        // The outer UPDATE still sees the closed sessions as active, hence the
+        // explicit exclusion.
+        sqlx::query!(
+            "WITH closed AS (
+                UPDATE user_sessions SET status = 'Inactive', last_activity = CURRENT_TIMESTAMP
+                WHERE id = ANY($1) RETURNING user_id
+            )
+            UPDATE users SET is_online = FALSE
+            WHERE id IN (SELECT user_id FROM closed)
+            AND NOT EXISTS (
+                SELECT 1 FROM user_sessions s
+                WHERE s.user_id = users.id AND s.status = 'Active' AND s.id <> ALL($1)
+            )",
+            session_ids
+        )
+        .execute(&self.pool)
+        .instrument(query_span("sessions.close"))
+        .await?;
+        Ok(())
+    }
+
+    async fn closed(&self, session_ids: &[i32]) -> RepoResult<Vec<i32>> {
+        if session_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let closed = sqlx::query_scalar!(
+            "SELECT id FROM user_sessions WHERE id = ANY($1) AND status IS DISTINCT FROM 'Active'",
+            session_ids
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("sessions.closed"))
+        .await?;
+        Ok(closed)
+    }
+}
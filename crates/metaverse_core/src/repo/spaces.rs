@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tracing::Instrument;
+
+use super::{RepoError, RepoResult, query_span};
+use crate::asset::ImageVariants;
+use crate::space::{CreateSpacePayload, GetSpaceResponse};
+
+#[async_trait]
+pub trait SpaceRepo: Send + Sync {
+    async fn create(&self, space: &CreateSpacePayload) -> RepoResult<i32>;
+    async fn get(&self, space_id: i32) -> RepoResult<GetSpaceResponse>;
+    /// Removes the space along with the elements placed in it.
+    async fn delete(&self, space_id: i32) -> RepoResult<()>;
+}
+
+pub struct PgSpaceRepo {
+    pool: PgPool,
+}
+
+impl PgSpaceRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SpaceRepo for PgSpaceRepo {
+    async fn create(&self, space: &CreateSpacePayload) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO spaces (map_id, name, description, width, height, background_url, thumbnail_url, background_asset_id, thumbnail_asset_id, max_occupancy, is_private, default_spawn_x, default_spawn_y) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id",
+            space.map_id,
+            space.name,
+            space.description,
+            space.width,
+            space.height,
+            space.background_url,
+            space.thumbnail_url,
+            space.background_asset_id,
+            space.thumbnail_asset_id,
+            space.max_occupancy,
+            space.is_private,
+            space.default_spawn_x,
+            space.default_spawn_y
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("spaces.create"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn get(&self, space_id: i32) -> RepoResult<GetSpaceResponse> {
+        let space = sqlx::query_as!(
+            GetSpaceResponse,
+            r#"SELECT s.map_id, s.name, s.description, s.width, s.height, s.background_url, s.thumbnail_url,
+                v.urls AS "thumbnail_variants?: ImageVariants",
+                COALESCE(s.max_occupancy, 0) AS "max_occupancy!",
+                COALESCE(s.is_private, FALSE) AS "is_private!",
+                s.default_spawn_x, s.default_spawn_y
+            FROM spaces s LEFT JOIN image_variants v ON v.asset_id = s.thumbnail_asset_id
+            WHERE s.id = $1"#,
+            space_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("spaces.get"))
+        .await?;
+        Ok(space)
+    }
+
+    async fn delete(&self, space_id: i32) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM space_elements WHERE space_id = $1", space_id)
+            .execute(&mut *tx)
+            .instrument(query_span("spaces.delete.elements"))
+            .await?;
+        let deleted = sqlx::query!("DELETE FROM spaces WHERE id = $1", space_id)
+            .execute(&mut *tx)
+            .instrument(query_span("spaces.delete.space"))
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
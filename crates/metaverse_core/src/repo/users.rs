@@ -0,0 +1,269 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::Instrument;
+
+use super::{RepoResult, query_span};
+use crate::asset::ImageVariants;
+use crate::avatar::{
+    AvatarComposition, AvatarLayer, AvatarPart, AvatarSlot, CreateAvatarPartPayload, WornPart,
+};
+use crate::common::Role;
+use crate::user::{AvatarPayload, CreateAvatarPayload, UserMetaDataResponsePayload};
+
+pub struct NewUser {
+    pub username: String,
+    pub email: String,
+    pub password_hash: String,
+    pub avatar_id: Option<i32>,
+    pub role: Role,
+}
+
+pub struct UserCredentials {
+    pub id: i32,
+    pub password_hash: String,
+    pub role: Role,
+    pub disabled: bool,
+}
+
+/// What a signed-in user may currently do, read on every request so that
+/// disabling or demoting someone takes effect before their token expires.
+pub struct UserAccess {
+    pub role: Role,
+    pub disabled: bool,
+}
+
+#[async_trait]
+pub trait UserRepo: Send + Sync {
+    async fn create(&self, user: NewUser) -> RepoResult<i32>;
+    async fn credentials(&self, username: &str) -> RepoResult<UserCredentials>;
+    async fn access(&self, user_id: i32) -> RepoResult<UserAccess>;
+    async fn record_login(&self, user_id: i32) -> RepoResult<()>;
+    async fn set_avatar(&self, user_id: i32, avatar_id: i32) -> RepoResult<()>;
+    async fn create_avatar(&self, avatar: &CreateAvatarPayload) -> RepoResult<i32>;
+    async fn avatars(&self) -> RepoResult<Vec<AvatarPayload>>;
+    /// Avatar image per user, ordered by user id. Users without an avatar
+    /// are returned with no image.
+    async fn avatar_images(&self, user_ids: &[i32])
+    -> RepoResult<Vec<UserMetaDataResponsePayload>>;
+    async fn create_avatar_part(&self, part: &CreateAvatarPartPayload) -> RepoResult<i32>;
+    async fn avatar_parts(&self) -> RepoResult<Vec<AvatarPart>>;
+    /// Replaces what the user wears, in the order given. Fails with
+    /// `Conflict` if a part doesn't exist.
+    async fn set_avatar_parts(&self, user_id: i32, parts: &[WornPart]) -> RepoResult<()>;
+    /// The composition of every user in `user_ids` who has one.
+    async fn avatar_compositions(
+        &self,
+        user_ids: &[i32],
+    ) -> RepoResult<HashMap<i32, AvatarComposition>>;
+}
+
+pub struct PgUserRepo {
+    pool: PgPool,
+}
+
+impl PgUserRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepo for PgUserRepo {
+    async fn create(&self, user: NewUser) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO users (username, email, password_hash, avatar_id, role) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            user.username,
+            user.email,
+            user.password_hash,
+            user.avatar_id,
+            user.role as Role,
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("users.create"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn credentials(&self, username: &str) -> RepoResult<UserCredentials> {
+        let credentials = sqlx::query_as!(
+            UserCredentials,
+            r#"SELECT id, password_hash, role AS "role: Role", disabled_at IS NOT NULL AS "disabled!" FROM users WHERE username = $1"#,
+            username
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("users.credentials"))
+        .await?;
+        Ok(credentials)
+    }
+
+    async fn access(&self, user_id: i32) -> RepoResult<UserAccess> {
+        let access = sqlx::query_as!(
+            UserAccess,
+            r#"SELECT role AS "role: Role", disabled_at IS NOT NULL AS "disabled!" FROM users WHERE id = $1"#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("users.access"))
+        .await?;
+        Ok(access)
+    }
+
+    async fn record_login(&self, user_id: i32) -> RepoResult<()> {
+        sqlx::query!(
+            "UPDATE users SET last_login = CURRENT_TIMESTAMP WHERE id = $1",
+            user_id
+        )
+        .execute(&self.pool)
+        .instrument(query_span("users.record_login"))
+        .await?;
+        Ok(())
+    }
+
+    async fn set_avatar(&self, user_id: i32, avatar_id: i32) -> RepoResult<()> {
+        sqlx::query!(
+            "UPDATE users SET avatar_id = $1 WHERE id = $2",
+            avatar_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .instrument(query_span("users.set_avatar"))
+        .await?;
+        Ok(())
+    }
+
+    async fn create_avatar(&self, avatar: &CreateAvatarPayload) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO avatars (name, image_url, image_asset_id) VALUES ($1, $2, $3) RETURNING id",
+            avatar.name,
+            avatar.image_url,
+            avatar.image_asset_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("users.create_avatar"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn avatars(&self) -> RepoResult<Vec<AvatarPayload>> {
+        let avatars = sqlx::query_as!(
+            AvatarPayload,
+            r#"SELECT a.id, a.name, a.image_url AS "image_url?", v.urls AS "image_variants?: ImageVariants"
+            FROM avatars a LEFT JOIN image_variants v ON v.asset_id = a.image_asset_id
+            ORDER BY a.id"#
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("users.avatars"))
+        .await?;
+        Ok(avatars)
+    }
+
+    async fn avatar_images(
+        &self,
+        user_ids: &[i32],
+    ) -> RepoResult<Vec<UserMetaDataResponsePayload>> {
+        let images = sqlx::query!(
+            r#"SELECT u.id, a.image_url AS "image_url?" FROM users u LEFT JOIN avatars a ON u.avatar_id = a.id WHERE u.id = ANY($1) ORDER BY u.id"#,
+            user_ids
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("users.avatar_images"))
+        .await?;
+        Ok(images
+            .into_iter()
+            .map(|row| UserMetaDataResponsePayload {
+                id: row.id,
+                image_url: row.image_url,
+                composition: None,
+            })
+            .collect())
+    }
+
+    async fn create_avatar_part(&self, part: &CreateAvatarPartPayload) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO avatar_parts (slot, name, image_url, image_asset_id) VALUES ($1, $2, $3, $4) RETURNING id",
+            part.slot.as_str(),
+            part.name,
+            part.image_url,
+            part.image_asset_id
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("users.create_avatar_part"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn avatar_parts(&self) -> RepoResult<Vec<AvatarPart>> {
+        let parts = sqlx::query_as!(
+            AvatarPart,
+            r#"SELECT p.id, p.slot AS "slot: AvatarSlot", p.name, p.image_url, v.urls AS "image_variants?: ImageVariants"
+            FROM avatar_parts p LEFT JOIN image_variants v ON v.asset_id = p.image_asset_id
+            ORDER BY p.id"#
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("users.avatar_parts"))
+        .await?;
+        Ok(parts)
+    }
+
+    async fn set_avatar_parts(&self, user_id: i32, parts: &[WornPart]) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM user_avatar_parts WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .instrument(query_span("users.clear_avatar_parts"))
+            .await?;
+        for (position, part) in parts.iter().enumerate() {
+            sqlx::query!(
+                "INSERT INTO user_avatar_parts (user_id, part_id, position, tint) VALUES ($1, $2, $3, $4)",
+                user_id,
+                part.part_id,
+                position as i16,
+                part.tint
+            )
+            .execute(&mut *tx)
+            .instrument(query_span("users.wear_avatar_part"))
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn avatar_compositions(
+        &self,
+        user_ids: &[i32],
+    ) -> RepoResult<HashMap<i32, AvatarComposition>> {
+        let rows = sqlx::query!(
+            r#"SELECT w.user_id, p.id AS part_id, p.slot AS "slot: AvatarSlot", p.name, p.image_url,
+                v.urls AS "image_variants?: ImageVariants", w.tint
+            FROM user_avatar_parts w
+            JOIN avatar_parts p ON p.id = w.part_id
+            LEFT JOIN image_variants v ON v.asset_id = p.image_asset_id
+            WHERE w.user_id = ANY($1)
+            ORDER BY w.user_id, w.position"#,
+            user_ids
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("users.avatar_compositions"))
+        .await?;
+        let mut compositions: HashMap<i32, AvatarComposition> = HashMap::new();
+        for row in rows {
+            compositions
+                .entry(row.user_id)
+                .or_default()
+                .layers
+                .push(AvatarLayer {
+                    part_id: row.part_id,
+                    slot: row.slot,
+                    name: row.name,
+                    image_url: row.image_url,
+                    image_variants: row.image_variants,
+                    tint: row.tint,
+                });
+        }
+        // Stable, so accessories stay in the order they were put on.
+        for composition in compositions.values_mut() {
+            composition.layers.sort_by_key(|layer| layer.slot);
+        }
+        Ok(compositions)
+    }
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tracing::Instrument;
+
+use super::{RepoResult, query_span};
+use crate::asset::ImageVariants;
+use crate::worlds::{CreateWorldPayload, World};
+
+#[async_trait]
+pub trait WorldRepo: Send + Sync {
+    async fn create(&self, creator_id: i32, world: &CreateWorldPayload) -> RepoResult<i32>;
+    async fn list(&self) -> RepoResult<Vec<World>>;
+}
+
+pub struct PgWorldRepo {
+    pool: PgPool,
+}
+
+impl PgWorldRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WorldRepo for PgWorldRepo {
+    async fn create(&self, creator_id: i32, world: &CreateWorldPayload) -> RepoResult<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO worlds (name, description, thumbnail_url, thumbnail_asset_id, creator_id, is_public) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            world.name,
+            world.description,
+            world.thumbnail_url,
+            world.thumbnail_asset_id,
+            creator_id,
+            world.is_public
+        )
+        .fetch_one(&self.pool)
+        .instrument(query_span("worlds.create"))
+        .await?;
+        Ok(id)
+    }
+
+    async fn list(&self) -> RepoResult<Vec<World>> {
+        let worlds = sqlx::query_as!(
+            World,
+            r#"SELECT w.id, w.name, w.description, w.thumbnail_url, v.urls AS "thumbnail_variants?: ImageVariants"
+            FROM worlds w LEFT JOIN image_variants v ON v.asset_id = w.thumbnail_asset_id
+            ORDER BY w.id"#
+        )
+        .fetch_all(&self.pool)
+        .instrument(query_span("worlds.list"))
+        .await?;
+        Ok(worlds)
+    }
+}
@@ -0,0 +1,153 @@
+//! JSON Schemas for the free-form JSON on elements, so malformed data is
+//! refused on the way in rather than crashing the client that reads it.
+//!
+//! A template may declare a `properties_schema` that the `custom_properties`
+//! of every element placed from it, and the state `set_state` interactions
+//! send, must match. Physics and animation data
+//! always have to match the built-in [`physics_schema`] and
+//! [`animation_schema`].
+
+use jsonschema::Validator;
+use serde_json::{Value, json};
+use std::sync::LazyLock;
+
+/// Why a value was refused: which field, and every way it failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{field} is invalid: {}", problems.join("; "))]
+pub struct SchemaError {
+    pub field: &'static str,
+    pub problems: Vec<String>,
+}
+
+static PHYSICS: LazyLock<Value> = LazyLock::new(|| {
+    json!({
+        "type": "object",
+        "properties": {
+            "body": { "enum": ["fixed", "dynamic", "kinematic"] },
+            "collider": { "enum": ["cuboid", "ball", "capsule"] },
+            "mass": { "type": "number", "exclusiveMinimum": 0 },
+            "friction": { "type": "number", "minimum": 0 },
+            "restitution": { "type": "number", "minimum": 0, "maximum": 1 },
+            "gravity_scale": { "type": "number" },
+            "sensor": { "type": "boolean" }
+        },
+        "additionalProperties": false
+    })
+});
+
+static ANIMATION: LazyLock<Value> = LazyLock::new(|| {
+    json!({
+        "type": "object",
+        "properties": {
+            "clips": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": {
+                        "frames": {
+                            "type": "array",
+                            "items": { "type": "integer", "minimum": 0 },
+                            "minItems": 1
+                        },
+                        "fps": { "type": "number", "exclusiveMinimum": 0 },
+                        "loop": { "type": "boolean" }
+                    },
+                    "required": ["frames"],
+                    "additionalProperties": false
+                }
+            },
+            "default": { "type": "string" }
+        },
+        "required": ["clips"],
+        "additionalProperties": false
+    })
+});
+
+static PHYSICS_VALIDATOR: LazyLock<Validator> =
+    LazyLock::new(|| jsonschema::validator_for(&PHYSICS).expect("the physics schema is valid"));
+static ANIMATION_VALIDATOR: LazyLock<Validator> =
+    LazyLock::new(|| jsonschema::validator_for(&ANIMATION).expect("the animation schema is valid"));
+
+/// What a template's `physics_properties` must look like: how the client
+/// builds its rigid body and collider.
+pub fn physics_schema() -> &'static Value {
+    &PHYSICS
+}
+
+/// What a template's `animation_data` must look like: named clips of sprite
+/// frame indices, and which one plays first.
+pub fn animation_schema() -> &'static Value {
+    &ANIMATION
+}
+
+/// Checks a template's own JSON: physics and animation data against the
+/// built-in schemas, and that its properties schema is one. Nulls count as
+/// absent.
+pub fn check_template(
+    physics_properties: Option<&Value>,
+    animation_data: Option<&Value>,
+    properties_schema: Option<&Value>,
+) -> Result<(), SchemaError> {
+    if let Some(physics) = present(physics_properties) {
+        problems("physics_properties", &PHYSICS_VALIDATOR, physics)?;
+    }
+    if let Some(animation) = present(animation_data) {
+        problems("animation_data", &ANIMATION_VALIDATOR, animation)?;
+    }
+    if let Some(schema) = present(properties_schema) {
+        compile("properties_schema", schema)?;
+    }
+    Ok(())
+}
+
+/// Checks an element's `custom_properties` against its template's
+/// `properties_schema`, if it has one.
+pub fn check_properties(
+    properties_schema: Option<&Value>,
+    custom_properties: &Value,
+) -> Result<(), SchemaError> {
+    let Some(schema) = present(properties_schema) else {
+        return Ok(());
+    };
+    let validator = compile("properties_schema", schema)?;
+    problems("custom_properties", &validator, custom_properties)
+}
+
+/// Checks the state a `set_state` interaction sends against the template's
+/// `properties_schema`. Without one no state is accepted.
+pub fn check_state(properties_schema: Option<&Value>, state: &Value) -> Result<(), SchemaError> {
+    let Some(schema) = present(properties_schema) else {
+        return Err(SchemaError {
+            field: "state",
+            problems: vec!["the template has no properties_schema".to_string()],
+        });
+    };
+    let validator = compile("properties_schema", schema)?;
+    problems("state", &validator, state)
+}
+
+fn present(value: Option<&Value>) -> Option<&Value> {
+    value.filter(|value| !value.is_null())
+}
+
+fn compile(field: &'static str, schema: &Value) -> Result<Validator, SchemaError> {
+    jsonschema::validator_for(schema).map_err(|err| SchemaError {
+        field,
+        problems: vec![err.to_string()],
+    })
+}
+
+fn problems(field: &'static str, validator: &Validator, value: &Value) -> Result<(), SchemaError> {
+    let problems: Vec<String> = validator
+        .iter_errors(value)
+        .map(|err| match err.instance_path.to_string() {
+            path if path.is_empty() => err.to_string(),
+            path => format!("{path}: {err}"),
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaError { field, problems })
+    }
+}
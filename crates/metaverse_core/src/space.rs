@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::asset::ImageVariants;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateSpacePayload {
+    pub map_id: i32,
+    pub name: String,
+    pub description: String,
+    pub width: i32,
+    pub height: i32,
+    /// Replaced by the URL of `background_asset_id`, if set.
+    #[serde(default)]
+    pub background_url: String,
+    #[serde(default)]
+    pub background_asset_id: Option<i32>,
+    /// Replaced by the URL of `thumbnail_asset_id`, if set.
+    #[serde(default)]
+    pub thumbnail_url: String,
+    #[serde(default)]
+    pub thumbnail_asset_id: Option<i32>,
+    pub max_occupancy: i32,
+    pub is_private: bool,
+    pub default_spawn_x: i32,
+    pub default_spawn_y: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeleteSpacePayload {
+    pub space_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetSpacePayload {
+    pub space_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetSpaceResponse {
+    pub map_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub width: i32,
+    pub height: i32,
+    pub background_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_variants: Option<ImageVariants>,
+    pub max_occupancy: i32,
+    pub is_private: bool,
+    pub default_spawn_x: Option<i32>,
+    pub default_spawn_y: Option<i32>,
+}
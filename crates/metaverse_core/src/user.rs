@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use crate::asset::ImageVariants;
+use crate::avatar::AvatarComposition;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UpdateAvatarPayload {
+    pub avatar_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateAvatarPayload {
+    pub name: String,
+    /// Replaced by the URL of `image_asset_id`, if set.
+    #[serde(default)]
+    pub image_url: String,
+    #[serde(default)]
+    pub image_asset_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AvatarPayload {
+    pub id: i32,
+    pub name: String,
+    pub image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_variants: Option<ImageVariants>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AvatarResponseBody {
+    pub avatars: Vec<AvatarPayload>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetUserMetadataRequestPayload {
+    pub ids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UserMetaDataResponsePayload {
+    pub id: i32,
+    pub image_url: Option<String>,
+    /// Set for users who have put an avatar together from parts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub composition: Option<AvatarComposition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetUserMetadataResponse {
+    pub avatars: Vec<UserMetaDataResponsePayload>,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::asset::ImageVariants;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateWorldPayload {
+    pub name: String,
+    pub description: String,
+    /// Replaced by the URL of `thumbnail_asset_id`, if set.
+    #[serde(default)]
+    pub thumbnail_url: String,
+    #[serde(default)]
+    pub thumbnail_asset_id: Option<i32>,
+    pub is_public: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct World {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub thumbnail_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_variants: Option<ImageVariants>,
+}
@@ -1,16 +1,20 @@
 use axum::{
     body::Body,
-    extract::Request,
+    extract::{Request, State},
     http::{Response, StatusCode},
     middleware::Next,
 };
+use metaverse_core::repo::UserRepo;
 use std::sync::Arc;
 
-use dotenv::dotenv;
+use crate::{
+    auth_middleware::current_role,
+    config::Config,
+    metrics::{AuthFailure, Metrics},
+};
+
 use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
-use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::env;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -19,17 +23,16 @@ pub struct Claims {
     pub role: String,
 }
 
-static SECRET_KEY: Lazy<Vec<u8>> = Lazy::new(|| {
-    dotenv().ok();
-    env::var("SECRET_KEY_JWT")
-        .expect("Error in getting secret key")
-        .into_bytes()
-});
-
-use tracing::{error, info};
+use tracing::{Span, debug, error};
 
-pub async fn admin_middleware(request: Request, next: Next) -> Result<Response<Body>, StatusCode> {
-    info!("Starting authentication process");
+pub async fn admin_middleware(
+    State(config): State<Arc<Config>>,
+    State(metrics): State<Arc<Metrics>>,
+    State(users): State<Arc<dyn UserRepo>>,
+    request: Request,
+    next: Next,
+) -> Result<Response<Body>, StatusCode> {
+    debug!("Starting authentication process");
 
     // Extract authorization header
     let token = match request
@@ -41,6 +44,7 @@ pub async fn admin_middleware(request: Request, next: Next) -> Result<Response<B
         Some(token) => token,
         None => {
             error!("Missing or invalid Authorization header");
+            metrics.auth_failure(AuthFailure::MissingToken);
             return Err(StatusCode::UNAUTHORIZED);
         }
     };
@@ -48,30 +52,37 @@ pub async fn admin_middleware(request: Request, next: Next) -> Result<Response<B
     // Decode the token
     let token_data = match decode::<Claims>(
         token,
-        &DecodingKey::from_secret(&*SECRET_KEY),
+        &DecodingKey::from_secret(config.auth.jwt_secret.as_bytes()),
         &Validation::new(Algorithm::HS256),
     ) {
         Ok(data) => {
-            info!("Token successfully decoded for user: {}", data.claims.sub);
+            debug!("Token successfully decoded for user: {}", data.claims.sub);
+            Span::current().record("user_id", data.claims.sub.as_str());
             data
         }
         Err(err) => {
             error!("Invalid token: {:?}", err);
+            metrics.auth_failure(AuthFailure::InvalidToken);
             return Err(StatusCode::UNAUTHORIZED);
         }
     };
 
-    // Get the claims
-    let claims = token_data.claims;
-    info!("Retrieved claims: {:?}", claims);
+    // Get the claims, with the role the user has now rather than at sign-in
+    let mut claims = token_data.claims;
+    claims.role = current_role(users.as_ref(), &metrics, &claims.sub)
+        .await?
+        .as_str()
+        .to_string();
+    debug!("Retrieved claims: {:?}", claims);
 
     // Check if user is admin
     if claims.role != "Admin" {
         error!("User is not an admin, role: {}", claims.role);
+        metrics.auth_failure(AuthFailure::NotAdmin);
         return Err(StatusCode::FORBIDDEN);
     }
 
-    info!("Admin authentication successful");
+    debug!("Admin authentication successful");
 
     // Create a new request with the claims in the extensions
     let (mut parts, body) = request.into_parts();
@@ -0,0 +1,51 @@
+//! Deletes uploads that nothing uses. Uploading and using a file are separate
+//! requests, so an upload only counts as orphaned once `orphan_grace_secs`
+//! have passed; one that gets used meanwhile is kept by its foreign keys.
+
+use metaverse_core::repo::{AssetRepo, RepoError};
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
+
+use super::{remove, storage::AssetStore};
+
+/// Collects orphans every `interval`, for as long as the process runs.
+pub async fn run_collector(
+    assets: Arc<dyn AssetRepo>,
+    store: Arc<dyn AssetStore>,
+    grace: Duration,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        collect_orphans(assets.as_ref(), store.as_ref(), grace).await;
+    }
+}
+
+/// Deletes every asset nothing has used for `grace`, and returns how many.
+pub async fn collect_orphans(
+    assets: &dyn AssetRepo,
+    store: &dyn AssetStore,
+    grace: Duration,
+) -> usize {
+    let orphans = match assets.orphans(grace).await {
+        Ok(orphans) => orphans,
+        Err(e) => {
+            error!("Error finding orphaned assets: {}", e);
+            return 0;
+        }
+    };
+    let mut deleted = 0;
+    for id in orphans {
+        match remove(assets, store, id, None).await {
+            Ok(()) => deleted += 1,
+            // Used or deleted since it was found.
+            Err(RepoError::Conflict | RepoError::NotFound) => {}
+            Err(e) => error!("Error deleting orphaned asset {}: {}", id, e),
+        }
+    }
+    if deleted > 0 {
+        info!("Deleted {} orphaned assets", deleted);
+    }
+    deleted
+}
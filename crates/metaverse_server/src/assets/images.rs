@@ -0,0 +1,157 @@
+//! Uploaded images are decoded, checked, and re-encoded at each
+//! [`ImageVariant`] size. Only pixels survive re-encoding, so EXIF (GPS
+//! positions included), colour profiles and text chunks are dropped; the EXIF
+//! orientation is applied first so photos stay upright. Animated GIFs keep
+//! their first frame.
+
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits, imageops::FilterType};
+use metaverse_core::asset::ImageVariant;
+use std::io::Cursor;
+
+use crate::config::ImageEncoding;
+
+/// Larger images are refused rather than decoded.
+const MAX_SIDE: u32 = 8192;
+
+pub struct Encoded {
+    pub variant: ImageVariant,
+    pub width: u32,
+    pub height: u32,
+    pub content: Vec<u8>,
+}
+
+impl ImageEncoding {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ImageEncoding::Webp => "image/webp",
+            ImageEncoding::Png => "image/png",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageEncoding::Webp => "webp",
+            ImageEncoding::Png => "png",
+        }
+    }
+
+    fn format(self) -> ImageFormat {
+        match self {
+            ImageEncoding::Webp => ImageFormat::WebP,
+            ImageEncoding::Png => ImageFormat::Png,
+        }
+    }
+}
+
+/// Every variant of the image in `content`, smallest first. Fails if it
+/// doesn't decode or is too large.
+pub fn variants(content: &[u8], encoding: ImageEncoding) -> image::ImageResult<Vec<Encoded>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SIDE);
+    limits.max_image_height = Some(MAX_SIDE);
+    let mut decoder = ImageReader::new(Cursor::new(content))
+        .with_guessed_format()?
+        .into_decoder()?;
+    decoder.set_limits(limits)?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    // The encoders take 8-bit RGB(A).
+    let image = if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.into_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.into_rgb8())
+    };
+
+    ImageVariant::ALL
+        .into_iter()
+        .map(|variant| {
+            let side = variant.max_side();
+            let resized = if image.width() > side || image.height() > side {
+                image.resize(side, side, FilterType::Lanczos3)
+            } else {
+                image.clone()
+            };
+            let mut content = Vec::new();
+            resized.write_to(&mut Cursor::new(&mut content), encoding.format())?;
+            Ok(Encoded {
+                variant,
+                width: resized.width(),
+                height: resized.height(),
+                content,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba([200, 40, 40, 255]));
+        let mut content = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut content), ImageFormat::Png)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn resizes_to_each_variant_without_enlarging() {
+        let variants = variants(&png(1000, 250), ImageEncoding::Webp).unwrap();
+
+        let sizes: Vec<_> = variants.iter().map(|v| (v.width, v.height)).collect();
+        assert_eq!(sizes, [(128, 32), (512, 128), (1000, 250)]);
+        for variant in &variants {
+            assert_eq!(
+                image::guess_format(&variant.content).unwrap(),
+                ImageFormat::WebP
+            );
+        }
+    }
+
+    #[test]
+    fn drops_metadata() {
+        // A PNG with a text chunk carrying a location.
+        let mut content = png(4, 4);
+        let text = b"tEXtLocation\x0051.5007,-0.1246";
+        let mut chunk = (text.len() as u32 - 4).to_be_bytes().to_vec();
+        chunk.extend_from_slice(text);
+        chunk.extend_from_slice(&crc32(text).to_be_bytes());
+        content.splice(33..33, chunk);
+        assert!(image::load_from_memory(&content).is_ok());
+
+        let variants = variants(&content, ImageEncoding::Png).unwrap();
+
+        for variant in variants {
+            let haystack = String::from_utf8_lossy(&variant.content);
+            assert!(!haystack.contains("Location"));
+        }
+    }
+
+    #[test]
+    fn refuses_images_that_do_not_decode_or_are_too_large() {
+        let mut truncated = png(16, 16);
+        truncated.truncate(40);
+
+        assert!(variants(&truncated, ImageEncoding::Webp).is_err());
+        assert!(variants(&png(MAX_SIDE + 1, 1), ImageEncoding::Webp).is_err());
+    }
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in bytes {
+            crc ^= u32::from(*byte);
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+}
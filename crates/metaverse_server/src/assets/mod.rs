@@ -0,0 +1,411 @@
+//! The asset registry: uploads, their metadata, and serving files kept by the
+//! local backend.
+//!
+//! An upload is hashed, and a file that was uploaded before is answered with
+//! the existing asset instead of being stored twice. Images are stored as the
+//! variants [`images`] makes of them rather than as uploaded.
+//!
+//! Entities hold assets through foreign keys, so an asset can only be deleted
+//! once nothing uses it; [`gc`] deletes those nobody got round to using.
+
+use crate::{auth_middleware::Claims, config::Config};
+use axum::{
+    Extension, Json,
+    body::Bytes,
+    extract::{Multipart, Path, State, multipart::MultipartError},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use metaverse_core::{
+    asset::{
+        Asset, AssetReference, AssetType, CreatorStorage, DeleteAssetPayload, Format,
+        GetAssetPayload, ImageVariant, content_hash,
+    },
+    repo::{AssetRepo, NewAsset, NewVariant, RepoError, RepoResult},
+};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+pub mod gc;
+pub mod images;
+pub mod storage;
+
+use crate::config::ImageEncoding;
+use storage::AssetStore;
+
+/// Matched path of [`upload_asset`], whose body limit follows
+/// `assets.max_upload_bytes` instead of `http.max_body_bytes`.
+pub const UPLOAD_ROUTE: &str = "/api/v1/assets/upload";
+
+/// Longest name the `assets` table holds.
+const MAX_NAME_CHARS: usize = 100;
+
+/// The multipart form [`upload_asset`] takes.
+#[allow(dead_code)]
+#[derive(utoipa::ToSchema)]
+pub struct UploadAssetForm {
+    /// An image (PNG, JPEG, GIF, WebP), a binary glTF model or audio (Ogg,
+    /// MP3, WAV).
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+    /// Defaults to the file's name.
+    name: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/upload",
+    tag = "assets",
+    request_body(content = UploadAssetForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "Asset stored", body = Asset),
+        (status = 200, description = "The same file was uploaded before", body = Asset),
+        (status = 413, description = "File larger than the upload limit"),
+        (status = 415, description = "Not a supported file type"),
+        (status = 422, description = "An image that doesn't decode or is too large")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn upload_asset(
+    State(assets): State<Arc<dyn AssetRepo>>,
+    State(store): State<Arc<dyn AssetStore>>,
+    State(config): State<Arc<Config>>,
+    Extension(claims): Extension<Arc<Claims>>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Asset>), StatusCode> {
+    let creator_id = claims.sub.parse::<i32>().map_err(|e| {
+        error!("Error getting user id: {}", e);
+        StatusCode::FORBIDDEN
+    })?;
+
+    let mut name = None;
+    let mut file = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(rejected)? {
+        match field.name() {
+            Some("name") => name = Some(field.text().await.map_err(rejected)?),
+            Some("file") => {
+                let declared = field.content_type().map(str::to_string);
+                let file_name = field.file_name().map(str::to_string);
+                let mut content = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(rejected)? {
+                    if content.len() + chunk.len() > config.assets.max_upload_bytes {
+                        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+                    }
+                    content.extend_from_slice(&chunk);
+                }
+                file = Some((content, declared, file_name));
+            }
+            _ => {}
+        }
+    }
+    let Some((content, declared, file_name)) = file else {
+        warn!("Upload without a file field");
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let Some(format) = Format::sniff(&content) else {
+        warn!("Upload of an unsupported file type");
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    };
+    if let Some(declared) = declared
+        && declared != "application/octet-stream"
+        && !format.matches(&declared)
+    {
+        warn!("Upload declared as {} is {}", declared, format.mime_type);
+        return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    let hash = content_hash(&content);
+    if let Some(existing) = existing(assets.as_ref(), &hash).await? {
+        return Ok((StatusCode::OK, Json(existing)));
+    }
+
+    let name: String = name
+        .or(file_name)
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| format!("{hash}.{}", format.extension))
+        .chars()
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let asset = if format.asset_type == AssetType::Image {
+        store_image(
+            store.as_ref(),
+            name,
+            hash.clone(),
+            content,
+            config.assets.image_format,
+        )
+        .await?
+    } else {
+        store_file(store.as_ref(), name, hash.clone(), content, format).await?
+    };
+    match assets.create(creator_id, &asset).await {
+        Ok(created) => Ok((StatusCode::CREATED, Json(created))),
+        // Someone else uploaded the same file meanwhile.
+        Err(RepoError::Conflict) => match existing(assets.as_ref(), &hash).await? {
+            Some(existing) => Ok((StatusCode::OK, Json(existing))),
+            None => Err(StatusCode::CONFLICT),
+        },
+        Err(e) => {
+            error!("Error registering asset: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/get_asset",
+    tag = "assets",
+    request_body = GetAssetPayload,
+    responses((status = 200, description = "The requested asset", body = Asset)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_asset(
+    State(assets): State<Arc<dyn AssetRepo>>,
+    Json(payload): Json<GetAssetPayload>,
+) -> Result<Json<Asset>, StatusCode> {
+    match assets.get(payload.asset_id).await {
+        Ok(asset) => Ok(Json(asset)),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error getting asset: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/get_asset_references",
+    tag = "assets",
+    request_body = GetAssetPayload,
+    responses((status = 200, description = "Everything using the asset", body = Vec<AssetReference>)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_asset_references(
+    State(assets): State<Arc<dyn AssetRepo>>,
+    Json(payload): Json<GetAssetPayload>,
+) -> Result<Json<Vec<AssetReference>>, StatusCode> {
+    match assets.references(payload.asset_id).await {
+        Ok(references) => Ok(Json(references)),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error getting asset references: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Uploaders may delete their own assets and admins any.
+#[utoipa::path(
+    post,
+    path = "/delete_asset",
+    tag = "assets",
+    request_body = DeleteAssetPayload,
+    responses(
+        (status = 200, description = "Asset and its files deleted"),
+        (status = 404, description = "No such asset, or not yours"),
+        (status = 409, description = "Still in use, see /get_asset_references")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_asset(
+    State(assets): State<Arc<dyn AssetRepo>>,
+    State(store): State<Arc<dyn AssetStore>>,
+    Extension(claims): Extension<Arc<Claims>>,
+    Json(payload): Json<DeleteAssetPayload>,
+) -> Result<StatusCode, StatusCode> {
+    let creator_id = if claims.role == "Admin" {
+        None
+    } else {
+        Some(claims.sub.parse::<i32>().map_err(|e| {
+            error!("Error getting user id: {}", e);
+            StatusCode::FORBIDDEN
+        })?)
+    };
+    match remove(
+        assets.as_ref(),
+        store.as_ref(),
+        payload.asset_id,
+        creator_id,
+    )
+    .await
+    {
+        Ok(()) => Ok(StatusCode::OK),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(RepoError::Conflict) => {
+            warn!("Asset {} is still in use", payload.asset_id);
+            Err(StatusCode::CONFLICT)
+        }
+        Err(e) => {
+            error!("Error deleting asset: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/storage_report",
+    tag = "assets",
+    responses((status = 200, description = "Storage used by each uploader, most first", body = Vec<CreatorStorage>)),
+    security(("bearer_auth" = []))
+)]
+pub async fn storage_report(
+    State(assets): State<Arc<dyn AssetRepo>>,
+) -> Result<Json<Vec<CreatorStorage>>, StatusCode> {
+    match assets.storage_by_creator().await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            error!("Error reporting asset storage: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Files kept by the local backend. Keys name their content, so responses
+/// may be cached for good.
+pub async fn serve_asset(
+    State(store): State<Arc<dyn AssetStore>>,
+    Path(key): Path<String>,
+) -> Result<Response, StatusCode> {
+    if !storage::is_key(&key) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    match store.get(&key).await {
+        Ok(Some(content)) => {
+            let mime_type = Format::sniff(&content)
+                .map_or("application/octet-stream", |format| format.mime_type);
+            Ok((
+                [
+                    (header::CONTENT_TYPE, mime_type),
+                    (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+                ],
+                content,
+            )
+                .into_response())
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error reading asset {}: {}", key, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn store_file(
+    store: &dyn AssetStore,
+    name: String,
+    hash: String,
+    content: Vec<u8>,
+    format: Format,
+) -> Result<NewAsset, StatusCode> {
+    let storage_key = format!("{hash}.{}", format.extension);
+    let size_bytes = content.len() as i64;
+    put(store, &storage_key, content, format.mime_type).await?;
+    Ok(NewAsset {
+        name,
+        asset_type: format.asset_type,
+        mime_type: format.mime_type.to_string(),
+        size_bytes,
+        hash,
+        url: store.url(&storage_key),
+        storage_key,
+        variants: Vec::new(),
+    })
+}
+
+/// Stores every variant of an image. The asset itself is its full size.
+async fn store_image(
+    store: &dyn AssetStore,
+    name: String,
+    hash: String,
+    content: Vec<u8>,
+    encoding: ImageEncoding,
+) -> Result<NewAsset, StatusCode> {
+    // Decoding and resizing can take a while for large images.
+    let encoded = tokio::task::spawn_blocking(move || images::variants(&content, encoding))
+        .await
+        .map_err(|e| {
+            error!("Error processing image: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map_err(|e| {
+            warn!("Upload of an unusable image: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    let mut variants = Vec::new();
+    for image in encoded {
+        let storage_key = format!("{hash}-{}.{}", image.variant.as_str(), encoding.extension());
+        let size_bytes = image.content.len() as i64;
+        put(store, &storage_key, image.content, encoding.mime_type()).await?;
+        variants.push(NewVariant {
+            variant: image.variant,
+            width: image.width as i32,
+            height: image.height as i32,
+            mime_type: encoding.mime_type().to_string(),
+            size_bytes,
+            url: store.url(&storage_key),
+            storage_key,
+        });
+    }
+    let full = variants
+        .iter()
+        .find(|variant| variant.variant == ImageVariant::Full)
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(NewAsset {
+        name,
+        asset_type: AssetType::Image,
+        mime_type: full.mime_type.clone(),
+        size_bytes: full.size_bytes,
+        hash,
+        storage_key: full.storage_key.clone(),
+        url: full.url.clone(),
+        variants,
+    })
+}
+
+async fn put(
+    store: &dyn AssetStore,
+    key: &str,
+    content: Vec<u8>,
+    mime_type: &str,
+) -> Result<(), StatusCode> {
+    store
+        .put(key, Bytes::from(content), mime_type)
+        .await
+        .map_err(|e| {
+            error!("Error storing asset {}: {}", key, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Deletes an asset, then its files. A file that can't be removed is only
+/// logged: the asset is gone either way.
+async fn remove(
+    assets: &dyn AssetRepo,
+    store: &dyn AssetStore,
+    id: i32,
+    creator_id: Option<i32>,
+) -> RepoResult<()> {
+    for key in assets.delete(id, creator_id).await? {
+        if let Err(e) = store.delete(&key).await {
+            error!("Error deleting asset file {}: {}", key, e);
+        }
+    }
+    Ok(())
+}
+
+async fn existing(assets: &dyn AssetRepo, hash: &str) -> Result<Option<Asset>, StatusCode> {
+    assets.by_hash(hash).await.map_err(|e| {
+        error!("Error looking up asset: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+fn rejected(err: MultipartError) -> StatusCode {
+    warn!("Bad upload: {}", err);
+    err.status()
+}
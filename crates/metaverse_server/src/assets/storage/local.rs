@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use axum::body::Bytes;
+use std::{io::ErrorKind, path::PathBuf};
+
+use super::{AssetStore, StorageError, check_key};
+
+/// Files in a directory on this server, served by [`crate::assets::serve_asset`].
+pub struct LocalStore {
+    dir: PathBuf,
+    public_url: String,
+}
+
+impl LocalStore {
+    pub fn new(dir: PathBuf, public_url: &str) -> Self {
+        Self {
+            dir,
+            public_url: public_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl AssetStore for LocalStore {
+    async fn put(&self, key: &str, content: Bytes, _mime_type: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // Written aside and renamed, so readers never see half a file.
+        let partial = self.dir.join(format!(".{key}.{}", uuid::Uuid::new_v4()));
+        tokio::fs::write(&partial, &content).await?;
+        if let Err(err) = tokio::fs::rename(&partial, self.dir.join(key)).await {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StorageError> {
+        check_key(key)?;
+        match tokio::fs::read(self.dir.join(key)).await {
+            Ok(content) => Ok(Some(content.into())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        match tokio::fs::remove_file(self.dir.join(key)).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{key}", self.public_url)
+    }
+}
@@ -0,0 +1,67 @@
+//! Where uploaded files are kept. The registry in the `assets` table only
+//! knows each file's key; an [`AssetStore`] holds the bytes behind it.
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use std::sync::Arc;
+
+use crate::config::{AssetStorage, AssetsConfig};
+
+mod local;
+mod s3;
+
+pub use local::LocalStore;
+pub use s3::S3Store;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("{0:?} is not an asset key")]
+    InvalidKey(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("storage answered {status}: {body}")]
+    Status { status: u16, body: String },
+}
+
+#[async_trait]
+pub trait AssetStore: Send + Sync {
+    /// Stores `content` under `key`, replacing anything already there.
+    async fn put(&self, key: &str, content: Bytes, mime_type: &str) -> Result<(), StorageError>;
+    /// `None` if nothing is stored under `key`.
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StorageError>;
+    /// Succeeds if nothing was stored under `key` either.
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+    /// Where clients download `key` from.
+    fn url(&self, key: &str) -> String;
+}
+
+/// The backend `config` picks.
+pub fn from_config(config: &AssetsConfig) -> Arc<dyn AssetStore> {
+    match config.storage {
+        AssetStorage::Local => Arc::new(LocalStore::new(
+            config.dir.clone(),
+            config.public_url.as_deref().unwrap_or("/assets"),
+        )),
+        AssetStorage::S3 => Arc::new(S3Store::new(&config.s3, config.public_url.as_deref())),
+    }
+}
+
+/// Keys are single file names such as `{hash}.png`, so they can't point
+/// outside the directory or bucket.
+pub fn is_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('.')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+fn check_key(key: &str) -> Result<(), StorageError> {
+    if is_key(key) {
+        Ok(())
+    } else {
+        Err(StorageError::InvalidKey(key.to_string()))
+    }
+}
@@ -0,0 +1,130 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{Response, StatusCode},
+    middleware::Next,
+};
+use metaverse_core::{
+    common::Role,
+    repo::{RepoError, UserRepo},
+};
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    metrics::{AuthFailure, Metrics},
+};
+
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    pub role: String,
+}
+
+use tracing::{Span, debug, error, warn};
+
+/// Looks up the token's user, so that disabling or demoting someone takes
+/// effect on their next request rather than when their token expires.
+/// Returns the user's current role.
+pub(crate) async fn current_role(
+    users: &dyn UserRepo,
+    metrics: &Metrics,
+    sub: &str,
+) -> Result<Role, StatusCode> {
+    let Ok(user_id) = sub.parse::<i32>() else {
+        error!("Token subject is not a user id: {sub}");
+        metrics.auth_failure(AuthFailure::InvalidToken);
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    match users.access(user_id).await {
+        Ok(access) if access.disabled => {
+            warn!("Rejected token of disabled user {user_id}");
+            metrics.auth_failure(AuthFailure::Disabled);
+            Err(StatusCode::FORBIDDEN)
+        }
+        Ok(access) => Ok(access.role),
+        Err(RepoError::NotFound) => {
+            warn!("Rejected token of unknown user {user_id}");
+            metrics.auth_failure(AuthFailure::InvalidToken);
+            Err(StatusCode::UNAUTHORIZED)
+        }
+        Err(err) => {
+            error!("Failed to look up user {user_id}: {err}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn auth_middleware(
+    State(config): State<Arc<Config>>,
+    State(metrics): State<Arc<Metrics>>,
+    State(users): State<Arc<dyn UserRepo>>,
+    request: Request,
+    next: Next,
+) -> Result<Response<Body>, StatusCode> {
+    debug!("Authenticating request...");
+
+    // Extract authorization header
+    let token = match request
+        .headers()
+        .get("Authorization")
+        .and_then(|val| val.to_str().ok())
+        .and_then(|auth| auth.strip_prefix("Bearer "))
+    {
+        Some(token) => token,
+        None => {
+            error!("Missing or invalid Authorization header");
+            metrics.auth_failure(AuthFailure::MissingToken);
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    };
+
+    // Decode the token
+    let token_data = match decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config.auth.jwt_secret.as_bytes()),
+        &Validation::new(Algorithm::HS256),
+    ) {
+        Ok(data) => {
+            debug!("Token successfully decoded for user: {}", data.claims.sub);
+            Span::current().record("user_id", data.claims.sub.as_str());
+            data
+        }
+        Err(err) => {
+            error!("Invalid token: {:?}", err);
+            metrics.auth_failure(AuthFailure::InvalidToken);
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    };
+
+    // The role in the token may be stale; handlers see the current one
+    let mut claims = token_data.claims;
+    claims.role = current_role(users.as_ref(), &metrics, &claims.sub)
+        .await?
+        .as_str()
+        .to_string();
+
+    // Create an Arc<Claims> to share across middleware
+    let claims = Arc::new(claims);
+
+    // Create a new request with the claims in the extensions
+    let (mut parts, body) = request.into_parts();
+    parts.extensions.insert(claims.clone());
+    let new_request = Request::from_parts(parts, body);
+
+    // Log the claims after insertion
+    if let Some(retrieved_claims) = new_request.extensions().get::<Arc<Claims>>() {
+        debug!("Claims after insertion: {:?}", retrieved_claims);
+    } else {
+        error!("Failed to retrieve claims after insertion");
+    }
+
+    debug!("Authentication successful, proceeding to next middleware...");
+
+    // Pass the new request to the next middleware
+    Ok(next.run(new_request).await)
+}
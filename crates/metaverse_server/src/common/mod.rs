@@ -0,0 +1,191 @@
+use crate::{
+    config::Config,
+    metrics::{AuthFailure, Metrics},
+};
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{EncodingKey, Header, encode};
+use metaverse_core::common::{SignInPayload, SignInResponse, SignUpPayload};
+use metaverse_core::repo::{NewUser, RepoError, UserRepo};
+use std::sync::Arc;
+use tracing::{debug, error, info};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Claims {
+    sub: String,
+    exp: usize,
+    role: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/signin",
+    tag = "common",
+    request_body = SignInPayload,
+    responses(
+        (status = 200, description = "Signed in", body = SignInResponse),
+        (status = 401, description = "Unknown user or wrong password"),
+        (status = 403, description = "Account disabled by an administrator")
+    )
+)]
+pub async fn signin(
+    State(users): State<Arc<dyn UserRepo>>,
+    State(config): State<Arc<Config>>,
+    State(metrics): State<Arc<Metrics>>,
+    Json(payload): Json<SignInPayload>,
+) -> Result<Json<SignInResponse>, StatusCode> {
+    let response = users.credentials(&payload.username).await;
+
+    match response {
+        Ok(record) => {
+            let password_matches =
+                bcrypt::verify(&payload.password, &record.password_hash).unwrap_or(false);
+            // Checked after the password so it doesn't reveal which accounts exist.
+            if password_matches && record.disabled {
+                metrics.auth_failure(AuthFailure::Disabled);
+                Err(StatusCode::FORBIDDEN)
+            } else if password_matches {
+                let expiration =
+                    Utc::now() + Duration::seconds(config.auth.token_lifetime_secs as i64);
+                let claims = Claims {
+                    sub: record.id.to_string(),
+                    exp: expiration.timestamp() as usize,
+                    role: record.role.as_str().to_string(),
+                };
+                let token = encode(
+                    &Header::default(),
+                    &claims,
+                    &EncodingKey::from_secret(config.auth.jwt_secret.as_bytes()),
+                )
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+                let _ = users.record_login(record.id).await;
+
+                Ok(Json(SignInResponse { token }))
+            } else {
+                metrics.auth_failure(AuthFailure::BadCredentials);
+                Err(StatusCode::UNAUTHORIZED)
+            }
+        }
+        Err(RepoError::NotFound) => {
+            metrics.auth_failure(AuthFailure::BadCredentials);
+            Err(StatusCode::UNAUTHORIZED)
+        }
+        Err(err) => {
+            error!("Database error during signin: {:?}", err);
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/signup",
+    tag = "common",
+    request_body = SignUpPayload,
+    responses(
+        (status = 201, description = "User created"),
+        (status = 409, description = "Username or email already taken")
+    )
+)]
+pub async fn signup(
+    State(users): State<Arc<dyn UserRepo>>,
+    Json(payload): Json<SignUpPayload>,
+) -> Result<StatusCode, StatusCode> {
+    info!("User attempting to sign up: {}", payload.username);
+
+    let password_hash =
+        hash_password(&payload.password).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    debug!(
+        "Inserting user: username={}, email={}, avatar_id={:?}, role={}",
+        payload.username,
+        payload.email_id,
+        payload.avatar_id,
+        payload.role.as_str()
+    );
+
+    let response = users
+        .create(NewUser {
+            username: payload.username,
+            email: payload.email_id,
+            password_hash,
+            avatar_id: payload.avatar_id,
+            role: payload.role,
+        })
+        .await;
+
+    match response {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(RepoError::Conflict) => Ok(StatusCode::CONFLICT),
+        Err(error) => {
+            error!("Database error during signin: {:?}", error);
+            Ok(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Hashes a password the way `signin` expects to verify it.
+pub fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
+    bcrypt::hash(password, bcrypt::DEFAULT_COST)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes;
+    use metaverse_core::common::Role;
+
+    fn alice() -> SignUpPayload {
+        SignUpPayload {
+            username: "alice".to_string(),
+            email_id: "alice@example.com".to_string(),
+            password: "secret".to_string(),
+            avatar_id: None,
+            role: Role::User,
+        }
+    }
+
+    fn credentials(password: &str) -> SignInPayload {
+        SignInPayload {
+            username: "alice".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn signup_reports_duplicate_users() {
+        let users = fakes::state().users;
+
+        let first = signup(State(users.clone()), Json(alice())).await;
+        let second = signup(State(users), Json(alice())).await;
+
+        assert_eq!(first, Ok(StatusCode::CREATED));
+        assert_eq!(second, Ok(StatusCode::CONFLICT));
+    }
+
+    #[tokio::test]
+    async fn signin_checks_the_password() {
+        let state = fakes::state();
+        let users = state.users;
+        signup(State(users.clone()), Json(alice())).await.unwrap();
+
+        let rejected = signin(
+            State(users.clone()),
+            State(state.config.clone()),
+            State(state.metrics.clone()),
+            Json(credentials("wrong")),
+        )
+        .await;
+        let accepted = signin(
+            State(users),
+            State(state.config),
+            State(state.metrics),
+            Json(credentials("secret")),
+        )
+        .await;
+
+        assert_eq!(rejected.err(), Some(StatusCode::UNAUTHORIZED));
+        assert!(!accepted.unwrap().token.is_empty());
+    }
+}
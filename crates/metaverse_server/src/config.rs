@@ -0,0 +1,852 @@
+//! Server configuration.
+//!
+//! Values come from, lowest to highest precedence: built-in defaults, the TOML
+//! file named by `--config`, environment variables, then command line flags.
+//! Every setting has both an environment variable and a flag; see
+//! [`ConfigArgs`] or `--help` for the names.
+
+use crate::assets::UPLOAD_ROUTE;
+use clap::{ArgAction, Args, ValueEnum};
+use metaverse_core::db::DatabaseConfig;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub http: HttpConfig,
+    /// Serve HTTPS and WSS directly instead of plain HTTP.
+    pub tls: Option<TlsConfig>,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub cors: CorsConfig,
+    pub features: FeatureConfig,
+    pub realtime: RealtimeConfig,
+    pub assets: AssetsConfig,
+    pub logging: LoggingConfig,
+    pub tracing: TracingConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen_addr: SocketAddr,
+    /// Time allowed after SIGTERM/Ctrl-C for connections to drain and state to
+    /// be saved before the process exits anyway.
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+/// Limits and headers applied to every HTTP request.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Largest request body accepted, in bytes, unless the route has its own
+    /// limit in `route_body_limits`.
+    pub max_body_bytes: usize,
+    /// Per-route body limits in bytes, keyed by path such as
+    /// `/api/v1/element/create_map_elements`.
+    pub route_body_limits: BTreeMap<String, usize>,
+    /// Requests still unanswered after this long get `408 Request Timeout`.
+    pub request_timeout_secs: u64,
+    /// Compress responses with gzip or brotli for clients that accept it.
+    pub compression: bool,
+    /// `max-age` of the `Strict-Transport-Security` header; 0 leaves it out.
+    pub hsts_max_age_secs: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 64 * 1024,
+            route_body_limits: BTreeMap::new(),
+            request_timeout_secs: 30,
+            compression: true,
+            hsts_max_age_secs: 365 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// PEM certificate chain and private key. Both files are checked for changes
+/// every `reload_interval_secs` and the new pair is used for new connections;
+/// open connections keep the certificate they started with.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    #[serde(default = "default_tls_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+}
+
+fn default_tls_reload_interval_secs() -> u64 {
+    30
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// HMAC key for signing and checking session tokens.
+    pub jwt_secret: String,
+    pub token_lifetime_secs: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: String::new(),
+            token_lifetime_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+// Keep the secret out of startup logs.
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("jwt_secret", &"<redacted>")
+            .field("token_lifetime_secs", &self.token_lifetime_secs)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Browser origins allowed to call the API, e.g. `https://play.example.com`.
+    /// Empty means cross-origin requests are refused.
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureConfig {
+    /// Serve Swagger UI and the OpenAPI document.
+    pub docs: bool,
+    /// Allow anyone to create an account through `/common/signup`.
+    pub signup: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            docs: true,
+            signup: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RealtimeConfig {
+    /// How often buffered positions are written to `user_sessions`.
+    pub position_flush_secs: u64,
+    /// How many tiles from an element users may use it from, for elements
+    /// that don't set their own `range`.
+    pub interaction_range: u32,
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        Self {
+            position_flush_secs: 5,
+            interaction_range: 2,
+        }
+    }
+}
+
+/// Uploaded files and where they are kept.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssetsConfig {
+    pub storage: AssetStorage,
+    /// Largest file accepted by `/assets/upload`, in bytes.
+    pub max_upload_bytes: usize,
+    /// What uploaded images are re-encoded to at each size.
+    pub image_format: ImageEncoding,
+    /// Directory the `local` backend keeps files in. They are served from
+    /// `/assets/{key}`.
+    pub dir: PathBuf,
+    /// Base URL stored assets are linked from. Defaults to `/assets` for the
+    /// `local` backend and the bucket's URL for `s3`; set it when a CDN sits
+    /// in front of either.
+    pub public_url: Option<String>,
+    /// How long an upload nothing uses is kept before it is deleted, giving
+    /// its uploader time to use it.
+    pub orphan_grace_secs: u64,
+    /// How often to look for such uploads. 0 turns deleting them off.
+    pub gc_interval_secs: u64,
+    /// Used by the `s3` backend.
+    pub s3: S3Config,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        Self {
+            storage: AssetStorage::Local,
+            max_upload_bytes: 10 * 1024 * 1024,
+            image_format: ImageEncoding::Webp,
+            dir: PathBuf::from("assets"),
+            public_url: None,
+            orphan_grace_secs: 7 * 24 * 60 * 60,
+            gc_interval_secs: 60 * 60,
+            s3: S3Config::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetStorage {
+    /// Files in a directory on this server.
+    #[default]
+    Local,
+    /// An S3-compatible bucket, such as AWS S3 or MinIO.
+    S3,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageEncoding {
+    /// Lossless WebP, usually the smaller.
+    #[default]
+    Webp,
+    Png,
+}
+
+/// Objects are addressed path-style, `{endpoint}/{bucket}/{key}`, which every
+/// S3-compatible store supports.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Config {
+    /// e.g. `https://s3.eu-west-1.amazonaws.com` or `http://localhost:9000`.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+        }
+    }
+}
+
+// Keep the secret out of startup logs.
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Which events are logged comes from `RUST_LOG` (default `info`); this
+/// only picks how they are written.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable, for terminals.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+/// Distributed tracing. Spans cover each HTTP request and each database
+/// query, and requests carrying a W3C `traceparent` header join the caller's
+/// trace.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TracingConfig {
+    pub exporter: TraceExporter,
+    /// OTLP/HTTP traces endpoint, used by the `otlp` exporter.
+    pub otlp_endpoint: String,
+    /// JSON lines file, used by the `file` exporter.
+    pub file_path: PathBuf,
+    /// Reported as `service.name` on every span.
+    pub service_name: String,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            exporter: TraceExporter::None,
+            otlp_endpoint: "http://localhost:4318/v1/traces".to_string(),
+            file_path: PathBuf::from("traces.jsonl"),
+            service_name: "metaverse".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceExporter {
+    /// Don't export spans.
+    #[default]
+    None,
+    /// Send spans to an OpenTelemetry collector over OTLP/HTTP.
+    Otlp,
+    /// Append spans to a local file, one JSON object per line.
+    File,
+}
+
+/// Command line flags, each of which can also be set from the environment.
+#[derive(Debug, Default, Args)]
+pub struct ConfigArgs {
+    /// TOML config file.
+    #[arg(long = "config", env = "METAVERSE_CONFIG", value_name = "PATH")]
+    pub config_file: Option<PathBuf>,
+    #[arg(long, env = "LISTEN_ADDR")]
+    pub listen_addr: Option<SocketAddr>,
+    #[arg(long, env = "SHUTDOWN_TIMEOUT_SECS")]
+    pub shutdown_timeout_secs: Option<u64>,
+    #[arg(long, env = "MAX_BODY_BYTES")]
+    pub max_body_bytes: Option<usize>,
+    #[arg(long, env = "REQUEST_TIMEOUT_SECS")]
+    pub request_timeout_secs: Option<u64>,
+    #[arg(long, env = "ENABLE_COMPRESSION", action = ArgAction::Set)]
+    pub compression: Option<bool>,
+    #[arg(long, env = "HSTS_MAX_AGE_SECS")]
+    pub hsts_max_age_secs: Option<u64>,
+    #[arg(long, env = "TLS_CERT_PATH", value_name = "PATH")]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(long, env = "TLS_KEY_PATH", value_name = "PATH")]
+    pub tls_key: Option<PathBuf>,
+    #[arg(long, env = "TLS_RELOAD_INTERVAL_SECS")]
+    pub tls_reload_interval_secs: Option<u64>,
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    pub database_url: Option<String>,
+    #[arg(long, env = "MAX_CONNECTIONS")]
+    pub max_connections: Option<u32>,
+    #[arg(long, env = "MIN_CONNECTIONS")]
+    pub min_connections: Option<u32>,
+    #[arg(long, env = "DB_ACQUIRE_TIMEOUT_SECS")]
+    pub db_acquire_timeout_secs: Option<u64>,
+    #[arg(long, env = "DB_IDLE_TIMEOUT_SECS")]
+    pub db_idle_timeout_secs: Option<u64>,
+    #[arg(long, env = "MIGRATE_ON_START", action = ArgAction::Set)]
+    pub migrate_on_start: Option<bool>,
+    #[arg(long, env = "SECRET_KEY_JWT", hide_env_values = true)]
+    pub jwt_secret: Option<String>,
+    #[arg(long, env = "TOKEN_LIFETIME_SECS")]
+    pub token_lifetime_secs: Option<u64>,
+    /// Comma separated in the environment; repeat the flag on the command line.
+    #[arg(
+        long = "cors-origin",
+        env = "CORS_ALLOWED_ORIGINS",
+        value_delimiter = ','
+    )]
+    pub cors_origins: Option<Vec<String>>,
+    #[arg(long, env = "ENABLE_DOCS", action = ArgAction::Set)]
+    pub docs: Option<bool>,
+    #[arg(long, env = "ENABLE_SIGNUP", action = ArgAction::Set)]
+    pub signup: Option<bool>,
+    #[arg(long, env = "POSITION_FLUSH_SECS")]
+    pub position_flush_secs: Option<u64>,
+    #[arg(long, env = "INTERACTION_RANGE")]
+    pub interaction_range: Option<u32>,
+    #[arg(long, env = "ASSET_STORAGE")]
+    pub asset_storage: Option<AssetStorage>,
+    #[arg(long, env = "MAX_UPLOAD_BYTES")]
+    pub max_upload_bytes: Option<usize>,
+    #[arg(long, env = "IMAGE_FORMAT")]
+    pub image_format: Option<ImageEncoding>,
+    #[arg(long, env = "ASSET_DIR", value_name = "PATH")]
+    pub asset_dir: Option<PathBuf>,
+    #[arg(long, env = "ASSET_PUBLIC_URL")]
+    pub asset_public_url: Option<String>,
+    #[arg(long, env = "ORPHAN_GRACE_SECS")]
+    pub orphan_grace_secs: Option<u64>,
+    #[arg(long, env = "ASSET_GC_INTERVAL_SECS")]
+    pub asset_gc_interval_secs: Option<u64>,
+    #[arg(long, env = "S3_ENDPOINT")]
+    pub s3_endpoint: Option<String>,
+    #[arg(long, env = "S3_BUCKET")]
+    pub s3_bucket: Option<String>,
+    #[arg(long, env = "S3_REGION")]
+    pub s3_region: Option<String>,
+    #[arg(long, env = "S3_ACCESS_KEY_ID")]
+    pub s3_access_key_id: Option<String>,
+    #[arg(long, env = "S3_SECRET_ACCESS_KEY", hide_env_values = true)]
+    pub s3_secret_access_key: Option<String>,
+    #[arg(long, env = "LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+    #[arg(long, env = "TRACE_EXPORTER")]
+    pub trace_exporter: Option<TraceExporter>,
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+    #[arg(long, env = "TRACE_FILE", value_name = "PATH")]
+    pub trace_file: Option<PathBuf>,
+    #[arg(long, env = "OTEL_SERVICE_NAME")]
+    pub service_name: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("could not read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+impl Config {
+    /// Builds the config from `args` layered over its `--config` file, then
+    /// validates it.
+    pub fn load(args: ConfigArgs) -> Result<Self, ConfigError> {
+        let config = Self::merge(args)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Like [`Config::load`], but only needs and checks `[database]`, for
+    /// commands that don't run the server.
+    pub fn load_database(args: ConfigArgs) -> Result<DatabaseConfig, ConfigError> {
+        let config = Self::merge(args)?;
+        let mut problems = Vec::new();
+        config.check_database(&mut problems);
+        if problems.is_empty() {
+            Ok(config.database)
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    fn merge(args: ConfigArgs) -> Result<Self, ConfigError> {
+        let mut config = match &args.config_file {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply(args);
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn apply(&mut self, args: ConfigArgs) {
+        if let Some(addr) = args.listen_addr {
+            self.server.listen_addr = addr;
+        }
+        if let Some(secs) = args.shutdown_timeout_secs {
+            self.server.shutdown_timeout_secs = secs;
+        }
+        if let Some(bytes) = args.max_body_bytes {
+            self.http.max_body_bytes = bytes;
+        }
+        if let Some(secs) = args.request_timeout_secs {
+            self.http.request_timeout_secs = secs;
+        }
+        if let Some(compression) = args.compression {
+            self.http.compression = compression;
+        }
+        if let Some(secs) = args.hsts_max_age_secs {
+            self.http.hsts_max_age_secs = secs;
+        }
+        match (args.tls_cert, args.tls_key, &mut self.tls) {
+            (None, None, _) => {}
+            (cert, key, Some(tls)) => {
+                if let Some(cert) = cert {
+                    tls.cert_path = cert;
+                }
+                if let Some(key) = key {
+                    tls.key_path = key;
+                }
+            }
+            (cert, key, tls @ None) => {
+                *tls = Some(TlsConfig {
+                    cert_path: cert.unwrap_or_default(),
+                    key_path: key.unwrap_or_default(),
+                    reload_interval_secs: default_tls_reload_interval_secs(),
+                });
+            }
+        }
+        if let (Some(secs), Some(tls)) = (args.tls_reload_interval_secs, &mut self.tls) {
+            tls.reload_interval_secs = secs;
+        }
+        if let Some(url) = args.database_url {
+            self.database.url = url;
+        }
+        if let Some(max) = args.max_connections {
+            self.database.max_connections = max;
+        }
+        if let Some(min) = args.min_connections {
+            self.database.min_connections = min;
+        }
+        if let Some(secs) = args.db_acquire_timeout_secs {
+            self.database.acquire_timeout_secs = secs;
+        }
+        if let Some(secs) = args.db_idle_timeout_secs {
+            self.database.idle_timeout_secs = secs;
+        }
+        if let Some(migrate) = args.migrate_on_start {
+            self.database.migrate_on_start = migrate;
+        }
+        if let Some(secret) = args.jwt_secret {
+            self.auth.jwt_secret = secret;
+        }
+        if let Some(secs) = args.token_lifetime_secs {
+            self.auth.token_lifetime_secs = secs;
+        }
+        if let Some(origins) = args.cors_origins {
+            self.cors.allowed_origins = origins;
+        }
+        if let Some(docs) = args.docs {
+            self.features.docs = docs;
+        }
+        if let Some(signup) = args.signup {
+            self.features.signup = signup;
+        }
+        if let Some(secs) = args.position_flush_secs {
+            self.realtime.position_flush_secs = secs;
+        }
+        if let Some(range) = args.interaction_range {
+            self.realtime.interaction_range = range;
+        }
+        if let Some(storage) = args.asset_storage {
+            self.assets.storage = storage;
+        }
+        if let Some(bytes) = args.max_upload_bytes {
+            self.assets.max_upload_bytes = bytes;
+        }
+        if let Some(format) = args.image_format {
+            self.assets.image_format = format;
+        }
+        if let Some(dir) = args.asset_dir {
+            self.assets.dir = dir;
+        }
+        if let Some(url) = args.asset_public_url {
+            self.assets.public_url = Some(url);
+        }
+        if let Some(secs) = args.orphan_grace_secs {
+            self.assets.orphan_grace_secs = secs;
+        }
+        if let Some(secs) = args.asset_gc_interval_secs {
+            self.assets.gc_interval_secs = secs;
+        }
+        if let Some(endpoint) = args.s3_endpoint {
+            self.assets.s3.endpoint = endpoint;
+        }
+        if let Some(bucket) = args.s3_bucket {
+            self.assets.s3.bucket = bucket;
+        }
+        if let Some(region) = args.s3_region {
+            self.assets.s3.region = region;
+        }
+        if let Some(key) = args.s3_access_key_id {
+            self.assets.s3.access_key_id = key;
+        }
+        if let Some(secret) = args.s3_secret_access_key {
+            self.assets.s3.secret_access_key = secret;
+        }
+        if let Some(format) = args.log_format {
+            self.logging.format = format;
+        }
+        if let Some(exporter) = args.trace_exporter {
+            self.tracing.exporter = exporter;
+        }
+        if let Some(endpoint) = args.otlp_endpoint {
+            self.tracing.otlp_endpoint = endpoint;
+        }
+        if let Some(path) = args.trace_file {
+            self.tracing.file_path = path;
+        }
+        if let Some(name) = args.service_name {
+            self.tracing.service_name = name;
+        }
+    }
+
+    /// Checks every setting and reports all problems at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.http.max_body_bytes == 0 {
+            problems.push("http.max_body_bytes must be at least 1".to_string());
+        }
+        for (route, bytes) in &self.http.route_body_limits {
+            if !route.starts_with('/') {
+                problems.push(format!(
+                    "http.route_body_limits key {route:?} is not a path like /api/v1/..."
+                ));
+            }
+            if *bytes == 0 {
+                problems.push(format!(
+                    "http.route_body_limits.{route:?} must be at least 1"
+                ));
+            }
+        }
+        if self.http.request_timeout_secs == 0 {
+            problems.push("http.request_timeout_secs must be at least 1".to_string());
+        }
+
+        self.check_database(&mut problems);
+
+        if self.auth.jwt_secret.is_empty() {
+            problems.push(
+                "auth.jwt_secret is not set; use SECRET_KEY_JWT, --jwt-secret or [auth] jwt_secret"
+                    .to_string(),
+            );
+        }
+        if self.auth.token_lifetime_secs == 0 {
+            problems.push("auth.token_lifetime_secs must be at least 1".to_string());
+        }
+
+        if self.realtime.position_flush_secs == 0 {
+            problems.push("realtime.position_flush_secs must be at least 1".to_string());
+        }
+
+        if let Some(tls) = &self.tls {
+            for (name, path) in [("cert_path", &tls.cert_path), ("key_path", &tls.key_path)] {
+                if path.as_os_str().is_empty() {
+                    problems.push(format!(
+                        "tls.{name} is not set; TLS needs both a certificate and a key"
+                    ));
+                } else if !path.is_file() {
+                    problems.push(format!("tls.{name} {} does not exist", path.display()));
+                }
+            }
+            if tls.reload_interval_secs == 0 {
+                problems.push("tls.reload_interval_secs must be at least 1".to_string());
+            }
+        }
+
+        for origin in &self.cors.allowed_origins {
+            if !is_origin(origin) {
+                problems.push(format!(
+                    "cors.allowed_origins entry {origin:?} is not an origin like https://example.com"
+                ));
+            }
+        }
+
+        if self.assets.max_upload_bytes == 0 {
+            problems.push("assets.max_upload_bytes must be at least 1".to_string());
+        }
+        if let Some(url) = &self.assets.public_url
+            && !url.starts_with('/')
+            && !is_http_url(url)
+        {
+            problems.push(format!(
+                "assets.public_url {url:?} is not a path or an http(s) URL"
+            ));
+        }
+        match self.assets.storage {
+            AssetStorage::Local => {
+                if self.assets.dir.as_os_str().is_empty() {
+                    problems.push("assets.dir is not set".to_string());
+                }
+            }
+            AssetStorage::S3 => {
+                let s3 = &self.assets.s3;
+                if !is_http_url(&s3.endpoint) {
+                    problems.push(format!(
+                        "assets.s3.endpoint {:?} is not an http(s) URL",
+                        s3.endpoint
+                    ));
+                }
+                for (name, value) in [
+                    ("bucket", &s3.bucket),
+                    ("region", &s3.region),
+                    ("access_key_id", &s3.access_key_id),
+                    ("secret_access_key", &s3.secret_access_key),
+                ] {
+                    if value.is_empty() {
+                        problems.push(format!("assets.s3.{name} is not set"));
+                    }
+                }
+            }
+        }
+
+        match self.tracing.exporter {
+            TraceExporter::None => {}
+            TraceExporter::Otlp => {
+                if !is_http_url(&self.tracing.otlp_endpoint) {
+                    problems.push(format!(
+                        "tracing.otlp_endpoint {:?} is not an http(s) URL",
+                        self.tracing.otlp_endpoint
+                    ));
+                }
+            }
+            TraceExporter::File => {
+                if self.tracing.file_path.as_os_str().is_empty() {
+                    problems.push("tracing.file_path is not set".to_string());
+                }
+            }
+        }
+        if self.tracing.service_name.is_empty() {
+            problems.push("tracing.service_name must not be empty".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// The body limit for a matched route: its own from `route_body_limits`,
+    /// room for a whole file plus the rest of the form on the upload route,
+    /// or else `max_body_bytes`.
+    pub fn body_limit(&self, route: &str) -> usize {
+        match self.http.route_body_limits.get(route) {
+            Some(limit) => *limit,
+            None if route == UPLOAD_ROUTE => {
+                self.assets.max_upload_bytes + self.http.max_body_bytes
+            }
+            None => self.http.max_body_bytes,
+        }
+    }
+
+    fn check_database(&self, problems: &mut Vec<String>) {
+        if self.database.url.is_empty() {
+            problems.push(
+                "database.url is not set; use DATABASE_URL, --database-url or [database] url"
+                    .to_string(),
+            );
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            problems.push(format!(
+                "database.min_connections ({}) is larger than database.max_connections ({})",
+                self.database.min_connections, self.database.max_connections
+            ));
+        }
+        if self.database.acquire_timeout_secs == 0 {
+            problems.push("database.acquire_timeout_secs must be at least 1".to_string());
+        }
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+// An origin is a scheme and host with an optional port, and nothing after it.
+fn is_origin(value: &str) -> bool {
+    let Some(host) = value
+        .strip_prefix("https://")
+        .or_else(|| value.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    !host.is_empty() && !host.contains(['/', '?', '#'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid() -> Config {
+        let mut config = Config::default();
+        config.database.url = "postgres://localhost/metaverse".to_string();
+        config.auth.jwt_secret = "secret".to_string();
+        config
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [server]
+            listen_addr = "127.0.0.1:8080"
+
+            [database]
+            url = "postgres://file/metaverse"
+            max_connections = 20
+            "#,
+        )
+        .unwrap();
+
+        config.apply(ConfigArgs {
+            max_connections: Some(7),
+            docs: Some(false),
+            ..Default::default()
+        });
+
+        assert_eq!(config.server.listen_addr.to_string(), "127.0.0.1:8080");
+        assert_eq!(config.database.url, "postgres://file/metaverse");
+        assert_eq!(config.database.max_connections, 7);
+        assert_eq!(config.database.acquire_timeout_secs, 30);
+        assert!(!config.features.docs);
+        assert!(config.features.signup);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let parsed = toml::from_str::<Config>("[database]\nmax_conections = 3\n");
+
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let mut config = Config::default();
+        config.database.min_connections = 10;
+        config.cors.allowed_origins = vec!["https://play.example.com/".to_string()];
+
+        let Err(ConfigError::Invalid(problems)) = config.validate() else {
+            panic!("config should be invalid");
+        };
+
+        assert_eq!(problems.len(), 4, "{problems:#?}");
+    }
+
+    #[test]
+    fn accepts_origins_with_ports() {
+        let mut config = valid();
+        config.cors.allowed_origins = vec![
+            "http://localhost:5173".to_string(),
+            "https://play.example.com".to_string(),
+        ];
+
+        assert!(config.validate().is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::element::{
+    CreateElementTemplatePayload, TemplateRevision, UpdateElementTemplatePayload,
+};
+use metaverse_core::repo::{ElementRepo, RepoError};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+#[utoipa::path(
+    post,
+    path = "/create_new_element",
+    tag = "element",
+    request_body = CreateElementTemplatePayload,
+    responses(
+        (status = 201, description = "Element template created"),
+        (status = 400, description = "Interactive template without a valid `Interaction` as its interaction_data, or JSON not matching its schema")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_element_template(
+    State(elements): State<Arc<dyn ElementRepo>>,
+    Json(payload): Json<CreateElementTemplatePayload>,
+) -> Result<StatusCode, StatusCode> {
+    check_template(&payload)?;
+    let response = elements.create_template(&payload).await;
+    match response {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(e) => {
+            error!("Error creating element templates {e}");
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/update_element",
+    tag = "element",
+    request_body = UpdateElementTemplatePayload,
+    responses(
+        (status = 200, description = "The template's new revision", body = TemplateRevision),
+        (status = 400, description = "Interactive template without a valid `Interaction` as its interaction_data, or JSON not matching its schema"),
+        (status = 404, description = "No such template")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_element_template(
+    State(elements): State<Arc<dyn ElementRepo>>,
+    Json(payload): Json<UpdateElementTemplatePayload>,
+) -> Result<Json<TemplateRevision>, StatusCode> {
+    check_template(&payload.template)?;
+    let response = elements
+        .update_template(payload.template_id, &payload.template)
+        .await;
+    match response {
+        Ok(revision) => Ok(Json(TemplateRevision {
+            template_id: payload.template_id,
+            revision,
+        })),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error updating element template {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn check_template(template: &CreateElementTemplatePayload) -> Result<(), StatusCode> {
+    template.validate().map_err(|err| {
+        warn!("Rejected template {:?}: {}", template.name, err);
+        StatusCode::BAD_REQUEST
+    })
+}
@@ -0,0 +1,30 @@
+pub mod element_templates;
+pub mod map_elements;
+pub mod space_elements;
+pub mod template_revisions;
+
+use axum::http::StatusCode;
+use metaverse_core::repo::{ElementRepo, RepoError};
+use metaverse_core::schema;
+use tracing::{error, warn};
+
+/// Checks an element's `custom_properties` against its template's
+/// `properties_schema` before it is placed.
+async fn check_properties(
+    elements: &dyn ElementRepo,
+    template_id: i32,
+    custom_properties: &serde_json::Value,
+) -> Result<(), StatusCode> {
+    let properties_schema = match elements.properties_schema(template_id).await {
+        Ok(properties_schema) => properties_schema,
+        Err(RepoError::NotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error loading properties schema of template {template_id}: {e}");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    schema::check_properties(properties_schema.as_ref(), custom_properties).map_err(|err| {
+        warn!("Rejected element of template {template_id}: {err}");
+        StatusCode::BAD_REQUEST
+    })
+}
@@ -0,0 +1,38 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::element::CreateSpaceElementsPayload;
+use metaverse_core::repo::ElementRepo;
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    post,
+    path = "/create_space_element",
+    tag = "element",
+    request_body = CreateSpaceElementsPayload,
+    responses(
+        (status = 201, description = "Element placed in the space"),
+        (status = 400, description = "custom_properties don't match the template's properties_schema"),
+        (status = 404, description = "No such template")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_space_elements(
+    State(elements): State<Arc<dyn ElementRepo>>,
+    Json(payload): Json<CreateSpaceElementsPayload>,
+) -> Result<StatusCode, StatusCode> {
+    super::check_properties(
+        elements.as_ref(),
+        payload.template_id,
+        &payload.custom_properties,
+    )
+    .await?;
+    let response = elements.create_space_element(&payload).await;
+
+    match response {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(e) => {
+            error!("Error creating space elements {e}");
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
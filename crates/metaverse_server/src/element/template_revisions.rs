@@ -0,0 +1,66 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::element::{PinRevisionPayload, RevisionPreview};
+use metaverse_core::repo::{ElementRepo, RepoError};
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    post,
+    path = "/preview_template_revision",
+    tag = "element",
+    request_body = PinRevisionPayload,
+    responses(
+        (status = 200, description = "The elements pinning would move", body = RevisionPreview),
+        (status = 400, description = "Not exactly one of a space and a map was given"),
+        (status = 404, description = "No such template revision")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn preview_template_revision(
+    State(elements): State<Arc<dyn ElementRepo>>,
+    Json(payload): Json<PinRevisionPayload>,
+) -> Result<Json<RevisionPreview>, StatusCode> {
+    // Scoped like pinning, so the preview lists exactly what a pin would move.
+    if payload.space_id.is_some() == payload.map_id.is_some() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    match elements.preview_revision(&payload).await {
+        Ok(preview) => Ok(Json(preview)),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error previewing template revision {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/pin_template_revision",
+    tag = "element",
+    request_body = PinRevisionPayload,
+    responses(
+        (status = 200, description = "The elements moved to the revision", body = RevisionPreview),
+        (status = 400, description = "Not exactly one of a space and a map was given"),
+        (status = 404, description = "No such template revision"),
+        (status = 409, description = "Some elements' custom_properties don't match the revision's schema; preview to see which")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn pin_template_revision(
+    State(elements): State<Arc<dyn ElementRepo>>,
+    Json(payload): Json<PinRevisionPayload>,
+) -> Result<Json<RevisionPreview>, StatusCode> {
+    if payload.space_id.is_some() == payload.map_id.is_some() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    match elements.pin_revision(&payload).await {
+        Ok(pinned) => Ok(Json(pinned)),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(RepoError::Conflict) => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            error!("Error pinning template revision {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
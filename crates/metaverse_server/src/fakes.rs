@@ -0,0 +1,561 @@
+//! In-memory repositories for exercising handlers without Postgres.
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use metaverse_core::{
+    asset::{Asset, AssetReference, CreatorStorage, ImageVariant, ImageVariants},
+    avatar::{AvatarComposition, AvatarLayer, AvatarPart, CreateAvatarPartPayload, WornPart},
+    element::{
+        CreateElementTemplatePayload, CreateMapElementsPayload, CreateSpaceElementsPayload,
+        PinRevisionPayload, PlacedElement, RevisionPreview,
+    },
+    maps::{CreateMapPayload, GetMapResponse},
+    realtime::ElementState,
+    repo::{
+        AssetRepo, ElementRepo, HealthRepo, MapRepo, NewAsset, NewUser, PoolStats, RepoError,
+        RepoResult, SessionPosition, SessionRepo, SpaceRepo, UserAccess, UserCredentials, UserRepo,
+        WorldRepo,
+    },
+    space::{CreateSpacePayload, GetSpaceResponse},
+    user::{AvatarPayload, CreateAvatarPayload, UserMetaDataResponsePayload},
+    worlds::{CreateWorldPayload, World},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    AppState,
+    assets::storage::{AssetStore, StorageError},
+    config::Config,
+    metrics::Metrics,
+    realtime::Hub,
+};
+
+pub fn state() -> AppState {
+    let mut config = Config::default();
+    config.auth.jwt_secret = "test-secret".to_string();
+    AppState {
+        users: Arc::new(MemoryUsers::default()),
+        worlds: Arc::new(MemoryWorlds::default()),
+        maps: Arc::new(MemoryMaps::default()),
+        spaces: Arc::new(MemorySpaces::default()),
+        elements: Arc::new(MemoryElements::default()),
+        assets: Arc::new(MemoryAssets::default()),
+        storage: Arc::new(MemoryStore::default()),
+        health: Arc::new(MemoryHealth),
+        realtime: Arc::new(Hub::new(Arc::new(MemorySessions::default()))),
+        metrics: Arc::new(Metrics::new()),
+        config: Arc::new(config),
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryUsers {
+    users: Mutex<Vec<NewUser>>,
+    avatars: Mutex<Vec<AvatarPayload>>,
+    parts: Mutex<Vec<AvatarPart>>,
+    worn: Mutex<HashMap<i32, Vec<WornPart>>>,
+}
+
+#[async_trait]
+impl UserRepo for MemoryUsers {
+    async fn create(&self, user: NewUser) -> RepoResult<i32> {
+        let mut users = self.users.lock().unwrap();
+        if users
+            .iter()
+            .any(|u| u.username == user.username || u.email == user.email)
+        {
+            return Err(RepoError::Conflict);
+        }
+        users.push(user);
+        Ok(users.len() as i32)
+    }
+
+    async fn credentials(&self, username: &str) -> RepoResult<UserCredentials> {
+        let users = self.users.lock().unwrap();
+        let (index, user) = users
+            .iter()
+            .enumerate()
+            .find(|(_, u)| u.username == username)
+            .ok_or(RepoError::NotFound)?;
+        Ok(UserCredentials {
+            id: index as i32 + 1,
+            password_hash: user.password_hash.clone(),
+            role: user.role,
+            disabled: false,
+        })
+    }
+
+    async fn access(&self, user_id: i32) -> RepoResult<UserAccess> {
+        let users = self.users.lock().unwrap();
+        let user = usize::try_from(user_id - 1)
+            .ok()
+            .and_then(|index| users.get(index))
+            .ok_or(RepoError::NotFound)?;
+        Ok(UserAccess {
+            role: user.role,
+            disabled: false,
+        })
+    }
+
+    async fn record_login(&self, _user_id: i32) -> RepoResult<()> {
+        Ok(())
+    }
+
+    async fn set_avatar(&self, user_id: i32, avatar_id: i32) -> RepoResult<()> {
+        let mut users = self.users.lock().unwrap();
+        let user = usize::try_from(user_id - 1)
+            .ok()
+            .and_then(|index| users.get_mut(index))
+            .ok_or(RepoError::NotFound)?;
+        user.avatar_id = Some(avatar_id);
+        Ok(())
+    }
+
+    async fn create_avatar(&self, avatar: &CreateAvatarPayload) -> RepoResult<i32> {
+        let mut avatars = self.avatars.lock().unwrap();
+        let id = avatars.len() as i32 + 1;
+        avatars.push(AvatarPayload {
+            id,
+            name: avatar.name.clone(),
+            image_url: Some(avatar.image_url.clone()),
+            image_variants: None,
+        });
+        Ok(id)
+    }
+
+    async fn avatars(&self) -> RepoResult<Vec<AvatarPayload>> {
+        Ok(self.avatars.lock().unwrap().clone())
+    }
+
+    async fn avatar_images(
+        &self,
+        user_ids: &[i32],
+    ) -> RepoResult<Vec<UserMetaDataResponsePayload>> {
+        let users = self.users.lock().unwrap();
+        let avatars = self.avatars.lock().unwrap();
+        let mut ids = user_ids.to_vec();
+        ids.sort_unstable();
+        Ok(ids
+            .into_iter()
+            .filter_map(|id| {
+                let user = users.get(usize::try_from(id - 1).ok()?)?;
+                let image_url = user.avatar_id.and_then(|avatar_id| {
+                    avatars
+                        .iter()
+                        .find(|a| a.id == avatar_id)
+                        .and_then(|a| a.image_url.clone())
+                });
+                Some(UserMetaDataResponsePayload {
+                    id,
+                    image_url,
+                    composition: None,
+                })
+            })
+            .collect())
+    }
+
+    async fn create_avatar_part(&self, part: &CreateAvatarPartPayload) -> RepoResult<i32> {
+        let mut parts = self.parts.lock().unwrap();
+        let id = parts.len() as i32 + 1;
+        parts.push(AvatarPart {
+            id,
+            slot: part.slot,
+            name: part.name.clone(),
+            image_url: part.image_url.clone(),
+            image_variants: None,
+        });
+        Ok(id)
+    }
+
+    async fn avatar_parts(&self) -> RepoResult<Vec<AvatarPart>> {
+        Ok(self.parts.lock().unwrap().clone())
+    }
+
+    async fn set_avatar_parts(&self, user_id: i32, parts: &[WornPart]) -> RepoResult<()> {
+        let known = self.parts.lock().unwrap();
+        if parts
+            .iter()
+            .any(|w| known.iter().all(|p| p.id != w.part_id))
+        {
+            return Err(RepoError::Conflict);
+        }
+        self.worn.lock().unwrap().insert(user_id, parts.to_vec());
+        Ok(())
+    }
+
+    async fn avatar_compositions(
+        &self,
+        user_ids: &[i32],
+    ) -> RepoResult<HashMap<i32, AvatarComposition>> {
+        let parts = self.parts.lock().unwrap();
+        let worn = self.worn.lock().unwrap();
+        Ok(user_ids
+            .iter()
+            .filter_map(|id| {
+                let mut layers: Vec<AvatarLayer> = worn
+                    .get(id)?
+                    .iter()
+                    .filter_map(|w| {
+                        let part = parts.iter().find(|p| p.id == w.part_id)?;
+                        Some(AvatarLayer {
+                            part_id: part.id,
+                            slot: part.slot,
+                            name: part.name.clone(),
+                            image_url: part.image_url.clone(),
+                            image_variants: None,
+                            tint: w.tint.clone(),
+                        })
+                    })
+                    .collect();
+                layers.sort_by_key(|layer| layer.slot);
+                Some((*id, AvatarComposition { layers }))
+            })
+            .collect())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryWorlds {
+    worlds: Mutex<Vec<World>>,
+}
+
+#[async_trait]
+impl WorldRepo for MemoryWorlds {
+    async fn create(&self, _creator_id: i32, world: &CreateWorldPayload) -> RepoResult<i32> {
+        let mut worlds = self.worlds.lock().unwrap();
+        let id = worlds.len() as i32 + 1;
+        worlds.push(World {
+            id,
+            name: world.name.clone(),
+            description: Some(world.description.clone()),
+            thumbnail_url: Some(world.thumbnail_url.clone()),
+            thumbnail_variants: None,
+        });
+        Ok(id)
+    }
+
+    async fn list(&self) -> RepoResult<Vec<World>> {
+        Ok(self.worlds.lock().unwrap().clone())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryMaps {
+    maps: Mutex<Vec<GetMapResponse>>,
+}
+
+#[async_trait]
+impl MapRepo for MemoryMaps {
+    async fn create(&self, map: &CreateMapPayload) -> RepoResult<i32> {
+        let mut maps = self.maps.lock().unwrap();
+        let map_id = maps.len() as i32 + 1;
+        maps.push(GetMapResponse {
+            map_id,
+            world_id: map.world_id,
+            name: map.name.clone(),
+            width: map.width,
+            height: map.height,
+            background_url: Some(map.background_url.clone()),
+        });
+        Ok(map_id)
+    }
+
+    async fn get(&self, map_id: i32) -> RepoResult<GetMapResponse> {
+        self.maps
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|m| m.map_id == map_id)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+}
+
+#[derive(Default)]
+pub struct MemorySpaces {
+    spaces: Mutex<Vec<Option<GetSpaceResponse>>>,
+}
+
+#[async_trait]
+impl SpaceRepo for MemorySpaces {
+    async fn create(&self, space: &CreateSpacePayload) -> RepoResult<i32> {
+        let mut spaces = self.spaces.lock().unwrap();
+        spaces.push(Some(GetSpaceResponse {
+            map_id: space.map_id,
+            name: space.name.clone(),
+            description: Some(space.description.clone()),
+            width: space.width,
+            height: space.height,
+            background_url: Some(space.background_url.clone()),
+            thumbnail_url: Some(space.thumbnail_url.clone()),
+            thumbnail_variants: None,
+            max_occupancy: space.max_occupancy,
+            is_private: space.is_private,
+            default_spawn_x: Some(space.default_spawn_x),
+            default_spawn_y: Some(space.default_spawn_y),
+        }));
+        Ok(spaces.len() as i32)
+    }
+
+    async fn get(&self, space_id: i32) -> RepoResult<GetSpaceResponse> {
+        let spaces = self.spaces.lock().unwrap();
+        usize::try_from(space_id - 1)
+            .ok()
+            .and_then(|index| spaces.get(index).cloned().flatten())
+            .ok_or(RepoError::NotFound)
+    }
+
+    async fn delete(&self, space_id: i32) -> RepoResult<()> {
+        let mut spaces = self.spaces.lock().unwrap();
+        usize::try_from(space_id - 1)
+            .ok()
+            .and_then(|index| spaces.get_mut(index)?.take())
+            .map(drop)
+            .ok_or(RepoError::NotFound)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryElements {
+    created: Mutex<i32>,
+}
+
+impl MemoryElements {
+    fn next_id(&self) -> i32 {
+        let mut created = self.created.lock().unwrap();
+        *created += 1;
+        *created
+    }
+}
+
+#[async_trait]
+impl ElementRepo for MemoryElements {
+    async fn create_template(&self, _template: &CreateElementTemplatePayload) -> RepoResult<i32> {
+        Ok(self.next_id())
+    }
+
+    async fn create_templates(
+        &self,
+        templates: &[CreateElementTemplatePayload],
+    ) -> RepoResult<Vec<i32>> {
+        Ok(templates.iter().map(|_| self.next_id()).collect())
+    }
+
+    async fn update_template(
+        &self,
+        _template_id: i32,
+        _template: &CreateElementTemplatePayload,
+    ) -> RepoResult<i32> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn preview_revision(&self, _pin: &PinRevisionPayload) -> RepoResult<RevisionPreview> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn pin_revision(&self, _pin: &PinRevisionPayload) -> RepoResult<RevisionPreview> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn create_space_element(&self, _element: &CreateSpaceElementsPayload) -> RepoResult<i32> {
+        Ok(self.next_id())
+    }
+
+    async fn create_map_element(&self, _element: &CreateMapElementsPayload) -> RepoResult<i32> {
+        Ok(self.next_id())
+    }
+
+    async fn properties_schema(&self, _template_id: i32) -> RepoResult<Option<serde_json::Value>> {
+        Ok(None)
+    }
+
+    async fn space_element(&self, _element_id: i32) -> RepoResult<PlacedElement> {
+        Err(RepoError::NotFound)
+    }
+
+    async fn element_states(&self, _space_id: i32) -> RepoResult<Vec<ElementState>> {
+        Ok(Vec::new())
+    }
+
+    async fn set_element_state(
+        &self,
+        _element_id: i32,
+        _version: i32,
+        _state: &serde_json::Value,
+    ) -> RepoResult<i32> {
+        Err(RepoError::NotFound)
+    }
+}
+
+#[derive(Default)]
+pub struct MemorySessions {
+    positions: Mutex<Vec<SessionPosition>>,
+    open: Mutex<Vec<i32>>,
+    created: Mutex<i32>,
+}
+
+#[async_trait]
+impl SessionRepo for MemorySessions {
+    async fn open(&self, _user_id: i32, _space_id: i32, _x: i32, _y: i32) -> RepoResult<i32> {
+        let mut created = self.created.lock().unwrap();
+        *created += 1;
+        self.open.lock().unwrap().push(*created);
+        Ok(*created)
+    }
+
+    async fn save_positions(&self, positions: &[SessionPosition]) -> RepoResult<()> {
+        self.positions.lock().unwrap().extend_from_slice(positions);
+        Ok(())
+    }
+
+    async fn close(&self, session_ids: &[i32]) -> RepoResult<()> {
+        self.open
+            .lock()
+            .unwrap()
+            .retain(|id| !session_ids.contains(id));
+        Ok(())
+    }
+
+    async fn closed(&self, session_ids: &[i32]) -> RepoResult<Vec<i32>> {
+        let open = self.open.lock().unwrap();
+        Ok(session_ids
+            .iter()
+            .copied()
+            .filter(|id| !open.contains(id))
+            .collect())
+    }
+}
+
+pub struct MemoryHealth;
+
+#[async_trait]
+impl HealthRepo for MemoryHealth {
+    async fn ping(&self) -> RepoResult<()> {
+        Ok(())
+    }
+
+    async fn pending_migrations(&self) -> RepoResult<Vec<i64>> {
+        Ok(Vec::new())
+    }
+
+    fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: 0,
+            idle: 0,
+            max: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryAssets {
+    assets: Mutex<Vec<Asset>>,
+}
+
+#[async_trait]
+impl AssetRepo for MemoryAssets {
+    async fn create(&self, _creator_id: i32, asset: &NewAsset) -> RepoResult<Asset> {
+        let mut assets = self.assets.lock().unwrap();
+        if assets.iter().any(|a| a.hash == asset.hash) {
+            return Err(RepoError::Conflict);
+        }
+        let url = |variant| {
+            asset
+                .variants
+                .iter()
+                .find(|v| v.variant == variant)
+                .map(|v| v.url.clone())
+        };
+        let variants = match (
+            url(ImageVariant::Thumbnail),
+            url(ImageVariant::Medium),
+            url(ImageVariant::Full),
+        ) {
+            (Some(thumbnail), Some(medium), Some(full)) => Some(ImageVariants {
+                thumbnail,
+                medium,
+                full,
+            }),
+            _ => None,
+        };
+        let created = Asset {
+            id: assets.len() as i32 + 1,
+            name: asset.name.clone(),
+            asset_type: asset.asset_type,
+            mime_type: asset.mime_type.clone(),
+            size_bytes: asset.size_bytes,
+            hash: asset.hash.clone(),
+            url: asset.url.clone(),
+            variants,
+        };
+        assets.push(created.clone());
+        Ok(created)
+    }
+
+    async fn get(&self, id: i32) -> RepoResult<Asset> {
+        let assets = self.assets.lock().unwrap();
+        assets
+            .iter()
+            .find(|a| a.id == id)
+            .cloned()
+            .ok_or(RepoError::NotFound)
+    }
+
+    async fn by_hash(&self, hash: &str) -> RepoResult<Option<Asset>> {
+        let assets = self.assets.lock().unwrap();
+        Ok(assets.iter().find(|a| a.hash == hash).cloned())
+    }
+
+    // Nothing here refers to assets, so every asset is unused.
+    async fn references(&self, id: i32) -> RepoResult<Vec<AssetReference>> {
+        self.get(id).await.map(|_| Vec::new())
+    }
+
+    async fn delete(&self, id: i32, _creator_id: Option<i32>) -> RepoResult<Vec<String>> {
+        let mut assets = self.assets.lock().unwrap();
+        let index = assets
+            .iter()
+            .position(|a| a.id == id)
+            .ok_or(RepoError::NotFound)?;
+        assets.remove(index);
+        Ok(Vec::new())
+    }
+
+    async fn orphans(&self, _grace: Duration) -> RepoResult<Vec<i32>> {
+        Ok(self.assets.lock().unwrap().iter().map(|a| a.id).collect())
+    }
+
+    async fn storage_by_creator(&self) -> RepoResult<Vec<CreatorStorage>> {
+        Ok(Vec::new())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    files: Mutex<HashMap<String, Bytes>>,
+}
+
+#[async_trait]
+impl AssetStore for MemoryStore {
+    async fn put(&self, key: &str, content: Bytes, _mime_type: &str) -> Result<(), StorageError> {
+        self.files.lock().unwrap().insert(key.to_string(), content);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StorageError> {
+        Ok(self.files.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.files.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("/assets/{key}")
+    }
+}
@@ -0,0 +1,240 @@
+//! The middleware stack around the router: CORS, security headers, request
+//! body limits, timeouts and response compression.
+
+use crate::{
+    config::{Config, CorsConfig},
+    telemetry::REQUEST_ID_HEADER,
+};
+use axum::{
+    Router,
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::Limited;
+use std::{sync::Arc, time::Duration};
+use tower_http::{
+    compression::CompressionLayer, cors::CorsLayer, set_header::SetResponseHeaderLayer,
+    timeout::TimeoutLayer,
+};
+
+/// Wraps `router` (API, probes and docs alike) in the configured stack.
+///
+/// Body limits depend on the matched route, so they are applied inside
+/// [`crate::router`] by [`limit_body`] instead.
+pub fn apply(router: Router, config: &Config) -> Router {
+    let mut router = router.layer(TimeoutLayer::with_status_code(
+        StatusCode::REQUEST_TIMEOUT,
+        Duration::from_secs(config.http.request_timeout_secs),
+    ));
+    if config.http.compression {
+        router = router.layer(CompressionLayer::new());
+    }
+    for (name, value) in security_headers(config) {
+        router = router.layer(SetResponseHeaderLayer::if_not_present(name, value));
+    }
+    // Outermost, so preflights and errors from the layers above carry CORS
+    // headers too.
+    router.layer(cors(&config.cors))
+}
+
+/// Lets the configured browser origins call the API.
+pub fn cors(config: &CorsConfig) -> CorsLayer {
+    let origins: Vec<HeaderValue> = config
+        .allowed_origins
+        .iter()
+        .filter_map(|origin| origin.parse().ok())
+        .collect();
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .expose_headers([REQUEST_ID_HEADER])
+        .max_age(Duration::from_secs(10 * 60))
+}
+
+fn security_headers(config: &Config) -> Vec<(HeaderName, HeaderValue)> {
+    let mut headers = vec![
+        (
+            header::X_CONTENT_TYPE_OPTIONS,
+            HeaderValue::from_static("nosniff"),
+        ),
+        (
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static("frame-ancestors 'none'"),
+        ),
+        // For browsers that predate frame-ancestors.
+        (header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+        (
+            header::REFERRER_POLICY,
+            HeaderValue::from_static("no-referrer"),
+        ),
+    ];
+    if config.http.hsts_max_age_secs > 0 {
+        let value = format!(
+            "max-age={}; includeSubDomains",
+            config.http.hsts_max_age_secs
+        );
+        headers.push((
+            header::STRICT_TRANSPORT_SECURITY,
+            HeaderValue::from_str(&value).unwrap(),
+        ));
+    }
+    headers
+}
+
+/// Refuses request bodies larger than the route's limit with
+/// `413 Payload Too Large`.
+///
+/// A declared `Content-Length` over the limit is refused up front; other
+/// bodies are cut off once they pass it, which the extractors report as 413.
+pub async fn limit_body(
+    State(config): State<Arc<Config>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limit = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| config.body_limit(path.as_str()))
+        .unwrap_or(config.http.max_body_bytes);
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > limit as u64) {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+    let request = request.map(|body| Body::new(Limited::new(body, limit)));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes;
+    use tower::ServiceExt;
+
+    fn app(config: Config) -> Router {
+        let mut state = fakes::state();
+        state.config = Arc::new(Config {
+            auth: state.config.auth.clone(),
+            ..config
+        });
+        let (router, _) = crate::router(state.clone()).split_for_parts();
+        apply(router, &state.config)
+    }
+
+    fn signup(body: String, content_length: Option<usize>) -> Request<Body> {
+        let mut request =
+            Request::post("/api/v1/common/signup").header(header::CONTENT_TYPE, "application/json");
+        if let Some(length) = content_length {
+            request = request.header(header::CONTENT_LENGTH, length);
+        }
+        request.body(Body::from(body)).unwrap()
+    }
+
+    fn oversized_signup() -> String {
+        serde_json::json!({
+            "username": "alice",
+            "email_id": "alice@example.com",
+            "password": "x".repeat(2000),
+            "role": "User",
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn sets_security_headers() {
+        let response = app(Config::default())
+            .oneshot(Request::get("/healthz").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let headers = response.headers();
+        assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(
+            headers[header::CONTENT_SECURITY_POLICY],
+            "frame-ancestors 'none'"
+        );
+        assert_eq!(
+            headers[header::STRICT_TRANSPORT_SECURITY],
+            "max-age=31536000; includeSubDomains"
+        );
+    }
+
+    #[tokio::test]
+    async fn answers_preflights_for_allowed_origins_only() {
+        let mut config = Config::default();
+        config.cors.allowed_origins = vec!["https://play.example.com".to_string()];
+        let preflight = |origin: &str| {
+            Request::options("/api/v1/worlds/get_worlds")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let allowed = app(config.clone())
+            .oneshot(preflight("https://play.example.com"))
+            .await
+            .unwrap();
+        let refused = app(config)
+            .oneshot(preflight("https://evil.example.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://play.example.com"
+        );
+        assert!(
+            !refused
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_request_bodies_per_route() {
+        let mut config = Config::default();
+        config.http.max_body_bytes = 1024;
+        let body = oversized_signup();
+
+        let declared = app(config.clone())
+            .oneshot(signup(body.clone(), Some(body.len())))
+            .await
+            .unwrap();
+        let streamed = app(config.clone())
+            .oneshot(signup(body.clone(), None))
+            .await
+            .unwrap();
+        config
+            .http
+            .route_body_limits
+            .insert("/api/v1/common/signup".to_string(), 4096);
+        let raised = app(config).oneshot(signup(body, None)).await.unwrap();
+
+        assert_eq!(declared.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(streamed.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(raised.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn compresses_responses_when_accepted() {
+        let response = app(Config::default())
+            .oneshot(
+                Request::get("/metrics")
+                    .header(header::ACCEPT_ENCODING, "gzip")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    }
+}
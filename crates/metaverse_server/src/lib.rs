@@ -0,0 +1,196 @@
+//! HTTP handlers, middleware and routing for the metaverse API.
+
+use axum::{Router, extract::DefaultBodyLimit, middleware, routing::get};
+use config::AssetStorage;
+use openapi::ApiDoc;
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, router::UtoipaMethodRouterExt, routes};
+use utoipa_swagger_ui::SwaggerUi;
+mod admin_middleware;
+pub mod assets;
+mod auth_middleware;
+pub mod common;
+pub mod config;
+mod element;
+#[cfg(test)]
+mod fakes;
+pub mod layers;
+mod maps;
+pub mod metrics;
+mod openapi;
+pub mod realtime;
+pub mod shutdown;
+mod space;
+mod state;
+pub mod telemetry;
+pub mod tls;
+mod user;
+mod worlds;
+use admin_middleware::admin_middleware;
+use auth_middleware::auth_middleware;
+pub use state::AppState;
+// use maps::{create_map, get_map, get_maps};
+
+/// Every API route, together with the OpenAPI document generated from them.
+pub fn router(state: AppState) -> OpenApiRouter {
+    let mut common_routes = OpenApiRouter::new().routes(routes!(common::signin));
+    if state.config.features.signup {
+        common_routes = common_routes.routes(routes!(common::signup));
+    }
+    let common_routes = common_routes
+        .routes(
+            routes!(user::create_avatar).layer(middleware::from_fn_with_state(
+                state.clone(),
+                admin_middleware,
+            )),
+        )
+        .routes(
+            routes!(user::create_avatar_part).layer(middleware::from_fn_with_state(
+                state.clone(),
+                admin_middleware,
+            )),
+        )
+        .with_state(state.clone());
+
+    let user_routes = OpenApiRouter::new()
+        .routes(routes!(user::metadata))
+        .routes(routes!(user::get_avatars))
+        .routes(routes!(user::get_metadata_bulk))
+        .routes(routes!(user::get_avatar_parts))
+        .routes(routes!(user::set_avatar))
+        .routes(routes!(user::get_avatar))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state.clone());
+
+    let world_routes =
+        OpenApiRouter::new()
+            .routes(routes!(worlds::create_world::create_world).layer(
+                middleware::from_fn_with_state(state.clone(), admin_middleware),
+            ))
+            .routes(routes!(worlds::get_worlds::get_worlds))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone());
+
+    let space_routes =
+        OpenApiRouter::new()
+            .routes(routes!(space::create_space::create_space).layer(
+                middleware::from_fn_with_state(state.clone(), admin_middleware),
+            ))
+            .routes(routes!(space::delete_space::delete_space).layer(
+                middleware::from_fn_with_state(state.clone(), admin_middleware),
+            ))
+            .routes(routes!(space::get_space::get_space))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state.clone());
+
+    let map_routes = OpenApiRouter::new()
+        .routes(
+            routes!(maps::create_maps::create_map).layer(middleware::from_fn_with_state(
+                state.clone(),
+                admin_middleware,
+            )),
+        )
+        .routes(routes!(maps::get_map::get_map))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        // .route("/get_maps", post(get_maps))
+        .with_state(state.clone());
+
+    let element_routes = OpenApiRouter::new()
+        .routes(routes!(element::element_templates::create_element_template))
+        .routes(routes!(element::element_templates::update_element_template))
+        .routes(routes!(
+            element::template_revisions::preview_template_revision
+        ))
+        .routes(routes!(element::template_revisions::pin_template_revision))
+        .routes(routes!(element::space_elements::create_space_elements))
+        .routes(routes!(element::map_elements::create_map_elements))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            admin_middleware,
+        ))
+        .with_state(state.clone());
+
+    let realtime_routes = OpenApiRouter::new()
+        .routes(routes!(realtime::connect))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state.clone());
+
+    let asset_routes = OpenApiRouter::new()
+        .routes(routes!(assets::upload_asset))
+        .routes(routes!(assets::get_asset))
+        .routes(routes!(assets::get_asset_references))
+        .routes(routes!(assets::delete_asset))
+        .routes(
+            routes!(assets::storage_report).layer(middleware::from_fn_with_state(
+                state.clone(),
+                admin_middleware,
+            )),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state.clone());
+
+    let api_routes = OpenApiRouter::new()
+        .nest("/common", common_routes)
+        .nest("/user", user_routes)
+        .nest("/map", map_routes)
+        .nest("/element", element_routes)
+        .nest("/space", space_routes)
+        .nest("/worlds", world_routes)
+        .nest("/realtime", realtime_routes)
+        .nest("/assets", asset_routes);
+    //
+    //
+    // ;
+
+    // .nest("/admin", admin_routes)
+
+    // `limit_body` replaces axum's fixed default limit with the configured ones.
+    let api_routes = api_routes
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            layers::limit_body,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            metrics::track_requests,
+        ))
+        .layer(DefaultBodyLimit::disable());
+
+    // Probes for orchestrators and Prometheus; not part of the API document.
+    let mut ops_routes = OpenApiRouter::new()
+        .route("/healthz", get(metrics::healthz))
+        .route("/readyz", get(metrics::readyz))
+        .route("/metrics", get(metrics::metrics));
+    // Files kept by other backends are downloaded from the backend itself.
+    if state.config.assets.storage == AssetStorage::Local {
+        ops_routes = ops_routes.route("/assets/{key}", get(assets::serve_asset));
+    }
+    let ops_routes = ops_routes.with_state(state);
+
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .nest("/api/v1/", api_routes)
+        .merge(ops_routes)
+        .layer(middleware::from_fn(telemetry::request_span))
+}
+
+pub fn with_docs(api: Router, openapi: utoipa::openapi::OpenApi) -> Router {
+    api.merge(SwaggerUi::new("/api/v1/docs").url("/api/v1/openapi.json", openapi))
+}
@@ -0,0 +1,28 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::maps::{CreateMapPayload, CreateMapResponse};
+use metaverse_core::repo::MapRepo;
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    post,
+    path = "/create",
+    tag = "map",
+    request_body = CreateMapPayload,
+    responses((status = 200, description = "Map created", body = CreateMapResponse)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_map(
+    State(maps): State<Arc<dyn MapRepo>>,
+    Json(payload): Json<CreateMapPayload>,
+) -> Result<Json<CreateMapResponse>, StatusCode> {
+    let result = maps.create(&payload).await;
+
+    match result {
+        Ok(map_id) => Ok(Json(CreateMapResponse { map_id })),
+        Err(e) => {
+            error!("Error creating space: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
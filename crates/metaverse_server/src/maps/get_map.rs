@@ -0,0 +1,29 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::maps::{GetMapPayload, GetMapResponse};
+use metaverse_core::repo::{MapRepo, RepoError};
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    post,
+    path = "/get_map",
+    tag = "map",
+    request_body = GetMapPayload,
+    responses((status = 200, description = "The requested map", body = GetMapResponse)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_map(
+    State(maps): State<Arc<dyn MapRepo>>,
+    Json(payload): Json<GetMapPayload>,
+) -> Result<Json<GetMapResponse>, StatusCode> {
+    let result = maps.get(payload.map_id).await;
+
+    match result {
+        Ok(result) => Ok(Json(result)),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error getting space: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
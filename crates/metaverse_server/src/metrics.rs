@@ -0,0 +1,238 @@
+//! Prometheus metrics and the `/healthz`, `/readyz` and `/metrics` probes.
+
+use crate::realtime::Hub;
+use axum::{
+    Json,
+    extract::{MatchedPath, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metaverse_core::repo::HealthRepo;
+use opentelemetry::trace::TraceContextExt;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use serde_json::json;
+use std::{sync::Arc, time::Instant};
+use tracing::{Span, error};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Why a request was refused by authentication.
+#[derive(Debug, Clone, Copy)]
+pub enum AuthFailure {
+    MissingToken,
+    InvalidToken,
+    NotAdmin,
+    BadCredentials,
+    Disabled,
+}
+
+impl AuthFailure {
+    fn as_str(self) -> &'static str {
+        match self {
+            AuthFailure::MissingToken => "missing_token",
+            AuthFailure::InvalidToken => "invalid_token",
+            AuthFailure::NotAdmin => "not_admin",
+            AuthFailure::BadCredentials => "bad_credentials",
+            AuthFailure::Disabled => "disabled",
+        }
+    }
+}
+
+/// Every metric the server exports, registered in its own registry so each
+/// `AppState` (and each test) counts separately.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    auth_failures: IntCounterVec,
+    realtime_connections: IntGauge,
+    space_users: IntGaugeVec,
+    db_connections: IntGaugeVec,
+    db_max_connections: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("metaverse".to_string()), None).unwrap();
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time to produce an HTTP response",
+            ),
+            &["method", "route"],
+        )
+        .unwrap();
+        let auth_failures = IntCounterVec::new(
+            Opts::new("auth_failures_total", "Rejected sign-ins and tokens"),
+            &["reason"],
+        )
+        .unwrap();
+        let realtime_connections = IntGauge::new(
+            "realtime_connections",
+            "Open realtime WebSocket connections",
+        )
+        .unwrap();
+        let space_users = IntGaugeVec::new(
+            Opts::new("space_users", "Users present in each space"),
+            &["space_id"],
+        )
+        .unwrap();
+        let db_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .unwrap();
+        let db_max_connections =
+            IntGauge::new("db_pool_max_connections", "Database pool size limit").unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+        registry.register(Box::new(auth_failures.clone())).unwrap();
+        registry
+            .register(Box::new(realtime_connections.clone()))
+            .unwrap();
+        registry.register(Box::new(space_users.clone())).unwrap();
+        registry.register(Box::new(db_connections.clone())).unwrap();
+        registry
+            .register(Box::new(db_max_connections.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_duration,
+            auth_failures,
+            realtime_connections,
+            space_users,
+            db_connections,
+            db_max_connections,
+        }
+    }
+
+    pub fn auth_failure(&self, reason: AuthFailure) {
+        self.auth_failures
+            .with_label_values(&[reason.as_str()])
+            .inc();
+    }
+
+    // Gauges are sampled when scraped rather than tracked on every change.
+    fn sample(&self, hub: &Hub, health: &dyn HealthRepo) {
+        self.realtime_connections.set(hub.connection_count() as i64);
+        self.space_users.reset();
+        for (space_id, users) in hub.space_occupancy() {
+            self.space_users
+                .with_label_values(&[&space_id.to_string()])
+                .set(users as i64);
+        }
+        let pool = health.pool_stats();
+        self.db_connections
+            .with_label_values(&["idle"])
+            .set(pool.idle as i64);
+        self.db_connections
+            .with_label_values(&["in_use"])
+            .set(pool.size.saturating_sub(pool.idle) as i64);
+        self.db_max_connections.set(pool.max as i64);
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counts and times each request under its route template, so
+/// `/space/get_space` is one series however many spaces there are.
+pub async fn track_requests(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().to_string();
+    // The request span is opened before routing, so it learns the route here.
+    let span = Span::current();
+    span.record("route", route.as_str());
+    span.context()
+        .span()
+        .update_name(format!("{method} {route}"));
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    metrics
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    metrics
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    response
+}
+
+/// Liveness: the process is up and serving requests.
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Readiness: the database answers and has every migration applied.
+pub async fn readyz(State(health): State<Arc<dyn HealthRepo>>) -> Response {
+    if let Err(err) = health.ping().await {
+        error!("Readiness check could not reach the database: {:?}", err);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "unavailable", "database": "unreachable" })),
+        )
+            .into_response();
+    }
+    match health.pending_migrations().await {
+        Ok(pending) if pending.is_empty() => {
+            Json(json!({ "status": "ready", "database": "ok" })).into_response()
+        }
+        Ok(pending) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "status": "unavailable",
+                "database": "ok",
+                "pending_migrations": pending,
+            })),
+        )
+            .into_response(),
+        Err(err) => {
+            error!("Readiness check could not read migrations: {:?}", err);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "status": "unavailable", "database": "ok" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub async fn metrics(
+    State(metrics): State<Arc<Metrics>>,
+    State(hub): State<Arc<Hub>>,
+    State(health): State<Arc<dyn HealthRepo>>,
+) -> Response {
+    metrics.sample(&hub, health.as_ref());
+    let mut body = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(err) = encoder.encode(&metrics.registry.gather(), &mut body) {
+        error!("Failed to encode metrics: {}", err);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    ([(header::CONTENT_TYPE, encoder.format_type())], body).into_response()
+}
@@ -0,0 +1,173 @@
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Metaverse API"),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "common", description = "Sign up, sign in and avatar management"),
+        (name = "user", description = "Per-user metadata"),
+        (name = "worlds", description = "Worlds"),
+        (name = "map", description = "Maps inside a world"),
+        (name = "space", description = "Spaces inside a map"),
+        (name = "element", description = "Element templates and placed elements"),
+        (name = "realtime", description = "WebSocket presence within a space"),
+        (name = "assets", description = "Uploaded images, models and audio")
+    )
+)]
+pub struct ApiDoc;
+
+// Registers the JWT issued by /common/signin so protected paths can reference it.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use tower::ServiceExt;
+    use utoipa::openapi::OpenApi;
+
+    fn spec_and_router() -> (axum::Router, OpenApi) {
+        crate::router(crate::fakes::state()).split_for_parts()
+    }
+
+    fn operations(spec: &OpenApi) -> Vec<(Method, String)> {
+        let mut operations = Vec::new();
+        for (path, item) in &spec.paths.paths {
+            let methods = [
+                (&item.get, Method::GET),
+                (&item.post, Method::POST),
+                (&item.put, Method::PUT),
+                (&item.delete, Method::DELETE),
+                (&item.patch, Method::PATCH),
+            ];
+            for (operation, method) in methods {
+                if operation.is_some() {
+                    operations.push((method, path.clone()));
+                }
+            }
+        }
+        operations.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.as_str().cmp(b.0.as_str())));
+        operations
+    }
+
+    #[tokio::test]
+    async fn spec_lists_every_route() {
+        let (_, spec) = spec_and_router();
+        let documented: Vec<(String, String)> = operations(&spec)
+            .into_iter()
+            .map(|(method, path)| (method.to_string(), path))
+            .collect();
+
+        let expected = [
+            ("POST", "/api/v1/assets/delete_asset"),
+            ("POST", "/api/v1/assets/get_asset"),
+            ("POST", "/api/v1/assets/get_asset_references"),
+            ("GET", "/api/v1/assets/storage_report"),
+            ("POST", "/api/v1/assets/upload"),
+            ("POST", "/api/v1/common/create_avatar"),
+            ("POST", "/api/v1/common/create_avatar_part"),
+            ("POST", "/api/v1/common/signin"),
+            ("POST", "/api/v1/common/signup"),
+            ("POST", "/api/v1/element/create_map_element"),
+            ("POST", "/api/v1/element/create_new_element"),
+            ("POST", "/api/v1/element/create_space_element"),
+            ("POST", "/api/v1/element/pin_template_revision"),
+            ("POST", "/api/v1/element/preview_template_revision"),
+            ("POST", "/api/v1/element/update_element"),
+            ("POST", "/api/v1/map/create"),
+            ("POST", "/api/v1/map/get_map"),
+            ("GET", "/api/v1/realtime/ws"),
+            ("POST", "/api/v1/space/create"),
+            ("POST", "/api/v1/space/delete_space"),
+            ("POST", "/api/v1/space/get_space"),
+            ("GET", "/api/v1/user/avatar_parts"),
+            ("GET", "/api/v1/user/avatars"),
+            ("POST", "/api/v1/user/get_avatar"),
+            ("POST", "/api/v1/user/metadata"),
+            ("POST", "/api/v1/user/metadata/bulk"),
+            ("POST", "/api/v1/user/set_avatar"),
+            ("POST", "/api/v1/worlds/create"),
+            ("GET", "/api/v1/worlds/get_worlds"),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(method, path)| (method.to_string(), path.to_string()))
+            .collect();
+
+        assert_eq!(documented, expected);
+    }
+
+    #[tokio::test]
+    async fn every_documented_operation_is_routed() {
+        let (router, spec) = spec_and_router();
+
+        for (method, path) in operations(&spec) {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(method.clone())
+                        .uri(&path)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_ne!(
+                response.status(),
+                StatusCode::NOT_FOUND,
+                "{method} {path} is documented but not routed"
+            );
+            assert_ne!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{method} {path} is routed with a different method"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_the_generated_spec() {
+        let (router, spec) = spec_and_router();
+        let app = crate::with_docs(router, spec.clone());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let served: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(served, serde_json::to_value(&spec).unwrap());
+    }
+}
@@ -0,0 +1,387 @@
+use metaverse_core::{
+    realtime::{ElementState, PresentUser, ServerMessage},
+    repo::{SessionPosition, SessionRepo},
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+pub type ConnectionId = u64;
+
+/// What a connection's writer task should do next.
+#[derive(Debug)]
+pub enum Outgoing {
+    Message(ServerMessage),
+    /// Send a close frame telling the client the server is restarting.
+    Restart,
+    /// Send a close frame telling the client its session was ended.
+    Ended,
+}
+
+/// Where a connection is, used to size moves against the space.
+#[derive(Debug, Clone, Copy)]
+pub struct Presence {
+    pub space_id: i32,
+    pub session_id: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+struct Connection {
+    user_id: i32,
+    outbox: mpsc::UnboundedSender<Outgoing>,
+    presence: Option<Presence>,
+    position: PresentUser,
+    /// Moved since the last flush.
+    dirty: bool,
+}
+
+/// Tracks every realtime connection, relays presence within a space and
+/// buffers position updates so they reach `user_sessions` in batches rather
+/// than once per move.
+pub struct Hub {
+    sessions: Arc<dyn SessionRepo>,
+    connections: Mutex<HashMap<ConnectionId, Connection>>,
+    next_id: Mutex<ConnectionId>,
+    closing: AtomicBool,
+}
+
+impl Hub {
+    pub fn new(sessions: Arc<dyn SessionRepo>) -> Self {
+        Self {
+            sessions,
+            connections: Mutex::new(HashMap::new()),
+            next_id: Mutex::new(0),
+            closing: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing.load(Ordering::SeqCst)
+    }
+
+    pub fn sessions(&self) -> &Arc<dyn SessionRepo> {
+        &self.sessions
+    }
+
+    /// Registers a connection, or returns `None` once shutdown has started.
+    pub fn connect(
+        &self,
+        user_id: i32,
+    ) -> Option<(ConnectionId, mpsc::UnboundedReceiver<Outgoing>)> {
+        if self.is_closing() {
+            return None;
+        }
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let (outbox, inbox) = mpsc::unbounded_channel();
+        self.connections.lock().unwrap().insert(
+            id,
+            Connection {
+                user_id,
+                outbox,
+                presence: None,
+                position: PresentUser {
+                    user_id,
+                    x: 0,
+                    y: 0,
+                    rotation: 0,
+                },
+                dirty: false,
+            },
+        );
+        Some((id, inbox))
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
+
+    /// Number of users present in each occupied space.
+    pub fn space_occupancy(&self) -> Vec<(i32, usize)> {
+        let mut occupancy: HashMap<i32, usize> = HashMap::new();
+        for connection in self.connections.lock().unwrap().values() {
+            if let Some(presence) = connection.presence {
+                *occupancy.entry(presence.space_id).or_default() += 1;
+            }
+        }
+        occupancy.into_iter().collect()
+    }
+
+    pub fn user_id(&self, id: ConnectionId) -> Option<i32> {
+        self.connections.lock().unwrap().get(&id).map(|c| c.user_id)
+    }
+
+    pub fn presence(&self, id: ConnectionId) -> Option<Presence> {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(&id)
+            .and_then(|c| c.presence)
+    }
+
+    pub fn position(&self, id: ConnectionId) -> Option<PresentUser> {
+        self.connections
+            .lock()
+            .unwrap()
+            .get(&id)
+            .filter(|c| c.presence.is_some())
+            .map(|c| c.position)
+    }
+
+    pub fn send(&self, id: ConnectionId, message: ServerMessage) {
+        if let Some(connection) = self.connections.lock().unwrap().get(&id) {
+            let _ = connection.outbox.send(Outgoing::Message(message));
+        }
+    }
+
+    /// Sends `message` to everyone in the space.
+    pub fn publish(&self, space_id: i32, message: ServerMessage) {
+        let connections = self.connections.lock().unwrap();
+        for (_, connection) in occupants(&connections, space_id) {
+            let _ = connection.outbox.send(Outgoing::Message(message.clone()));
+        }
+    }
+
+    /// Places the connection in a space, tells everyone already there and
+    /// replies with the current occupants and the element states given.
+    pub fn join(
+        &self,
+        id: ConnectionId,
+        presence: Presence,
+        x: i32,
+        y: i32,
+        elements: Vec<ElementState>,
+    ) {
+        let mut connections = self.connections.lock().unwrap();
+        let Some(connection) = connections.get_mut(&id) else {
+            return;
+        };
+        connection.presence = Some(presence);
+        connection.position.x = x;
+        connection.position.y = y;
+        connection.position.rotation = 0;
+        connection.dirty = false;
+        let you = connection.position;
+
+        let users = occupants(&connections, presence.space_id)
+            .filter(|(other, _)| **other != id)
+            .map(|(_, c)| c.position)
+            .collect();
+        broadcast(
+            &connections,
+            presence.space_id,
+            id,
+            ServerMessage::UserJoined(you),
+        );
+        if let Some(connection) = connections.get(&id) {
+            let _ = connection
+                .outbox
+                .send(Outgoing::Message(ServerMessage::Joined {
+                    space_id: presence.space_id,
+                    you,
+                    users,
+                    elements,
+                }));
+        }
+    }
+
+    /// Records a move and relays it to the rest of the space.
+    pub fn move_to(&self, id: ConnectionId, x: i32, y: i32, rotation: i32) {
+        let mut connections = self.connections.lock().unwrap();
+        let Some(connection) = connections.get_mut(&id) else {
+            return;
+        };
+        let Some(presence) = connection.presence else {
+            return;
+        };
+        connection.position.x = x;
+        connection.position.y = y;
+        connection.position.rotation = rotation;
+        connection.dirty = true;
+        let moved = connection.position;
+        broadcast(
+            &connections,
+            presence.space_id,
+            id,
+            ServerMessage::UserMoved(moved),
+        );
+    }
+
+    /// Moves the connection without it asking to, telling everyone in the
+    /// space including itself.
+    pub fn teleport(&self, id: ConnectionId, x: i32, y: i32) {
+        let mut connections = self.connections.lock().unwrap();
+        let Some(connection) = connections.get_mut(&id) else {
+            return;
+        };
+        let Some(presence) = connection.presence else {
+            return;
+        };
+        connection.position.x = x;
+        connection.position.y = y;
+        connection.dirty = true;
+        let moved = connection.position;
+        drop(connections);
+        self.publish(presence.space_id, ServerMessage::UserMoved(moved));
+    }
+
+    /// Takes the connection out of its space. Returns the session to close
+    /// and its final position if it moved since the last flush.
+    pub fn leave(&self, id: ConnectionId) -> Option<(i32, Option<SessionPosition>)> {
+        let mut connections = self.connections.lock().unwrap();
+        let connection = connections.get_mut(&id)?;
+        let presence = connection.presence.take()?;
+        let unsaved = std::mem::take(&mut connection.dirty)
+            .then(|| session_position(presence.session_id, connection.position));
+        let user_id = connection.user_id;
+        broadcast(
+            &connections,
+            presence.space_id,
+            id,
+            ServerMessage::UserLeft { user_id },
+        );
+        Some((presence.session_id, unsaved))
+    }
+
+    /// Leaves the current space and forgets the connection.
+    pub fn disconnect(&self, id: ConnectionId) -> Option<(i32, Option<SessionPosition>)> {
+        let left = self.leave(id);
+        self.connections.lock().unwrap().remove(&id);
+        left
+    }
+
+    /// Writes every position that changed since the last flush.
+    pub async fn flush(&self) {
+        let positions: Vec<SessionPosition> = {
+            let mut connections = self.connections.lock().unwrap();
+            connections
+                .values_mut()
+                .filter_map(|c| {
+                    let presence = c.presence?;
+                    std::mem::take(&mut c.dirty)
+                        .then(|| session_position(presence.session_id, c.position))
+                })
+                .collect()
+        };
+        if let Err(err) = self.sessions.save_positions(&positions).await {
+            error!("Failed to save {} positions: {:?}", positions.len(), err);
+        }
+    }
+
+    /// Disconnects clients whose session was closed outside this server,
+    /// such as by `metaverse-admin`.
+    pub async fn evict_closed(&self) {
+        let session_ids: Vec<i32> = {
+            let connections = self.connections.lock().unwrap();
+            connections
+                .values()
+                .filter_map(|c| c.presence.map(|p| p.session_id))
+                .collect()
+        };
+        let closed = match self.sessions.closed(&session_ids).await {
+            Ok(closed) => closed,
+            Err(err) => {
+                error!("Failed to check for closed sessions: {:?}", err);
+                return;
+            }
+        };
+        if closed.is_empty() {
+            return;
+        }
+        info!(
+            "Disconnecting {} clients with closed sessions",
+            closed.len()
+        );
+        let connections = self.connections.lock().unwrap();
+        for connection in connections.values() {
+            if connection
+                .presence
+                .is_some_and(|p| closed.contains(&p.session_id))
+            {
+                let _ = connection.outbox.send(Outgoing::Ended);
+            }
+        }
+    }
+
+    /// Flushes positions and evicts closed sessions every `interval` until
+    /// shutdown starts.
+    pub async fn run_flusher(self: Arc<Self>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        while !self.is_closing() {
+            ticker.tick().await;
+            self.flush().await;
+            self.evict_closed().await;
+        }
+    }
+
+    /// Stops accepting connections and asks every client to reconnect
+    /// elsewhere. Their sessions stay open until [`Hub::close_sessions`].
+    pub fn begin_shutdown(&self) {
+        self.closing.store(true, Ordering::SeqCst);
+        let connections = self.connections.lock().unwrap();
+        info!("Disconnecting {} realtime clients", connections.len());
+        for connection in connections.values() {
+            let _ = connection
+                .outbox
+                .send(Outgoing::Message(ServerMessage::Shutdown));
+            let _ = connection.outbox.send(Outgoing::Restart);
+        }
+    }
+
+    /// Saves outstanding positions and marks every open session inactive.
+    pub async fn close_sessions(&self) {
+        self.flush().await;
+        let session_ids: Vec<i32> = {
+            let mut connections = self.connections.lock().unwrap();
+            connections
+                .drain()
+                .filter_map(|(_, c)| c.presence.map(|p| p.session_id))
+                .collect()
+        };
+        info!("Closing {} realtime sessions", session_ids.len());
+        if let Err(err) = self.sessions.close(&session_ids).await {
+            error!("Failed to close sessions: {:?}", err);
+        }
+    }
+}
+
+fn occupants(
+    connections: &HashMap<ConnectionId, Connection>,
+    space_id: i32,
+) -> impl Iterator<Item = (&ConnectionId, &Connection)> {
+    connections
+        .iter()
+        .filter(move |(_, c)| c.presence.is_some_and(|p| p.space_id == space_id))
+}
+
+fn broadcast(
+    connections: &HashMap<ConnectionId, Connection>,
+    space_id: i32,
+    from: ConnectionId,
+    message: ServerMessage,
+) {
+    for (_, connection) in occupants(connections, space_id).filter(|(id, _)| **id != from) {
+        let _ = connection.outbox.send(Outgoing::Message(message.clone()));
+    }
+}
+
+fn session_position(session_id: i32, position: PresentUser) -> SessionPosition {
+    SessionPosition {
+        session_id,
+        x: position.x,
+        y: position.y,
+        rotation: position.rotation,
+    }
+}
@@ -0,0 +1,151 @@
+//! `interact`: using an element in the current space, as its template's
+//! [`Interaction`] says.
+
+use metaverse_core::{
+    element::{ElementType, PlacedElement},
+    interaction::{Action, Interaction},
+    realtime::{ElementState, PresentUser, ServerMessage},
+    repo::RepoError,
+    schema,
+};
+use serde_json::{Value, json};
+use tracing::{error, warn};
+
+use super::{Context, error_reply, hub::ConnectionId};
+
+pub(super) async fn interact(
+    context: &Context,
+    id: ConnectionId,
+    element_id: i32,
+    version: Option<i32>,
+    state: Option<Value>,
+) {
+    let hub = context.hub.as_ref();
+    let (Some(presence), Some(user)) = (hub.presence(id), hub.position(id)) else {
+        return error_reply(hub, id, "join a space before interacting");
+    };
+    let element = match context.elements.space_element(element_id).await {
+        Ok(element) if element.space_id == presence.space_id => element,
+        Ok(_) | Err(RepoError::NotFound) => return error_reply(hub, id, "element not found"),
+        Err(err) => {
+            error!("Error loading element {}: {:?}", element_id, err);
+            return error_reply(hub, id, "could not use element");
+        }
+    };
+    let Some(interaction) = interaction(&element) else {
+        return error_reply(hub, id, "element is not interactive");
+    };
+    let range = interaction.range.unwrap_or(context.interaction_range);
+    if distance(&element, user) > range {
+        return error_reply(hub, id, "too far away to use element");
+    }
+
+    match interaction.action {
+        Action::OpenUrl { url } => hub.send(id, ServerMessage::OpenUrl { element_id, url }),
+        Action::ShowText { title, text } => hub.send(
+            id,
+            ServerMessage::ShowText {
+                element_id,
+                title,
+                text,
+            },
+        ),
+        Action::Teleport { x, y } => {
+            if !(0..presence.width).contains(&x) || !(0..presence.height).contains(&y) {
+                return error_reply(hub, id, "teleport target is outside the space");
+            }
+            hub.teleport(id, x, y);
+        }
+        Action::Toggle => {
+            let on = element
+                .state
+                .as_ref()
+                .and_then(|state| state["on"].as_bool())
+                .unwrap_or(false);
+            change_state(context, id, &element, version, json!({ "on": !on })).await;
+        }
+        Action::SetState => {
+            let Some(state) = state else {
+                return error_reply(hub, id, "set_state needs a state");
+            };
+            if let Err(err) = schema::check_state(element.properties_schema.as_ref(), &state) {
+                return error_reply(hub, id, &err.to_string());
+            }
+            change_state(context, id, &element, version, state).await;
+        }
+        Action::StartCall { room } => {
+            let room =
+                room.unwrap_or_else(|| format!("space-{}-element-{element_id}", presence.space_id));
+            hub.publish(
+                presence.space_id,
+                ServerMessage::CallStarted {
+                    element_id,
+                    room,
+                    user_id: user.user_id,
+                },
+            );
+        }
+    }
+}
+
+/// Saves an element's new state, unless it has changed since the client or
+/// this interaction last looked, and tells the space.
+async fn change_state(
+    context: &Context,
+    id: ConnectionId,
+    element: &PlacedElement,
+    seen: Option<i32>,
+    state: Value,
+) {
+    let hub = context.hub.as_ref();
+    let stale = || error_reply(hub, id, "element was changed by someone else");
+    if seen.is_some_and(|seen| seen != element.state_version) {
+        return stale();
+    }
+    match context
+        .elements
+        .set_element_state(element.id, element.state_version, &state)
+        .await
+    {
+        Ok(version) => hub.publish(
+            element.space_id,
+            ServerMessage::ElementChanged(ElementState {
+                element_id: element.id,
+                state,
+                version,
+            }),
+        ),
+        Err(RepoError::Conflict) => stale(),
+        Err(err) => {
+            error!("Error saving state of element {}: {:?}", element.id, err);
+            error_reply(hub, id, "could not use element");
+        }
+    }
+}
+
+/// What the element does, if it is interactive and says so validly.
+fn interaction(element: &PlacedElement) -> Option<Interaction> {
+    if element.element_type != ElementType::Interactive {
+        return None;
+    }
+    match Interaction::parse(element.interaction_data.as_ref()?) {
+        Ok(interaction) => Some(interaction),
+        Err(err) => {
+            warn!(
+                "Element {} has invalid interaction_data: {}",
+                element.id, err
+            );
+            None
+        }
+    }
+}
+
+/// Tiles between the user and the nearest tile the element covers, counting
+/// diagonal steps as one.
+fn distance(element: &PlacedElement, user: PresentUser) -> u32 {
+    let gap = |at: i32, start: i32, size: i32| {
+        let end = start + size.max(1) - 1;
+        (start - at).max(at - end).max(0).unsigned_abs()
+    };
+    gap(user.x, element.x, element.width).max(gap(user.y, element.y, element.height))
+}
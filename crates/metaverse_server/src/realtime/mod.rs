@@ -0,0 +1,239 @@
+//! Realtime presence over WebSocket: users join a space, see who else is
+//! there, receive each other's moves and use the elements around them.
+
+mod hub;
+mod interact;
+
+pub use hub::Hub;
+
+use crate::{auth_middleware::Claims, config::Config};
+use axum::{
+    Extension,
+    extract::{
+        State, WebSocketUpgrade,
+        ws::{CloseFrame, Message, WebSocket, close_code},
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures_util::{SinkExt, StreamExt};
+use hub::{ConnectionId, Outgoing, Presence};
+use metaverse_core::{
+    realtime::{ClientMessage, ServerMessage},
+    repo::{ElementRepo, RepoError, SessionPosition, SpaceRepo},
+};
+use std::sync::Arc;
+use tracing::{error, warn};
+
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "realtime",
+    description = "Upgrades to a WebSocket carrying `ClientMessage` and `ServerMessage` JSON frames.",
+    responses(
+        (status = 101, description = "Switched to WebSocket"),
+        (status = 503, description = "Server is shutting down")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn connect(
+    ws: WebSocketUpgrade,
+    State(hub): State<Arc<Hub>>,
+    State(spaces): State<Arc<dyn SpaceRepo>>,
+    State(elements): State<Arc<dyn ElementRepo>>,
+    State(config): State<Arc<Config>>,
+    Extension(claims): Extension<Arc<Claims>>,
+) -> Response {
+    let Ok(user_id) = claims.sub.parse::<i32>() else {
+        error!("Error getting user id from {:?}", claims.sub);
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    if hub.is_closing() {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+    let context = Context {
+        hub,
+        spaces,
+        elements,
+        interaction_range: config.realtime.interaction_range,
+    };
+    ws.on_upgrade(move |socket| run(socket, context, user_id))
+}
+
+/// What a connection needs besides its socket.
+struct Context {
+    hub: Arc<Hub>,
+    spaces: Arc<dyn SpaceRepo>,
+    elements: Arc<dyn ElementRepo>,
+    /// Tiles from an element within which it can be used, unless it says.
+    interaction_range: u32,
+}
+
+async fn run(socket: WebSocket, context: Context, user_id: i32) {
+    let hub = context.hub.clone();
+    let Some((id, mut outbox)) = hub.connect(user_id) else {
+        return;
+    };
+    let (mut sink, mut stream) = socket.split();
+
+    let mut writer = tokio::spawn(async move {
+        while let Some(outgoing) = outbox.recv().await {
+            let frame = match outgoing {
+                Outgoing::Message(message) => match serde_json::to_string(&message) {
+                    Ok(text) => Message::Text(text.into()),
+                    Err(err) => {
+                        error!("Failed to encode {:?}: {}", message, err);
+                        continue;
+                    }
+                },
+                Outgoing::Restart => {
+                    let _ = sink
+                        .send(Message::Close(Some(CloseFrame {
+                            code: close_code::RESTART,
+                            reason: "server restarting".into(),
+                        })))
+                        .await;
+                    break;
+                }
+                Outgoing::Ended => {
+                    let _ = sink
+                        .send(Message::Close(Some(CloseFrame {
+                            code: close_code::POLICY,
+                            reason: "session ended by an administrator".into(),
+                        })))
+                        .await;
+                    break;
+                }
+            };
+            if sink.send(frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Read until the client goes away or the writer has said goodbye.
+    let mut writer_done = false;
+    loop {
+        tokio::select! {
+            frame = stream.next() => match frame {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(message) => handle(&context, id, message).await,
+                    Err(err) => error_reply(&hub, id, &format!("invalid message: {err}")),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = &mut writer => {
+                writer_done = true;
+                break;
+            }
+        }
+    }
+
+    // During shutdown the hub saves and closes every session at once.
+    if !hub.is_closing()
+        && let Some(left) = hub.disconnect(id)
+    {
+        end_session(&hub, left).await;
+    }
+    if !writer_done {
+        let _ = writer.await;
+    }
+}
+
+async fn handle(context: &Context, id: ConnectionId, message: ClientMessage) {
+    let hub = context.hub.as_ref();
+    match message {
+        ClientMessage::Join { space_id } => {
+            if let Some(left) = hub.leave(id) {
+                end_session(hub, left).await;
+            }
+            join(context, id, space_id).await;
+        }
+        ClientMessage::Move { x, y, rotation } => {
+            let Some(presence) = hub.presence(id) else {
+                return error_reply(hub, id, "join a space before moving");
+            };
+            if !(0..presence.width).contains(&x) || !(0..presence.height).contains(&y) {
+                return error_reply(hub, id, "position is outside the space");
+            }
+            hub.move_to(id, x, y, rotation);
+        }
+        ClientMessage::Interact {
+            element_id,
+            version,
+            state,
+        } => {
+            interact::interact(context, id, element_id, version, state).await;
+        }
+        ClientMessage::Leave => {
+            if let Some(left) = hub.leave(id) {
+                end_session(hub, left).await;
+            }
+        }
+    }
+}
+
+async fn join(context: &Context, id: ConnectionId, space_id: i32) {
+    let hub = context.hub.as_ref();
+    let Some(user_id) = hub.user_id(id) else {
+        return;
+    };
+    let space = match context.spaces.get(space_id).await {
+        Ok(space) => space,
+        Err(RepoError::NotFound) => return error_reply(hub, id, "space not found"),
+        Err(err) => {
+            error!("Error loading space {}: {:?}", space_id, err);
+            return error_reply(hub, id, "could not join space");
+        }
+    };
+    let elements = match context.elements.element_states(space_id).await {
+        Ok(elements) => elements,
+        Err(err) => {
+            error!(
+                "Error loading element states of space {}: {:?}",
+                space_id, err
+            );
+            return error_reply(hub, id, "could not join space");
+        }
+    };
+    let x = space.default_spawn_x.unwrap_or(0);
+    let y = space.default_spawn_y.unwrap_or(0);
+    let session_id = match hub.sessions().open(user_id, space_id, x, y).await {
+        Ok(session_id) => session_id,
+        Err(err) => {
+            error!("Error opening session for user {}: {:?}", user_id, err);
+            return error_reply(hub, id, "could not join space");
+        }
+    };
+    let presence = Presence {
+        space_id,
+        session_id,
+        width: space.width,
+        height: space.height,
+    };
+    hub.join(id, presence, x, y, elements);
+}
+
+async fn end_session(hub: &Hub, (session_id, unsaved): (i32, Option<SessionPosition>)) {
+    if let Some(position) = unsaved
+        && let Err(err) = hub.sessions().save_positions(&[position]).await
+    {
+        warn!(
+            "Failed to save final position of session {}: {:?}",
+            session_id, err
+        );
+    }
+    if let Err(err) = hub.sessions().close(&[session_id]).await {
+        warn!("Failed to close session {}: {:?}", session_id, err);
+    }
+}
+
+fn error_reply(hub: &Hub, id: ConnectionId, message: &str) {
+    hub.send(
+        id,
+        ServerMessage::Error {
+            message: message.to_string(),
+        },
+    );
+}
@@ -0,0 +1,27 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::repo::SpaceRepo;
+use metaverse_core::space::CreateSpacePayload;
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    post,
+    path = "/create",
+    tag = "space",
+    request_body = CreateSpacePayload,
+    responses((status = 201, description = "Space created")),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_space(
+    State(spaces): State<Arc<dyn SpaceRepo>>,
+    Json(payload): Json<CreateSpacePayload>,
+) -> Result<StatusCode, StatusCode> {
+    let response = spaces.create(&payload).await;
+    match response {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(e) => {
+            error!("Error creating spaces {e}");
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::repo::{RepoError, SpaceRepo};
+use metaverse_core::space::DeleteSpacePayload;
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    post,
+    path = "/delete_space",
+    tag = "space",
+    request_body = DeleteSpacePayload,
+    responses((status = 200, description = "Space deleted")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_space(
+    State(spaces): State<Arc<dyn SpaceRepo>>,
+    Json(payload): Json<DeleteSpacePayload>,
+) -> Result<StatusCode, StatusCode> {
+    let response = spaces.delete(payload.space_id).await;
+    match response {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Something went wrong {}", e);
+            Ok(StatusCode::BAD_REQUEST)
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::repo::{RepoError, SpaceRepo};
+use metaverse_core::space::{GetSpacePayload, GetSpaceResponse};
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    post,
+    path = "/get_space",
+    tag = "space",
+    request_body = GetSpacePayload,
+    responses(
+        (status = 200, description = "The requested space", body = GetSpaceResponse),
+        (status = 400, description = "Space could not be loaded")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_space(
+    State(spaces): State<Arc<dyn SpaceRepo>>,
+    Json(payload): Json<GetSpacePayload>,
+) -> Result<Json<GetSpaceResponse>, StatusCode> {
+    let response = spaces.get(payload.space_id).await;
+
+    match response {
+        Ok(response) => Ok(Json(response)),
+        Err(RepoError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Error getting space {}", e);
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes;
+
+    #[tokio::test]
+    async fn missing_space_is_not_found() {
+        let spaces = fakes::state().spaces;
+
+        let response = get_space(State(spaces), Json(GetSpacePayload { space_id: 7 })).await;
+
+        assert_eq!(response.err(), Some(StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{
+    assets::storage::{self, AssetStore},
+    config::Config,
+    metrics::Metrics,
+    realtime::Hub,
+};
+use axum::extract::FromRef;
+use metaverse_core::repo::{
+    AssetRepo, ElementRepo, HealthRepo, MapRepo, PgAssetRepo, PgElementRepo, PgHealthRepo,
+    PgMapRepo, PgSessionRepo, PgSpaceRepo, PgUserRepo, PgWorldRepo, SpaceRepo, UserRepo, WorldRepo,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Repositories and settings shared by the handlers. Each handler extracts
+/// only the part it needs, e.g. `State(worlds): State<Arc<dyn WorldRepo>>`.
+#[derive(Clone)]
+pub struct AppState {
+    pub users: Arc<dyn UserRepo>,
+    pub worlds: Arc<dyn WorldRepo>,
+    pub maps: Arc<dyn MapRepo>,
+    pub spaces: Arc<dyn SpaceRepo>,
+    pub elements: Arc<dyn ElementRepo>,
+    pub assets: Arc<dyn AssetRepo>,
+    /// Where uploaded files are kept.
+    pub storage: Arc<dyn AssetStore>,
+    pub health: Arc<dyn HealthRepo>,
+    pub realtime: Arc<Hub>,
+    pub metrics: Arc<Metrics>,
+    pub config: Arc<Config>,
+}
+
+impl AppState {
+    pub fn postgres(pool: PgPool, config: Arc<Config>) -> Self {
+        Self {
+            users: Arc::new(PgUserRepo::new(pool.clone())),
+            worlds: Arc::new(PgWorldRepo::new(pool.clone())),
+            maps: Arc::new(PgMapRepo::new(pool.clone())),
+            spaces: Arc::new(PgSpaceRepo::new(pool.clone())),
+            elements: Arc::new(PgElementRepo::new(pool.clone())),
+            assets: Arc::new(PgAssetRepo::new(pool.clone())),
+            storage: storage::from_config(&config.assets),
+            health: Arc::new(PgHealthRepo::new(pool.clone())),
+            realtime: Arc::new(Hub::new(Arc::new(PgSessionRepo::new(pool)))),
+            metrics: Arc::new(Metrics::new()),
+            config,
+        }
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn UserRepo> {
+    fn from_ref(state: &AppState) -> Self {
+        state.users.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn WorldRepo> {
+    fn from_ref(state: &AppState) -> Self {
+        state.worlds.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn MapRepo> {
+    fn from_ref(state: &AppState) -> Self {
+        state.maps.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn SpaceRepo> {
+    fn from_ref(state: &AppState) -> Self {
+        state.spaces.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn ElementRepo> {
+    fn from_ref(state: &AppState) -> Self {
+        state.elements.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn AssetRepo> {
+    fn from_ref(state: &AppState) -> Self {
+        state.assets.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn AssetStore> {
+    fn from_ref(state: &AppState) -> Self {
+        state.storage.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Hub> {
+    fn from_ref(state: &AppState) -> Self {
+        state.realtime.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn HealthRepo> {
+    fn from_ref(state: &AppState) -> Self {
+        state.health.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Metrics> {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
@@ -0,0 +1,303 @@
+//! Log output, trace export and the span wrapped around every request.
+
+use crate::config::{LogFormat, LoggingConfig, TraceExporter, TracingConfig};
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    Key, KeyValue, Value,
+    propagation::{Extractor, TextMapPropagator},
+    trace::{SpanId, Status, TracerProvider},
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    Resource,
+    error::{OTelSdkError, OTelSdkResult},
+    propagation::TraceContextPropagator,
+    trace::{SdkTracerProvider, SpanData, SpanExporter, Tracer},
+};
+use serde_json::{Map, json};
+use std::{
+    fs::{File, OpenOptions},
+    future::Future,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Instant, SystemTime},
+};
+use tracing::{Instrument, Subscriber, field, info, info_span, warn};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::{
+    EnvFilter, Layer, layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt,
+};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+#[derive(Debug, thiserror::Error)]
+pub enum TelemetryError {
+    #[error("could not open trace file {path}: {source}")]
+    TraceFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not set up the OTLP exporter: {0}")]
+    Otlp(#[from] opentelemetry_otlp::ExporterBuildError),
+}
+
+/// Keeps the trace pipeline alive. Call [`Telemetry::shutdown`] before
+/// exiting so spans still buffered are exported.
+#[must_use]
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider
+            && let Err(err) = provider.shutdown()
+        {
+            warn!("Failed to export the last spans: {}", err);
+        }
+    }
+}
+
+/// Installs the global subscriber. Filtering follows `RUST_LOG`, falling back
+/// to `info`, and applies to exported spans as well as logs.
+pub fn init(logging: &LoggingConfig, tracing: &TracingConfig) -> Result<Telemetry, TelemetryError> {
+    let provider = tracer_provider(tracing)?;
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let output = match logging.format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .with(provider.as_ref().map(trace_layer))
+        .init();
+    Ok(Telemetry { provider })
+}
+
+/// Builds the span pipeline for the configured exporter, or `None` when
+/// tracing is off.
+pub fn tracer_provider(
+    config: &TracingConfig,
+) -> Result<Option<SdkTracerProvider>, TelemetryError> {
+    let builder = SdkTracerProvider::builder().with_resource(
+        Resource::builder()
+            .with_service_name(config.service_name.clone())
+            .build(),
+    );
+    let provider = match config.exporter {
+        TraceExporter::None => return Ok(None),
+        TraceExporter::Otlp => builder.with_batch_exporter(
+            opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(&config.otlp_endpoint)
+                .build()?,
+        ),
+        TraceExporter::File => builder.with_batch_exporter(FileExporter::open(&config.file_path)?),
+    };
+    Ok(Some(provider.build()))
+}
+
+/// Turns `tracing` spans into OpenTelemetry spans sent through `provider`.
+pub fn trace_layer<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, Tracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("metaverse_server"))
+}
+
+/// Runs the request inside a `request` span and logs its outcome.
+///
+/// The span starts with the method and request ID. Routing fills in `route`
+/// and authentication fills in `user_id`, so every event logged while
+/// handling the request carries them. The ID is taken from an incoming
+/// `x-request-id` header when present and is echoed on the response. A W3C
+/// `traceparent` header makes the span a child of the caller's.
+pub async fn request_span(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        route = field::Empty,
+        user_id = field::Empty,
+        otel.kind = "server",
+    );
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(request.headers()));
+    // Fails only when no trace layer is installed, which is fine.
+    let _ = span.set_parent(parent);
+    let started = Instant::now();
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+
+    span.in_scope(|| {
+        info!(
+            status = response.status().as_u16(),
+            latency_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "finished request"
+        );
+    });
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// Appends finished spans to a file, one JSON object per line, so traces can
+/// be inspected without running a collector.
+#[derive(Debug)]
+pub struct FileExporter {
+    file: Mutex<File>,
+    service_name: Option<String>,
+}
+
+impl FileExporter {
+    pub fn open(path: &Path) -> Result<Self, TelemetryError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|source| TelemetryError::TraceFile {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        Ok(Self {
+            file: Mutex::new(file),
+            service_name: None,
+        })
+    }
+
+    fn write(&self, batch: &[SpanData]) -> OTelSdkResult {
+        let mut lines = Vec::new();
+        for span in batch {
+            serde_json::to_writer(&mut lines, &self.to_json(span))
+                .map_err(|err| OTelSdkError::InternalFailure(err.to_string()))?;
+            lines.push(b'\n');
+        }
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&lines)
+            .and_then(|_| file.flush())
+            .map_err(|err| OTelSdkError::InternalFailure(err.to_string()))
+    }
+
+    fn to_json(&self, span: &SpanData) -> serde_json::Value {
+        let attributes: Map<String, serde_json::Value> = span
+            .attributes
+            .iter()
+            .map(|KeyValue { key, value, .. }| (key.to_string(), value_to_json(value)))
+            .collect();
+        let status = match span.status {
+            Status::Unset => "unset",
+            Status::Ok => "ok",
+            Status::Error { .. } => "error",
+        };
+        json!({
+            "service": self.service_name,
+            "trace_id": span.span_context.trace_id().to_string(),
+            "span_id": span.span_context.span_id().to_string(),
+            "parent_span_id": (span.parent_span_id != SpanId::INVALID)
+                .then(|| span.parent_span_id.to_string()),
+            "name": span.name,
+            "kind": format!("{:?}", span.span_kind).to_lowercase(),
+            "start_unix_nano": unix_nanos(span.start_time),
+            "end_unix_nano": unix_nanos(span.end_time),
+            "status": status,
+            "attributes": attributes,
+        })
+    }
+}
+
+impl SpanExporter for FileExporter {
+    fn export(&self, batch: Vec<SpanData>) -> impl Future<Output = OTelSdkResult> + Send {
+        std::future::ready(self.write(&batch))
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.service_name = resource
+            .get(&Key::from_static_str("service.name"))
+            .map(|name| name.to_string());
+    }
+}
+
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(value) => json!(value),
+        Value::I64(value) => json!(value),
+        Value::F64(value) => json!(value),
+        other => json!(other.to_string()),
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    fn app() -> axum::Router {
+        let (router, _) = crate::router(crate::fakes::state()).split_for_parts();
+        router
+    }
+
+    #[tokio::test]
+    async fn generates_a_request_id() {
+        let response = app()
+            .oneshot(Request::get("/healthz").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let id = response.headers()[&REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(Uuid::parse_str(id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn echoes_the_callers_request_id() {
+        let response = app()
+            .oneshot(
+                Request::get("/api/v1/worlds/get_worlds")
+                    .header(&REQUEST_ID_HEADER, "trace-me")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()[&REQUEST_ID_HEADER], "trace-me");
+    }
+}
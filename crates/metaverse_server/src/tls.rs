@@ -0,0 +1,308 @@
+//! HTTPS and WSS served directly by the binary, with certificates reloaded
+//! from disk when they change.
+
+use crate::config::TlsConfig;
+use axum::serve::Listener;
+use std::{
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        self, ServerConfig,
+        crypto::{CryptoProvider, ring},
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+        server::{ClientHello, ResolvesServerCert},
+        sign::CertifiedKey,
+    },
+    server::TlsStream,
+};
+use tracing::{debug, error, info, warn};
+
+/// How long a client gets to finish the handshake before it is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("could not read {path}: {source}")]
+    Pem {
+        path: PathBuf,
+        source: rustls::pki_types::pem::Error,
+    },
+    #[error("could not read {path}: {source}")]
+    Metadata { path: PathBuf, source: io::Error },
+    #[error("{0} contains no certificates")]
+    NoCertificates(PathBuf),
+    #[error("invalid certificate or key: {0}")]
+    Rustls(#[from] rustls::Error),
+    #[error(transparent)]
+    Listen(#[from] io::Error),
+}
+
+/// Hands each handshake the current certificate.
+///
+/// [`CertResolver::watch`] swaps in a new pair when either file changes, so
+/// renewals take effect without a restart. Connections already established
+/// keep the certificate they were opened with.
+#[derive(Debug)]
+pub struct CertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    provider: Arc<CryptoProvider>,
+    current: RwLock<Arc<CertifiedKey>>,
+    /// Modification times of the loaded cert and key files.
+    loaded: Mutex<(SystemTime, SystemTime)>,
+}
+
+impl CertResolver {
+    pub fn load(config: &TlsConfig) -> Result<Self, TlsError> {
+        let provider = Arc::new(ring::default_provider());
+        let loaded = (modified(&config.cert_path)?, modified(&config.key_path)?);
+        let key = certified_key(&config.cert_path, &config.key_path, &provider)?;
+        Ok(Self {
+            cert_path: config.cert_path.clone(),
+            key_path: config.key_path.clone(),
+            provider,
+            current: RwLock::new(Arc::new(key)),
+            loaded: Mutex::new(loaded),
+        })
+    }
+
+    /// Reloads the pair if either file changed since it was last loaded.
+    /// Returns whether it did; on error the current pair stays in use.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let on_disk = (modified(&self.cert_path)?, modified(&self.key_path)?);
+        if *self.loaded.lock().unwrap() == on_disk {
+            return Ok(false);
+        }
+        let key = certified_key(&self.cert_path, &self.key_path, &self.provider)?;
+        *self.current.write().unwrap() = Arc::new(key);
+        *self.loaded.lock().unwrap() = on_disk;
+        Ok(true)
+    }
+
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Checks the files every `interval` for as long as the process runs.
+    pub async fn watch(self: Arc<Self>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match self.reload_if_changed() {
+                Ok(true) => info!("Reloaded TLS certificate {}", self.cert_path.display()),
+                Ok(false) => {}
+                // Often a renewal caught halfway, with only one file replaced.
+                Err(err) => warn!("Keeping the current TLS certificate: {}", err),
+            }
+        }
+    }
+
+    fn server_config(self: Arc<Self>) -> Result<ServerConfig, TlsError> {
+        let mut config = ServerConfig::builder_with_provider(self.provider.clone())
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_cert_resolver(self);
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(config)
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+fn modified(path: &Path) -> Result<SystemTime, TlsError> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|source| TlsError::Metadata {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+fn certified_key(
+    cert_path: &Path,
+    key_path: &Path,
+    provider: &CryptoProvider,
+) -> Result<CertifiedKey, TlsError> {
+    let pem_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| TlsError::Pem { path, source }
+    };
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .map_err(pem_error(cert_path))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(pem_error(cert_path))?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(cert_path.to_path_buf()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(pem_error(key_path))?;
+    // Also checks that the key belongs to the certificate.
+    Ok(CertifiedKey::from_der(certs, key, provider)?)
+}
+
+/// A listener for [`axum::serve`] that yields connections once their TLS
+/// handshake has finished.
+///
+/// Handshakes run on their own tasks so a slow client can't hold up the
+/// ones behind it.
+pub struct TlsListener {
+    local_addr: SocketAddr,
+    handshaken: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, certs: Arc<CertResolver>) -> Result<Self, TlsError> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(Arc::new(certs.server_config()?));
+        let (sender, handshaken) = mpsc::channel(64);
+        tokio::spawn(accept_loop(listener, acceptor, sender));
+        Ok(Self {
+            local_addr,
+            handshaken,
+        })
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    sender: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    loop {
+        let (stream, addr) = tokio::select! {
+            // The server stopped accepting, so stop listening.
+            _ = sender.closed() => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    // Usually out of file descriptors; wait for some to be freed.
+                    error!("Failed to accept a connection: {}", err);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+        };
+        let acceptor = acceptor.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = sender.send((stream, addr)).await;
+                }
+                Ok(Err(err)) => debug!("TLS handshake with {} failed: {}", addr, err),
+                Err(_) => debug!("TLS handshake with {} timed out", addr),
+            }
+        });
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.handshaken.recv().await {
+            Some(connection) => connection,
+            // The accept loop only ends once this listener is dropped.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    struct Pair {
+        cert_der: Vec<u8>,
+        cert_pem: String,
+        key_pem: String,
+    }
+
+    fn generate() -> Pair {
+        let rcgen::CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        Pair {
+            cert_der: cert.der().to_vec(),
+            cert_pem: cert.pem(),
+            key_pem: key_pair.serialize_pem(),
+        }
+    }
+
+    // Pushes the modification time forward so the change is seen even on
+    // filesystems with coarse timestamps.
+    fn write(path: &Path, contents: &str, ahead_secs: u64) {
+        std::fs::write(path, contents).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(ahead_secs))
+            .unwrap();
+    }
+
+    fn config(name: &str) -> TlsConfig {
+        let dir = std::env::temp_dir().join(format!("metaverse-tls-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        TlsConfig {
+            cert_path: dir.join("server.crt"),
+            key_path: dir.join("server.key"),
+            reload_interval_secs: 1,
+        }
+    }
+
+    #[test]
+    fn reloads_changed_certificates() {
+        let config = config("reload");
+        let (first, second) = (generate(), generate());
+        write(&config.cert_path, &first.cert_pem, 0);
+        write(&config.key_path, &first.key_pem, 0);
+        let certs = CertResolver::load(&config).unwrap();
+
+        assert!(!certs.reload_if_changed().unwrap());
+
+        write(&config.cert_path, &second.cert_pem, 10);
+        write(&config.key_path, &second.key_pem, 10);
+
+        assert!(certs.reload_if_changed().unwrap());
+        assert_eq!(certs.current().cert[0].as_ref(), second.cert_der);
+    }
+
+    #[test]
+    fn keeps_the_current_certificate_until_both_files_match() {
+        let config = config("halfway");
+        let (first, second) = (generate(), generate());
+        write(&config.cert_path, &first.cert_pem, 0);
+        write(&config.key_path, &first.key_pem, 0);
+        let certs = CertResolver::load(&config).unwrap();
+
+        write(&config.cert_path, &second.cert_pem, 10);
+
+        assert!(certs.reload_if_changed().is_err());
+        assert_eq!(certs.current().cert[0].as_ref(), first.cert_der);
+
+        write(&config.key_path, &second.key_pem, 20);
+
+        assert!(certs.reload_if_changed().unwrap());
+        assert_eq!(certs.current().cert[0].as_ref(), second.cert_der);
+    }
+}
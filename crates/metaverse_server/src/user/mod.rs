@@ -0,0 +1,257 @@
+use crate::auth_middleware::Claims;
+use axum::{
+    Extension, Json,
+    body::Body,
+    extract::State,
+    http::{Response, StatusCode},
+};
+use metaverse_core::avatar::{
+    AvatarComposition, AvatarPartsResponse, CreateAvatarPartPayload, GetAvatarPayload,
+    SetAvatarPayload, check_composition,
+};
+use metaverse_core::repo::{RepoError, UserRepo};
+use metaverse_core::user::{
+    AvatarResponseBody, CreateAvatarPayload, GetUserMetadataRequestPayload,
+    GetUserMetadataResponse, UpdateAvatarPayload,
+};
+use std::sync::Arc;
+use tracing::error;
+use tracing::warn;
+
+#[utoipa::path(
+    post,
+    path = "/metadata",
+    tag = "user",
+    request_body = UpdateAvatarPayload,
+    responses((status = 200, description = "Avatar updated")),
+    security(("bearer_auth" = []))
+)]
+#[axum::debug_handler]
+pub async fn metadata(
+    // Added pub
+    State(users): State<Arc<dyn UserRepo>>,
+    Extension(claims): Extension<Arc<Claims>>,
+    Json(payload): Json<UpdateAvatarPayload>,
+) -> Result<Response<Body>, StatusCode> {
+    // Added <Body>
+    let response = users
+        .set_avatar(claims.sub.parse::<i32>().unwrap(), payload.avatar_id)
+        .await;
+
+    match response {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::empty())
+            .unwrap()),
+        Err(_) => {
+            warn!("Unauthorized Login");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/create_avatar",
+    tag = "common",
+    request_body = CreateAvatarPayload,
+    responses((status = 201, description = "Avatar created")),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_avatar(
+    State(users): State<Arc<dyn UserRepo>>,
+    Json(payload): Json<CreateAvatarPayload>,
+) -> Result<StatusCode, StatusCode> {
+    let response = users.create_avatar(&payload).await;
+    match response {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(e) => {
+            error!("Avatar could not be created, {}", e);
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+// pub async fn get_avatars(
+//     State(pool): State<Arc<sqlx::PgPool>>,
+//     Extension(claims): Extension<Arc<Claims>>,
+// ) -> Result<Json<AvatarPayload>, StatusCode> {
+//     let user_id = claims
+//         .sub
+//         .parse::<i32>()
+//         .map_err(|_| StatusCode::BAD_REQUEST)?;
+//     let response = sqlx::query_as::<_, AvatarPayload>(
+//         "SELECT id, username, avatar_id FROM users WHERE id = $1",
+//     )
+//     .bind(user_id)
+//     .fetch_one(&*pool)
+//     .await;
+//     match response {
+//         Ok(response) => Ok(Json(response)),
+//         Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+//         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+//     }
+// }
+
+#[utoipa::path(
+    get,
+    path = "/avatars",
+    tag = "user",
+    responses((status = 200, description = "All avatars", body = AvatarResponseBody)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_avatars(
+    State(users): State<Arc<dyn UserRepo>>,
+) -> Result<Json<AvatarResponseBody>, StatusCode> {
+    let response = users.avatars().await;
+
+    match response {
+        Ok(avatars) => Ok(Json(AvatarResponseBody { avatars })),
+        Err(e) => {
+            error!("Something went wrong {}", e);
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/metadata/bulk",
+    tag = "user",
+    request_body = GetUserMetadataRequestPayload,
+    responses(
+        (status = 200, description = "Avatar images for the requested users", body = GetUserMetadataResponse),
+        (status = 400, description = "No user ids given")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_metadata_bulk(
+    State(users): State<Arc<dyn UserRepo>>,
+    Json(payload): Json<GetUserMetadataRequestPayload>,
+) -> Result<Json<GetUserMetadataResponse>, StatusCode> {
+    if payload.ids.is_empty() {
+        error!("Payload cannot be empty!");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut metadata = users.avatar_images(&payload.ids).await.map_err(|e| {
+        error!("Database error {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let mut compositions = users.avatar_compositions(&payload.ids).await.map_err(|e| {
+        error!("Database error {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    for avatar in &mut metadata {
+        avatar.composition = compositions.remove(&avatar.id);
+    }
+
+    Ok(Json(GetUserMetadataResponse { avatars: metadata }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/create_avatar_part",
+    tag = "common",
+    request_body = CreateAvatarPartPayload,
+    responses((status = 201, description = "Avatar part created")),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_avatar_part(
+    State(users): State<Arc<dyn UserRepo>>,
+    Json(payload): Json<CreateAvatarPartPayload>,
+) -> Result<StatusCode, StatusCode> {
+    match users.create_avatar_part(&payload).await {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(RepoError::Conflict) => {
+            warn!("Avatar part refers to a missing asset");
+            Err(StatusCode::BAD_REQUEST)
+        }
+        Err(e) => {
+            error!("Avatar part could not be created, {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/avatar_parts",
+    tag = "user",
+    responses((status = 200, description = "Every part avatars can be put together from", body = AvatarPartsResponse)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_avatar_parts(
+    State(users): State<Arc<dyn UserRepo>>,
+) -> Result<Json<AvatarPartsResponse>, StatusCode> {
+    match users.avatar_parts().await {
+        Ok(parts) => Ok(Json(AvatarPartsResponse { parts })),
+        Err(e) => {
+            error!("Error getting avatar parts: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/set_avatar",
+    tag = "user",
+    request_body = SetAvatarPayload,
+    responses(
+        (status = 200, description = "Avatar replaced"),
+        (status = 400, description = "Unknown parts, no body, too many parts in a slot or a bad tint")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_avatar(
+    State(users): State<Arc<dyn UserRepo>>,
+    Extension(claims): Extension<Arc<Claims>>,
+    Json(payload): Json<SetAvatarPayload>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = claims.sub.parse::<i32>().map_err(|e| {
+        error!("Error getting user id: {}", e);
+        StatusCode::FORBIDDEN
+    })?;
+    let parts = users.avatar_parts().await.map_err(|e| {
+        error!("Error getting avatar parts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let worn = check_composition(&payload.parts, &parts).map_err(|e| {
+        warn!("Refused avatar: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    match users.set_avatar_parts(user_id, &worn).await {
+        Ok(()) => Ok(StatusCode::OK),
+        // A part was deleted since it was checked.
+        Err(RepoError::Conflict) => Err(StatusCode::BAD_REQUEST),
+        Err(e) => {
+            error!("Error saving avatar: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/get_avatar",
+    tag = "user",
+    request_body = GetAvatarPayload,
+    responses((status = 200, description = "The parts the user wears, back to front", body = AvatarComposition)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_avatar(
+    State(users): State<Arc<dyn UserRepo>>,
+    Json(payload): Json<GetAvatarPayload>,
+) -> Result<Json<AvatarComposition>, StatusCode> {
+    match users.avatar_compositions(&[payload.user_id]).await {
+        Ok(mut compositions) => Ok(Json(
+            compositions.remove(&payload.user_id).unwrap_or_default(),
+        )),
+        Err(e) => {
+            error!("Error getting avatar: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
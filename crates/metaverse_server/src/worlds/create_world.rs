@@ -4,17 +4,19 @@ use std::sync::Arc;
 use tracing::error;
 
 use crate::admin_middleware::Claims;
+use metaverse_core::repo::WorldRepo;
+use metaverse_core::worlds::CreateWorldPayload;
 
-#[derive(serde::Deserialize, serde::Serialize)]
-pub struct CreateWorldPayload {
-    name: String,
-    description: String,
-    thumbnail_url: String,
-    is_public: bool,
-}
-
+#[utoipa::path(
+    post,
+    path = "/create",
+    tag = "worlds",
+    request_body = CreateWorldPayload,
+    responses((status = 201, description = "World created")),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_world(
-    State(pool): State<Arc<sqlx::PgPool>>,
+    State(worlds): State<Arc<dyn WorldRepo>>,
     Extension(claims): Extension<Arc<Claims>>,
     Json(payload): Json<CreateWorldPayload>,
 ) -> Result<StatusCode, StatusCode> {
@@ -22,14 +24,7 @@ pub async fn create_world(
         error!("Error getting user id: {}", e);
         StatusCode::FORBIDDEN
     })?;
-    let response = sqlx::query("INSERT INTO worlds (name, description, thumbnail_url, creator_id, is_public) VALUES ($1,$2,$3,$4,$5)")
-        .bind(payload.name)
-        .bind(payload.description)
-        .bind(payload.thumbnail_url)
-        .bind(creator_id)
-        .bind(payload.is_public)
-        .execute(&*pool)
-        .await;
+    let response = worlds.create(creator_id, &payload).await;
 
     match response {
         Ok(_) => Ok(StatusCode::CREATED),
@@ -0,0 +1,51 @@
+use axum::{Json, extract::State, http::StatusCode};
+use metaverse_core::repo::WorldRepo;
+use metaverse_core::worlds::World;
+use std::sync::Arc;
+use tracing::error;
+
+#[utoipa::path(
+    get,
+    path = "/get_worlds",
+    tag = "worlds",
+    responses((status = 200, description = "All worlds", body = Vec<World>)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_worlds(
+    State(worlds): State<Arc<dyn WorldRepo>>,
+) -> Result<Json<Vec<World>>, StatusCode> {
+    let response = worlds.list().await;
+    match response {
+        Ok(worlds) => Ok(Json(worlds)),
+        Err(e) => {
+            error!("Error fetching all worlds {}", e);
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metaverse_core::worlds::CreateWorldPayload;
+
+    use crate::fakes;
+
+    #[tokio::test]
+    async fn lists_created_worlds() {
+        let worlds = fakes::state().worlds;
+        let world = CreateWorldPayload {
+            name: "Lobby".to_string(),
+            description: "Where everyone spawns".to_string(),
+            thumbnail_url: "https://example.com/lobby.png".to_string(),
+            thumbnail_asset_id: None,
+            is_public: true,
+        };
+        worlds.create(1, &world).await.unwrap();
+
+        let Json(listed) = get_worlds(State(worlds)).await.unwrap();
+
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "Lobby");
+    }
+}
@@ -0,0 +1,452 @@
+mod harness;
+
+use axum::{
+    body::{Body, Bytes},
+    http::{Request, StatusCode, header},
+};
+use harness::TestApp;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use metaverse_server::{
+    assets::{
+        gc,
+        storage::{AssetStore, S3Store},
+    },
+    config::{Config, S3Config},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::{io::Cursor, time::Duration};
+
+/// A PNG of one colour, so different shades hash differently.
+fn png(width: u32, height: u32, shade: u8) -> Vec<u8> {
+    let image = RgbImage::from_pixel(width, height, Rgb([shade, 120, 60]));
+    let mut content = Vec::new();
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut content), ImageFormat::Png)
+        .unwrap();
+    content
+}
+
+/// Enough of a binary glTF header to be recognised as one.
+fn glb(padding: usize) -> Vec<u8> {
+    [b"glTF\x02\0\0\0".as_slice(), &vec![0; padding]].concat()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn uploads_and_deduplicates_assets(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.user_token("alice").await;
+    let content = glb(64);
+
+    let uploaded = app
+        .upload(&token, "chair.glb", "model/gltf-binary", &content)
+        .await;
+    assert_eq!(uploaded.status, StatusCode::CREATED);
+    let asset = uploaded.body;
+    assert_eq!(asset["name"], "chair.glb");
+    assert_eq!(asset["asset_type"], "model");
+    assert_eq!(asset["mime_type"], "model/gltf-binary");
+    assert_eq!(asset["size_bytes"], content.len());
+    assert!(asset.get("variants").is_none());
+    let hash = asset["hash"].as_str().unwrap();
+    assert_eq!(hash.len(), 64);
+    assert_eq!(asset["url"], format!("/assets/{hash}.glb"));
+
+    let served = app
+        .raw(
+            Request::get(asset["url"].as_str().unwrap())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(served.status(), StatusCode::OK);
+    assert_eq!(served.headers()[header::CONTENT_TYPE], "model/gltf-binary");
+    let bytes = axum::body::to_bytes(served.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(bytes, content);
+
+    let again = app
+        .upload(&token, "copy.glb", "application/octet-stream", &content)
+        .await;
+    assert_eq!(again.status, StatusCode::OK);
+    assert_eq!(again.body["id"], asset["id"]);
+
+    let fetched = app
+        .post(
+            "/assets/get_asset",
+            Some(&token),
+            json!({ "asset_id": asset["id"] }),
+        )
+        .await;
+    assert_eq!(fetched.status, StatusCode::OK);
+    assert_eq!(fetched.body, asset);
+
+    let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM assets")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(rows, 1);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn stores_images_as_resized_variants(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.user_token("alice").await;
+    let content = png(1200, 600, 10);
+
+    let uploaded = app.upload(&token, "lobby.png", "image/png", &content).await;
+    assert_eq!(uploaded.status, StatusCode::CREATED);
+    let asset = uploaded.body;
+    let hash = asset["hash"].as_str().unwrap();
+    assert_eq!(asset["asset_type"], "image");
+    assert_eq!(asset["mime_type"], "image/webp");
+    assert_eq!(asset["url"], format!("/assets/{hash}-full.webp"));
+    assert_eq!(asset["variants"]["full"], asset["url"]);
+    assert_eq!(
+        asset["variants"]["thumbnail"],
+        format!("/assets/{hash}-thumbnail.webp")
+    );
+
+    let sizes: Vec<(String, i32, i32)> =
+        sqlx::query_as("SELECT variant, width, height FROM asset_variants ORDER BY width")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        sizes,
+        [
+            ("thumbnail".to_string(), 128, 64),
+            ("medium".to_string(), 512, 256),
+            ("full".to_string(), 1200, 600),
+        ]
+    );
+
+    let served = app
+        .raw(
+            Request::get(asset["variants"]["medium"].as_str().unwrap())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(served.status(), StatusCode::OK);
+    assert_eq!(served.headers()[header::CONTENT_TYPE], "image/webp");
+    let bytes = axum::body::to_bytes(served.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let medium = image::load_from_memory(&bytes).unwrap();
+    assert_eq!((medium.width(), medium.height()), (512, 256));
+
+    // The upload, not what was stored, is what repeats are matched on.
+    let again = app.upload(&token, "copy.png", "image/png", &content).await;
+    assert_eq!(again.status, StatusCode::OK);
+    assert_eq!(again.body, asset);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn rejects_unsupported_and_mislabelled_files(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.user_token("alice").await;
+
+    let text = app
+        .upload(&token, "notes.txt", "text/plain", b"just some notes")
+        .await;
+    let mislabelled = app
+        .upload(&token, "song.mp3", "audio/mpeg", &png(8, 8, 0))
+        .await;
+    let mut truncated = png(64, 64, 0);
+    truncated.truncate(48);
+    let broken = app
+        .upload(&token, "broken.png", "image/png", &truncated)
+        .await;
+    let model = app
+        .upload(&token, "chair.glb", "model/gltf-binary", &glb(0))
+        .await;
+
+    assert_eq!(text.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(mislabelled.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(broken.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(model.status, StatusCode::CREATED);
+    assert_eq!(model.body["asset_type"], "model");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn rejects_files_over_the_upload_limit(pool: PgPool) {
+    let mut config = Config::default();
+    config.auth.jwt_secret = "integration-test-secret".to_string();
+    config.assets.max_upload_bytes = 1024;
+    let app = TestApp::with_config(pool, config);
+    let token = app.user_token("alice").await;
+
+    let small = app
+        .upload(&token, "small.glb", "model/gltf-binary", &glb(512))
+        .await;
+    let large = app
+        .upload(&token, "large.glb", "model/gltf-binary", &glb(2048))
+        .await;
+
+    assert_eq!(small.status, StatusCode::CREATED);
+    assert_eq!(large.status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn entities_reference_assets_by_id(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("admin").await;
+    let asset = app
+        .upload(&admin, "lobby.png", "image/png", &png(800, 600, 20))
+        .await
+        .body;
+
+    let created = app
+        .post(
+            "/worlds/create",
+            Some(&admin),
+            json!({
+                "name": "Lobby",
+                "description": "Where everyone starts",
+                "thumbnail_asset_id": asset["id"],
+                "is_public": true,
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let unknown = app
+        .post(
+            "/worlds/create",
+            Some(&admin),
+            json!({
+                "name": "Nowhere",
+                "description": "Points at a missing asset",
+                "thumbnail_asset_id": 9999,
+                "is_public": true,
+            }),
+        )
+        .await;
+    assert_ne!(unknown.status, StatusCode::CREATED);
+
+    let worlds = app.get("/worlds/get_worlds", Some(&admin)).await.body;
+    assert_eq!(worlds.as_array().unwrap().len(), 1);
+    assert_eq!(worlds[0]["thumbnail_url"], asset["url"]);
+    assert_eq!(worlds[0]["thumbnail_variants"], asset["variants"]);
+    let referenced: Option<i32> =
+        sqlx::query_scalar("SELECT thumbnail_asset_id FROM worlds WHERE name = 'Lobby'")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(referenced, asset["id"].as_i64().map(|id| id as i32));
+
+    let avatar = app
+        .post(
+            "/common/create_avatar",
+            Some(&admin),
+            json!({ "name": "Robot", "image_asset_id": asset["id"] }),
+        )
+        .await;
+    assert_eq!(avatar.status, StatusCode::CREATED);
+    let avatars = app.get("/user/avatars", Some(&admin)).await.body;
+    assert_eq!(avatars["avatars"][0]["image_url"], asset["url"]);
+    assert_eq!(
+        avatars["avatars"][0]["image_variants"]["thumbnail"],
+        asset["variants"]["thumbnail"]
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn tracks_references_and_refuses_deleting_used_assets(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("admin").await;
+    let alice = app.user_token("alice").await;
+    let bob = app.user_token("bob").await;
+    let asset = app
+        .upload(&alice, "lobby.png", "image/png", &png(300, 200, 30))
+        .await
+        .body;
+    let thumbnail = asset["variants"]["thumbnail"].as_str().unwrap();
+    let id = json!({ "asset_id": asset["id"] });
+
+    // Saved by URL alone, at one of the asset's sizes.
+    let created = app
+        .post(
+            "/worlds/create",
+            Some(&admin),
+            json!({
+                "name": "Lobby",
+                "description": "Where everyone starts",
+                "thumbnail_url": thumbnail,
+                "is_public": true,
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let worlds = app.get("/worlds/get_worlds", Some(&admin)).await.body;
+    assert_eq!(worlds[0]["thumbnail_url"], thumbnail);
+
+    let references = app
+        .post("/assets/get_asset_references", Some(&alice), id.clone())
+        .await;
+    assert_eq!(references.status, StatusCode::OK);
+    assert_eq!(
+        references.body,
+        json!([{ "referrer": "world", "referrer_id": worlds[0]["id"], "field": "thumbnail_url" }])
+    );
+
+    let in_use = app
+        .post("/assets/delete_asset", Some(&alice), id.clone())
+        .await;
+    let not_theirs = app
+        .post("/assets/delete_asset", Some(&bob), id.clone())
+        .await;
+    assert_eq!(in_use.status, StatusCode::CONFLICT);
+    assert_eq!(not_theirs.status, StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM worlds")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let deleted = app
+        .post("/assets/delete_asset", Some(&alice), id.clone())
+        .await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    let served = app
+        .raw(Request::get(thumbnail).body(Body::empty()).unwrap())
+        .await;
+    assert_eq!(served.status(), StatusCode::NOT_FOUND);
+    let gone = app
+        .post("/assets/get_asset_references", Some(&alice), id)
+        .await;
+    assert_eq!(gone.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn collects_orphaned_assets_after_the_grace_period(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("admin").await;
+    let used = app
+        .upload(&admin, "lobby.png", "image/png", &png(64, 64, 40))
+        .await
+        .body;
+    let orphan = app
+        .upload(&admin, "chair.glb", "model/gltf-binary", &glb(16))
+        .await
+        .body;
+    app.post(
+        "/worlds/create",
+        Some(&admin),
+        json!({
+            "name": "Lobby",
+            "description": "Where everyone starts",
+            "thumbnail_asset_id": used["id"],
+            "is_public": true,
+        }),
+    )
+    .await;
+    let assets = app.state.assets.as_ref();
+    let store = app.state.storage.as_ref();
+
+    assert_eq!(
+        gc::collect_orphans(assets, store, Duration::from_secs(3600)).await,
+        0
+    );
+    assert_eq!(gc::collect_orphans(assets, store, Duration::ZERO).await, 1);
+
+    let remaining: Vec<i32> = sqlx::query_scalar("SELECT id FROM assets")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, [used["id"].as_i64().unwrap() as i32]);
+    let key = orphan["url"]
+        .as_str()
+        .unwrap()
+        .trim_start_matches("/assets/");
+    assert_eq!(store.get(key).await.unwrap(), None);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn reports_storage_per_creator(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("admin").await;
+    let alice = app.user_token("alice").await;
+    let image = app
+        .upload(&alice, "lobby.png", "image/png", &png(640, 480, 50))
+        .await
+        .body;
+    app.upload(&alice, "chair.glb", "model/gltf-binary", &glb(100))
+        .await;
+    app.upload(&admin, "table.glb", "model/gltf-binary", &glb(10))
+        .await;
+    app.post(
+        "/common/create_avatar",
+        Some(&admin),
+        json!({ "name": "Robot", "image_asset_id": image["id"] }),
+    )
+    .await;
+    let variant_bytes: i64 =
+        sqlx::query_scalar("SELECT SUM(size_bytes)::BIGINT FROM asset_variants")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+
+    let forbidden = app.get("/assets/storage_report", Some(&alice)).await;
+    let report = app.get("/assets/storage_report", Some(&admin)).await;
+
+    assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+    assert_eq!(report.status, StatusCode::OK);
+    assert_eq!(
+        report.body,
+        json!([
+            {
+                "creator_id": app.user_id("alice").await,
+                "username": "alice",
+                "assets": 2,
+                "bytes": variant_bytes + 108,
+                "orphaned_assets": 1,
+                "orphaned_bytes": 108,
+            },
+            {
+                "creator_id": app.user_id("admin").await,
+                "username": "admin",
+                "assets": 1,
+                "bytes": 18,
+                "orphaned_assets": 1,
+                "orphaned_bytes": 18,
+            },
+        ])
+    );
+}
+
+/// Runs against an S3-compatible store such as a local MinIO when
+/// `S3_TEST_ENDPOINT`, `S3_TEST_BUCKET`, `S3_TEST_ACCESS_KEY_ID` and
+/// `S3_TEST_SECRET_ACCESS_KEY` are set, and is skipped otherwise.
+#[tokio::test]
+async fn stores_files_in_s3_compatible_buckets() {
+    let Ok(endpoint) = std::env::var("S3_TEST_ENDPOINT") else {
+        eprintln!("S3_TEST_ENDPOINT is not set, skipping");
+        return;
+    };
+    let var = |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("{name} is not set"));
+    let config = S3Config {
+        endpoint,
+        bucket: var("S3_TEST_BUCKET"),
+        region: std::env::var("S3_TEST_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+        access_key_id: var("S3_TEST_ACCESS_KEY_ID"),
+        secret_access_key: var("S3_TEST_SECRET_ACCESS_KEY"),
+    };
+    let store = S3Store::new(&config, None);
+    let key = format!("test-{}.png", std::process::id());
+    let content = Bytes::from(png(4, 4, 0));
+
+    store.put(&key, content.clone(), "image/png").await.unwrap();
+    assert_eq!(store.get(&key).await.unwrap(), Some(content));
+    store.delete(&key).await.unwrap();
+
+    assert_eq!(store.get(&key).await.unwrap(), None);
+    assert_eq!(
+        store.url(&key),
+        format!(
+            "{}/{}/{key}",
+            config.endpoint.trim_end_matches('/'),
+            config.bucket
+        )
+    );
+}
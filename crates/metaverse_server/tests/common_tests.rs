@@ -0,0 +1,187 @@
+mod harness;
+
+use axum::http::StatusCode;
+use harness::{PASSWORD, TestApp};
+use metaverse_server::config::Config;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn signup_then_signin(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    assert_eq!(app.signup("alice", "User").await, StatusCode::CREATED);
+    let token = app.signin("alice").await;
+
+    assert_eq!(token.split('.').count(), 3);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn signup_rejects_taken_usernames(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    app.signup("alice", "User").await;
+
+    assert_eq!(app.signup("alice", "User").await, StatusCode::CONFLICT);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn signin_rejects_bad_credentials(pool: PgPool) {
+    let app = TestApp::new(pool);
+    app.signup("alice", "User").await;
+
+    let wrong_password = app
+        .post(
+            "/common/signin",
+            None,
+            json!({ "username": "alice", "password": "nope" }),
+        )
+        .await;
+    let unknown_user = app
+        .post(
+            "/common/signin",
+            None,
+            json!({ "username": "bob", "password": PASSWORD }),
+        )
+        .await;
+
+    assert_eq!(wrong_password.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(unknown_user.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn signin_refuses_disabled_users(pool: PgPool) {
+    let app = TestApp::new(pool);
+    app.signup("alice", "User").await;
+    sqlx::query("UPDATE users SET disabled_at = now() WHERE username = 'alice'")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let right_password = app
+        .post(
+            "/common/signin",
+            None,
+            json!({ "username": "alice", "password": PASSWORD }),
+        )
+        .await;
+    let wrong_password = app
+        .post(
+            "/common/signin",
+            None,
+            json!({ "username": "alice", "password": "nope" }),
+        )
+        .await;
+
+    assert_eq!(right_password.status, StatusCode::FORBIDDEN);
+    assert_eq!(wrong_password.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn create_avatar_is_admin_only(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let user = app.user_token("alice").await;
+    let avatar = json!({ "name": "Robot", "image_url": "https://example.com/robot.png" });
+
+    let anonymous = app
+        .post("/common/create_avatar", None, avatar.clone())
+        .await;
+    let as_user = app
+        .post("/common/create_avatar", Some(&user), avatar.clone())
+        .await;
+    let as_admin = app
+        .post("/common/create_avatar", Some(&admin), avatar)
+        .await;
+
+    assert_eq!(anonymous.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(as_user.status, StatusCode::FORBIDDEN);
+    assert_eq!(as_admin.status, StatusCode::CREATED);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn rejects_tampered_tokens(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.user_token("alice").await;
+    let tampered = format!("{token}x");
+
+    let response = app.get("/user/avatars", Some(&tampered)).await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn disabling_a_user_revokes_their_tokens(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.user_token("alice").await;
+    let admin = app.admin_token("root").await;
+    sqlx::query("UPDATE users SET disabled_at = now()")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let as_user = app.get("/user/avatars", Some(&user)).await;
+    let as_admin = app
+        .post(
+            "/worlds/create",
+            Some(&admin),
+            json!({
+                "name": "Mine",
+                "description": "",
+                "thumbnail_url": "",
+                "is_public": true,
+            }),
+        )
+        .await;
+
+    assert_eq!(as_user.status, StatusCode::FORBIDDEN);
+    assert_eq!(as_admin.status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn demoting_an_admin_takes_effect_on_their_next_request(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    app.create_world(&admin, "Lobby").await;
+    sqlx::query("UPDATE users SET role = 'User' WHERE username = 'root'")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let response = app
+        .post(
+            "/worlds/create",
+            Some(&admin),
+            json!({
+                "name": "Mine",
+                "description": "",
+                "thumbnail_url": "",
+                "is_public": true,
+            }),
+        )
+        .await;
+    let listed = app.get("/worlds/get_worlds", Some(&admin)).await;
+
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(listed.status, StatusCode::OK);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn serves_openapi_document(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app.get("/openapi.json", None).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["info"]["title"], "Metaverse API");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn signup_can_be_switched_off(pool: PgPool) {
+    let mut config = Config::default();
+    config.auth.jwt_secret = "integration-test-secret".to_string();
+    config.features.signup = false;
+    let app = TestApp::with_config(pool, config);
+
+    assert_eq!(app.signup("alice", "User").await, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,409 @@
+mod harness;
+
+use axum::http::StatusCode;
+use harness::TestApp;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn places_templates_in_spaces_and_on_maps(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+    let template_id = app.create_template(&admin, "Door").await;
+
+    let in_space = app
+        .post(
+            "/element/create_space_element",
+            Some(&admin),
+            json!({
+                "space_id": space_id,
+                "template_id": template_id,
+                "x": 4,
+                "y": 5,
+                "z_index": 1,
+                "rotation": 90,
+                "custom_properties": { "locked": false },
+            }),
+        )
+        .await;
+    let on_map = app
+        .post(
+            "/element/create_map_element",
+            Some(&admin),
+            json!({
+                "map_id": map_id,
+                "template_id": template_id,
+                "x": 10,
+                "y": 12,
+                "z_index": 0,
+                "target_space_id": space_id,
+                "custom_properties": {},
+            }),
+        )
+        .await;
+
+    assert_eq!(in_space.status, StatusCode::CREATED);
+    assert_eq!(on_map.status, StatusCode::CREATED);
+    let placed: i64 = sqlx::query_scalar(
+        "SELECT (SELECT COUNT(*) FROM space_elements) + (SELECT COUNT(*) FROM map_elements)",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(placed, 2);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn element_routes_are_admin_only(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.user_token("alice").await;
+    let template = json!({
+        "name": "Lamp",
+        "element_type": "Decorative",
+        "image_url": "",
+        "model_url": "",
+        "width": 1,
+        "height": 1,
+        "is_collidable": false,
+        "interaction_data": {},
+        "physics_properties": {},
+    });
+
+    let anonymous = app
+        .post("/element/create_new_element", None, template.clone())
+        .await;
+    let as_user = app
+        .post("/element/create_new_element", Some(&user), template)
+        .await;
+
+    assert_eq!(anonymous.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(as_user.status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn interactive_templates_need_a_valid_interaction(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let template = |interaction_data| {
+        json!({
+            "name": "Sign",
+            "element_type": "Interactive",
+            "image_url": "",
+            "model_url": "",
+            "width": 1,
+            "height": 1,
+            "is_collidable": false,
+            "interaction_data": interaction_data,
+            "physics_properties": {},
+        })
+    };
+
+    let unknown = app
+        .post(
+            "/element/create_new_element",
+            Some(&admin),
+            template(json!({ "action": "fly" })),
+        )
+        .await;
+    let missing_text = app
+        .post(
+            "/element/create_new_element",
+            Some(&admin),
+            template(json!({ "action": "show_text" })),
+        )
+        .await;
+    let mut scripted = Vec::new();
+    for url in [
+        "javascript:alert(document.cookie)",
+        "data:text/html,<script>alert(1)</script>",
+        "not a url",
+    ] {
+        let response = app
+            .post(
+                "/element/create_new_element",
+                Some(&admin),
+                template(json!({ "action": "open_url", "url": url })),
+            )
+            .await;
+        scripted.push(response.status);
+    }
+    let valid = app
+        .post(
+            "/element/create_new_element",
+            Some(&admin),
+            template(json!({ "action": "show_text", "text": "Open daily" })),
+        )
+        .await;
+    let link = app
+        .post(
+            "/element/create_new_element",
+            Some(&admin),
+            template(json!({ "action": "open_url", "url": "https://example.com/menu" })),
+        )
+        .await;
+    let unchecked_state = app
+        .post(
+            "/element/create_new_element",
+            Some(&admin),
+            template(json!({ "action": "set_state" })),
+        )
+        .await;
+
+    assert_eq!(unknown.status, StatusCode::BAD_REQUEST);
+    assert_eq!(missing_text.status, StatusCode::BAD_REQUEST);
+    assert_eq!(scripted, [StatusCode::BAD_REQUEST; 3]);
+    assert_eq!(valid.status, StatusCode::CREATED);
+    assert_eq!(link.status, StatusCode::CREATED);
+    assert_eq!(unchecked_state.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn validates_element_json_against_schemas(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+    let template = |name: &str, extra: serde_json::Value| {
+        let mut template = json!({
+            "name": name,
+            "element_type": "Static",
+            "image_url": "",
+            "model_url": "",
+            "width": 1,
+            "height": 1,
+            "is_collidable": true,
+            "interaction_data": {},
+            "physics_properties": {},
+        });
+        template
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        template
+    };
+    let create = |body| app.post("/element/create_new_element", Some(&admin), body);
+
+    let heavy = create(template(
+        "Crate",
+        json!({ "physics_properties": { "mass": -1 } }),
+    ))
+    .await;
+    let unanimated = create(template(
+        "Flag",
+        json!({ "animation_data": { "clips": { "wave": { "frames": [] } } } }),
+    ))
+    .await;
+    let unschemed = create(template(
+        "Sign",
+        json!({ "properties_schema": { "type": 5 } }),
+    ))
+    .await;
+    let door = create(template(
+        "Door",
+        json!({
+            "physics_properties": { "body": "fixed", "collider": "cuboid" },
+            "animation_data": { "clips": { "open": { "frames": [0, 1, 2], "fps": 12 } }, "default": "open" },
+            "properties_schema": {
+                "type": "object",
+                "properties": { "locked": { "type": "boolean" } },
+                "required": ["locked"],
+            },
+        }),
+    ))
+    .await;
+    assert_eq!(heavy.status, StatusCode::BAD_REQUEST);
+    assert_eq!(unanimated.status, StatusCode::BAD_REQUEST);
+    assert_eq!(unschemed.status, StatusCode::BAD_REQUEST);
+    assert_eq!(door.status, StatusCode::CREATED);
+
+    let door_id: i32 = sqlx::query_scalar("SELECT id FROM element_templates WHERE name = 'Door'")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    let place = |template_id: i32, custom_properties| {
+        app.post(
+            "/element/create_space_element",
+            Some(&admin),
+            json!({
+                "space_id": space_id,
+                "template_id": template_id,
+                "x": 1,
+                "y": 1,
+                "z_index": 0,
+                "rotation": 0,
+                "custom_properties": custom_properties,
+            }),
+        )
+    };
+    let mistyped = place(door_id, json!({ "locked": "yes" })).await;
+    let missing = app
+        .post(
+            "/element/create_map_element",
+            Some(&admin),
+            json!({
+                "map_id": map_id,
+                "template_id": door_id,
+                "x": 1,
+                "y": 1,
+                "z_index": 0,
+                "target_space_id": space_id,
+                "custom_properties": {},
+            }),
+        )
+        .await;
+    let unknown = place(door_id + 100, json!({})).await;
+    let placed = place(door_id, json!({ "locked": true })).await;
+    assert_eq!(mistyped.status, StatusCode::BAD_REQUEST);
+    assert_eq!(missing.status, StatusCode::BAD_REQUEST);
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+    assert_eq!(placed.status, StatusCode::CREATED);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn revises_templates_and_pins_revisions_per_space(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let kitchen = app.create_space(&admin, map_id, "Kitchen").await;
+    let hall = app.create_space(&admin, map_id, "Hall").await;
+    let template_id = app.create_template(&admin, "Door").await;
+    for space_id in [kitchen, hall] {
+        let placed = app
+            .post(
+                "/element/create_space_element",
+                Some(&admin),
+                json!({
+                    "space_id": space_id,
+                    "template_id": template_id,
+                    "x": 1,
+                    "y": 1,
+                    "z_index": 0,
+                    "rotation": 0,
+                    "custom_properties": {},
+                }),
+            )
+            .await;
+        assert_eq!(placed.status, StatusCode::CREATED);
+    }
+    let update = |image: &str, properties_schema| {
+        app.post(
+            "/element/update_element",
+            Some(&admin),
+            json!({
+                "template_id": template_id,
+                "name": "Door",
+                "element_type": "Portal",
+                "image_url": image,
+                "model_url": "https://example.com/door.glb",
+                "width": 1,
+                "height": 2,
+                "is_collidable": true,
+                "interaction_data": {},
+                "physics_properties": {},
+                "properties_schema": properties_schema,
+            }),
+        )
+    };
+    let pin = |path: &'static str, revision: i32, scope: serde_json::Value| {
+        let mut body = json!({ "template_id": template_id, "revision": revision });
+        body.as_object_mut()
+            .unwrap()
+            .extend(scope.as_object().unwrap().clone());
+        app.post(path, Some(&admin), body)
+    };
+    let revisions = || async {
+        sqlx::query_scalar::<_, i32>(
+            "SELECT template_revision FROM space_elements ORDER BY space_id",
+        )
+        .fetch_all(&app.pool)
+        .await
+        .unwrap()
+    };
+
+    // Saving the same content again is not a new revision.
+    let unchanged = update("https://example.com/door.png", json!(null)).await;
+    let repainted = update("https://example.com/red-door.png", json!(null)).await;
+    assert_eq!(unchanged.body["revision"], 1);
+    assert_eq!(repainted.status, StatusCode::OK);
+    assert_eq!(repainted.body["revision"], 2);
+    assert_eq!(revisions().await, vec![1, 1]);
+
+    // A preview is scoped like the pin it previews, and lists what it moves.
+    let everywhere = pin("/element/preview_template_revision", 2, json!({})).await;
+    let both = pin(
+        "/element/preview_template_revision",
+        2,
+        json!({ "space_id": kitchen, "map_id": map_id }),
+    )
+    .await;
+    assert_eq!(everywhere.status, StatusCode::BAD_REQUEST);
+    assert_eq!(both.status, StatusCode::BAD_REQUEST);
+    let preview = pin(
+        "/element/preview_template_revision",
+        2,
+        json!({ "space_id": kitchen }),
+    )
+    .await;
+    assert_eq!(preview.status, StatusCode::OK);
+    assert_eq!(revisions().await, vec![1, 1]);
+
+    let forward = pin(
+        "/element/pin_template_revision",
+        2,
+        json!({ "space_id": kitchen }),
+    )
+    .await;
+    assert_eq!(forward.status, StatusCode::OK);
+    assert_eq!(forward.body["elements"], preview.body["elements"]);
+    assert_eq!(forward.body["elements"][0]["space_id"], kitchen);
+    assert_eq!(forward.body["elements"][0]["revision"], 1);
+    assert_eq!(revisions().await, vec![2, 1]);
+    let back = pin(
+        "/element/pin_template_revision",
+        1,
+        json!({ "space_id": kitchen }),
+    )
+    .await;
+    assert_eq!(back.status, StatusCode::OK);
+    assert_eq!(revisions().await, vec![1, 1]);
+
+    // Elements whose properties don't fit a revision can't move to it.
+    let locked = update(
+        "https://example.com/red-door.png",
+        json!({ "type": "object", "required": ["locked"] }),
+    )
+    .await;
+    assert_eq!(locked.body["revision"], 3);
+    let preview = pin(
+        "/element/preview_template_revision",
+        3,
+        json!({ "space_id": hall }),
+    )
+    .await;
+    let elements = preview.body["elements"].as_array().unwrap();
+    assert_eq!(elements.len(), 1);
+    assert!(!elements[0]["problems"].as_array().unwrap().is_empty());
+    let refused = pin(
+        "/element/pin_template_revision",
+        3,
+        json!({ "space_id": hall }),
+    )
+    .await;
+    assert_eq!(refused.status, StatusCode::CONFLICT);
+    assert_eq!(revisions().await, vec![1, 1]);
+
+    let unscoped = pin("/element/pin_template_revision", 2, json!({})).await;
+    let unknown = pin(
+        "/element/pin_template_revision",
+        9,
+        json!({ "space_id": hall }),
+    )
+    .await;
+    assert_eq!(unscoped.status, StatusCode::BAD_REQUEST);
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+}
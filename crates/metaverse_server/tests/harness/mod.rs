@@ -0,0 +1,306 @@
+//! Shared setup for the API integration tests.
+//!
+//! Tests use `#[sqlx::test(migrations = "../../migrations")]`, which creates a
+//! throwaway database on the server named by `DATABASE_URL`, applies every
+//! migration and drops it again afterwards. [`TestApp`] wraps the full router
+//! around that database.
+
+// Each test binary only uses part of the harness.
+#![allow(dead_code)]
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use metaverse_server::{
+    AppState,
+    config::Config,
+    layers, router,
+    tls::{CertResolver, TlsListener},
+    with_docs,
+};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+pub const PASSWORD: &str = "correct horse battery staple";
+
+pub struct TestApp {
+    router: Router,
+    pub state: AppState,
+    pub pool: PgPool,
+    asset_dir: PathBuf,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub body: Value,
+}
+
+impl TestApp {
+    pub fn new(pool: PgPool) -> Self {
+        let mut config = Config::default();
+        config.auth.jwt_secret = "integration-test-secret".to_string();
+        Self::with_config(pool, config)
+    }
+
+    /// Uploads go to a directory of the app's own, removed when it is
+    /// dropped, so tests deleting files can't remove each other's.
+    pub fn with_config(pool: PgPool, mut config: Config) -> Self {
+        let asset_dir =
+            std::env::temp_dir().join(format!("metaverse-test-assets-{}", uuid::Uuid::new_v4()));
+        config.assets.dir = asset_dir.clone();
+        let state = AppState::postgres(pool.clone(), Arc::new(config));
+        let (api, openapi) = router(state.clone()).split_for_parts();
+        Self {
+            router: layers::apply(with_docs(api, openapi), &state.config),
+            state,
+            pool,
+            asset_dir,
+        }
+    }
+
+    /// Serves the router on a local port, for tests that need a real socket
+    /// such as WebSocket upgrades.
+    pub async fn serve(&self) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, self.router.clone()).into_future());
+        addr
+    }
+
+    /// Like [`TestApp::serve`], but over HTTPS with the given certificates.
+    pub async fn serve_tls(&self, certs: Arc<CertResolver>) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let listener = TlsListener::new(listener, certs).unwrap();
+        tokio::spawn(axum::serve(listener, self.router.clone()).into_future());
+        addr
+    }
+
+    /// Sends a request as-is, for paths outside the API.
+    pub async fn raw(&self, request: Request<Body>) -> axum::response::Response {
+        self.router.clone().oneshot(request).await.unwrap()
+    }
+
+    /// Sends a request to `/api/v1{path}`, as JSON when a body is given.
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("/api/v1{path}"));
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {token}"));
+        }
+        let request = match body {
+            Some(body) => request
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        TestResponse { status, body }
+    }
+
+    pub async fn get(&self, path: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::GET, path, token, None).await
+    }
+
+    pub async fn post(&self, path: &str, token: Option<&str>, body: Value) -> TestResponse {
+        self.request(Method::POST, path, token, Some(body)).await
+    }
+
+    /// Uploads `content` as the `file` field of a multipart form.
+    pub async fn upload(
+        &self,
+        token: &str,
+        file_name: &str,
+        content_type: &str,
+        content: &[u8],
+    ) -> TestResponse {
+        const BOUNDARY: &str = "test-boundary";
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\nContent-Type: {content_type}\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(content);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+        let request = Request::post("/api/v1/assets/upload")
+            .header("Authorization", format!("Bearer {token}"))
+            .header(
+                "Content-Type",
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        TestResponse { status, body }
+    }
+
+    pub async fn signup(&self, username: &str, role: &str) -> StatusCode {
+        self.post(
+            "/common/signup",
+            None,
+            json!({
+                "username": username,
+                "email_id": format!("{username}@example.com"),
+                "password": PASSWORD,
+                "avatar_id": null,
+                "role": role,
+            }),
+        )
+        .await
+        .status
+    }
+
+    pub async fn signin(&self, username: &str) -> String {
+        let response = self
+            .post(
+                "/common/signin",
+                None,
+                json!({ "username": username, "password": PASSWORD }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "signin as {username}");
+        response.body["token"].as_str().unwrap().to_string()
+    }
+
+    /// Signs up a regular user and returns its token.
+    pub async fn user_token(&self, username: &str) -> String {
+        assert_eq!(self.signup(username, "User").await, StatusCode::CREATED);
+        self.signin(username).await
+    }
+
+    /// Signs up an admin and returns its token.
+    pub async fn admin_token(&self, username: &str) -> String {
+        assert_eq!(self.signup(username, "Admin").await, StatusCode::CREATED);
+        self.signin(username).await
+    }
+
+    pub async fn user_id(&self, username: &str) -> i32 {
+        sqlx::query_scalar("SELECT id FROM users WHERE username = $1")
+            .bind(username)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    pub async fn create_world(&self, admin: &str, name: &str) -> i32 {
+        let response = self
+            .post(
+                "/worlds/create",
+                Some(admin),
+                json!({
+                    "name": name,
+                    "description": "A world for tests",
+                    "thumbnail_url": "https://example.com/world.png",
+                    "is_public": true,
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+        sqlx::query_scalar("SELECT id FROM worlds WHERE name = $1")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    pub async fn create_map(&self, admin: &str, world_id: i32) -> i32 {
+        let response = self
+            .post(
+                "/map/create",
+                Some(admin),
+                json!({
+                    "world_id": world_id,
+                    "name": "Ground floor",
+                    "width": 64,
+                    "height": 48,
+                    "background_url": "https://example.com/map.png",
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        response.body["map_id"].as_i64().unwrap() as i32
+    }
+
+    pub async fn create_space(&self, admin: &str, map_id: i32, name: &str) -> i32 {
+        let response = self
+            .post(
+                "/space/create",
+                Some(admin),
+                json!({
+                    "map_id": map_id,
+                    "name": name,
+                    "description": "A room",
+                    "width": 20,
+                    "height": 10,
+                    "background_url": "https://example.com/space.png",
+                    "thumbnail_url": "https://example.com/space-thumb.png",
+                    "max_occupancy": 8,
+                    "is_private": false,
+                    "default_spawn_x": 2,
+                    "default_spawn_y": 3,
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+        sqlx::query_scalar("SELECT id FROM spaces WHERE name = $1")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    pub async fn create_template(&self, admin: &str, name: &str) -> i32 {
+        let response = self
+            .post(
+                "/element/create_new_element",
+                Some(admin),
+                json!({
+                    "name": name,
+                    "element_type": "Portal",
+                    "image_url": "https://example.com/door.png",
+                    "model_url": "https://example.com/door.glb",
+                    "width": 1,
+                    "height": 2,
+                    "is_collidable": true,
+                    "interaction_data": {},
+                    "physics_properties": {},
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+        sqlx::query_scalar("SELECT id FROM element_templates WHERE name = $1")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.asset_dir);
+    }
+}
@@ -0,0 +1,68 @@
+mod harness;
+
+use axum::http::StatusCode;
+use harness::TestApp;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn creates_and_fetches_a_map(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let user = app.user_token("alice").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+
+    let map_id = app.create_map(&admin, world_id).await;
+    let fetched = app
+        .post("/map/get_map", Some(&user), json!({ "map_id": map_id }))
+        .await;
+
+    assert_eq!(fetched.status, StatusCode::OK);
+    assert_eq!(
+        fetched.body,
+        json!({
+            "map_id": map_id,
+            "world_id": world_id,
+            "name": "Ground floor",
+            "width": 64,
+            "height": 48,
+            "background_url": "https://example.com/map.png",
+        })
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn unknown_map_is_not_found(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.user_token("alice").await;
+
+    let response = app
+        .post("/map/get_map", Some(&user), json!({ "map_id": 404 }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn users_cannot_create_maps(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let user = app.user_token("alice").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+
+    let response = app
+        .post(
+            "/map/create",
+            Some(&user),
+            json!({
+                "world_id": world_id,
+                "name": "Mine",
+                "width": 1,
+                "height": 1,
+                "background_url": "",
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,117 @@
+mod harness;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use harness::TestApp;
+use metaverse_core::db::{self, MigrationState};
+use serde_json::json;
+use sqlx::PgPool;
+
+// The probes live outside /api/v1, so these go through the router directly.
+async fn probe(app: &TestApp, path: &str) -> (StatusCode, String) {
+    let response = app
+        .raw(Request::get(path).body(Body::empty()).unwrap())
+        .await;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn healthz_is_always_ok(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    assert_eq!(
+        probe(&app, "/healthz").await,
+        (StatusCode::OK, "ok".to_string())
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn readyz_checks_migrations(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let (status, _) = probe(&app, "/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let version: i64 = sqlx::query_scalar(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT max(version) FROM _sqlx_migrations) RETURNING version",
+    )
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    let (status, body) = probe(&app, "/readyz").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["pending_migrations"], json!([version]));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn metrics_count_requests_and_auth_failures(pool: PgPool) {
+    let app = TestApp::new(pool);
+    app.signup("alice", "User").await;
+    let user = app.signin("alice").await;
+    app.post(
+        "/common/signin",
+        None,
+        json!({ "username": "alice", "password": "wrong" }),
+    )
+    .await;
+    app.get("/worlds/get_worlds", None).await;
+    app.post("/worlds/create", Some(&user), json!({})).await;
+
+    let (status, body) = probe(&app, "/metrics").await;
+
+    assert_eq!(status, StatusCode::OK);
+    for line in [
+        r#"metaverse_http_requests_total{method="POST",route="/api/v1/common/signin",status="200"} 1"#,
+        r#"metaverse_http_requests_total{method="POST",route="/api/v1/common/signin",status="401"} 1"#,
+        r#"metaverse_http_request_duration_seconds_count{method="POST",route="/api/v1/common/signin"} 2"#,
+        r#"metaverse_auth_failures_total{reason="bad_credentials"} 1"#,
+        r#"metaverse_auth_failures_total{reason="missing_token"} 1"#,
+        r#"metaverse_auth_failures_total{reason="not_admin"} 1"#,
+        "metaverse_realtime_connections 0",
+        "metaverse_db_pool_max_connections",
+    ] {
+        assert!(body.contains(line), "missing {line:?} in\n{body}");
+    }
+}
+
+#[sqlx::test(migrations = false)]
+async fn migrations_apply_and_revert(pool: PgPool) {
+    let versions: Vec<i64> = db::MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| m.version)
+        .collect();
+    let states = || async {
+        let mut conn = pool.acquire().await.unwrap();
+        db::migration_status(&mut conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.state)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(db::migrate_up(&pool).await.unwrap(), versions);
+    assert!(states().await.iter().all(|s| *s == MigrationState::Applied));
+    assert!(db::migrate_up(&pool).await.unwrap().is_empty());
+
+    let reverted = db::migrate_down(&pool, Some(0)).await.unwrap();
+    assert_eq!(reverted, versions.iter().rev().copied().collect::<Vec<_>>());
+    assert!(states().await.iter().all(|s| *s == MigrationState::Pending));
+    let users_table: Option<String> = sqlx::query_scalar("SELECT to_regclass('users')::text")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users_table, None);
+
+    assert_eq!(db::migrate_up(&pool).await.unwrap(), versions);
+    let app = TestApp::new(pool);
+    assert_eq!(probe(&app, "/readyz").await.0, StatusCode::OK);
+}
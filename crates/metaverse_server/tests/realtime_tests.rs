@@ -0,0 +1,656 @@
+mod harness;
+
+use axum::{body::Body, http::Request};
+use futures_util::{SinkExt, StreamExt};
+use harness::TestApp;
+use metaverse_core::realtime::{ClientMessage, ElementState, PresentUser, ServerMessage};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{Message, client::IntoClientRequest, protocol::frame::coding::CloseCode},
+};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn connect(
+    addr: SocketAddr,
+    token: &str,
+) -> Result<Socket, tokio_tungstenite::tungstenite::Error> {
+    let mut request = format!("ws://{addr}/api/v1/realtime/ws")
+        .into_client_request()
+        .unwrap();
+    request
+        .headers_mut()
+        .insert("Authorization", format!("Bearer {token}").parse().unwrap());
+    connect_async(request).await.map(|(socket, _)| socket)
+}
+
+async fn send(socket: &mut Socket, message: ClientMessage) {
+    let text = serde_json::to_string(&message).unwrap();
+    socket.send(Message::Text(text.into())).await.unwrap();
+}
+
+async fn next_frame(socket: &mut Socket) -> Message {
+    tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("timed out waiting for a frame")
+        .expect("socket closed")
+        .unwrap()
+}
+
+async fn receive(socket: &mut Socket) -> ServerMessage {
+    match next_frame(socket).await {
+        Message::Text(text) => serde_json::from_str(&text).unwrap(),
+        other => panic!("expected a text frame, got {other:?}"),
+    }
+}
+
+/// An admin, a space 20x10 with spawn (2, 3), and the space's id.
+async fn space(app: &TestApp) -> i32 {
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    app.create_space(&admin, map_id, "Kitchen").await
+}
+
+/// Places a 1x1 interactive element at (x, y) and returns its id.
+async fn place(
+    app: &TestApp,
+    admin: &str,
+    space_id: i32,
+    name: &str,
+    x: i32,
+    y: i32,
+    interaction: Value,
+) -> i32 {
+    place_with_schema(app, admin, space_id, name, x, y, interaction, Value::Null).await
+}
+
+/// Like [`place`], for a template with a `properties_schema`.
+#[allow(clippy::too_many_arguments)]
+async fn place_with_schema(
+    app: &TestApp,
+    admin: &str,
+    space_id: i32,
+    name: &str,
+    x: i32,
+    y: i32,
+    interaction: Value,
+    properties_schema: Value,
+) -> i32 {
+    let template = app
+        .post(
+            "/element/create_new_element",
+            Some(admin),
+            json!({
+                "name": name,
+                "element_type": "Interactive",
+                "image_url": "",
+                "model_url": "",
+                "width": 1,
+                "height": 1,
+                "is_collidable": false,
+                "interaction_data": interaction,
+                "physics_properties": {},
+                "properties_schema": properties_schema,
+            }),
+        )
+        .await;
+    assert_eq!(template.status, axum::http::StatusCode::CREATED);
+    let template_id: i32 = sqlx::query_scalar("SELECT id FROM element_templates WHERE name = $1")
+        .bind(name)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    let placed = app
+        .post(
+            "/element/create_space_element",
+            Some(admin),
+            json!({
+                "space_id": space_id,
+                "template_id": template_id,
+                "x": x,
+                "y": y,
+                "z_index": 0,
+                "rotation": 0,
+                "custom_properties": {},
+            }),
+        )
+        .await;
+    assert_eq!(placed.status, axum::http::StatusCode::CREATED);
+    sqlx::query_scalar("SELECT id FROM space_elements WHERE template_id = $1")
+        .bind(template_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn relays_presence_within_a_space(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let space_id = space(&app).await;
+    let alice_token = app.user_token("alice").await;
+    let bob_token = app.user_token("bob").await;
+    let alice_id = app.user_id("alice").await;
+    let bob_id = app.user_id("bob").await;
+    let addr = app.serve().await;
+
+    let mut alice = connect(addr, &alice_token).await.unwrap();
+    send(&mut alice, ClientMessage::Join { space_id }).await;
+    let ServerMessage::Joined { users, .. } = receive(&mut alice).await else {
+        panic!("alice should have joined");
+    };
+    assert!(users.is_empty());
+
+    let mut bob = connect(addr, &bob_token).await.unwrap();
+    send(&mut bob, ClientMessage::Join { space_id }).await;
+    let ServerMessage::Joined { you, users, .. } = receive(&mut bob).await else {
+        panic!("bob should have joined");
+    };
+    let spawned = |user_id| PresentUser {
+        user_id,
+        x: 2,
+        y: 3,
+        rotation: 0,
+    };
+    assert_eq!(you, spawned(bob_id));
+    assert_eq!(users, vec![spawned(alice_id)]);
+    assert!(matches!(
+        receive(&mut alice).await,
+        ServerMessage::UserJoined(user) if user == spawned(bob_id)
+    ));
+
+    let metrics = app
+        .raw(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await;
+    let metrics = axum::body::to_bytes(metrics.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let metrics = String::from_utf8(metrics.to_vec()).unwrap();
+    assert!(metrics.contains(&format!(
+        "metaverse_space_users{{space_id=\"{space_id}\"}} 2"
+    )));
+    assert!(metrics.contains("metaverse_realtime_connections 2"));
+
+    send(
+        &mut bob,
+        ClientMessage::Move {
+            x: 7,
+            y: 8,
+            rotation: 90,
+        },
+    )
+    .await;
+    assert!(matches!(
+        receive(&mut alice).await,
+        ServerMessage::UserMoved(PresentUser { user_id, x: 7, y: 8, rotation: 90 }) if user_id == bob_id
+    ));
+
+    send(
+        &mut bob,
+        ClientMessage::Move {
+            x: 20,
+            y: 0,
+            rotation: 0,
+        },
+    )
+    .await;
+    assert!(matches!(
+        receive(&mut bob).await,
+        ServerMessage::Error { .. }
+    ));
+
+    send(&mut bob, ClientMessage::Leave).await;
+    assert!(matches!(
+        receive(&mut alice).await,
+        ServerMessage::UserLeft { user_id } if user_id == bob_id
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn shutdown_saves_positions_and_closes_sessions(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let space_id = space(&app).await;
+    let token = app.user_token("alice").await;
+    let alice_id = app.user_id("alice").await;
+    let addr = app.serve().await;
+
+    let mut alice = connect(addr, &token).await.unwrap();
+    send(&mut alice, ClientMessage::Join { space_id }).await;
+    receive(&mut alice).await;
+    send(
+        &mut alice,
+        ClientMessage::Move {
+            x: 5,
+            y: 6,
+            rotation: 180,
+        },
+    )
+    .await;
+    // Moves aren't acknowledged; a bad message is, and arrives after the move
+    // has been handled.
+    alice.send(Message::Text("not json".into())).await.unwrap();
+    receive(&mut alice).await;
+
+    let hub = app.state.realtime.clone();
+    hub.begin_shutdown();
+    assert!(matches!(receive(&mut alice).await, ServerMessage::Shutdown));
+    let Message::Close(Some(frame)) = next_frame(&mut alice).await else {
+        panic!("expected a close frame");
+    };
+    assert_eq!(frame.code, CloseCode::Restart);
+    hub.close_sessions().await;
+
+    let (status, x, y, rotation): (String, i32, i32, i32) =
+        sqlx::query_as("SELECT status::text, x, y, rotation FROM user_sessions WHERE user_id = $1")
+            .bind(alice_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!((status.as_str(), x, y, rotation), ("Inactive", 5, 6, 180));
+
+    let (last_x, last_y, online): (i32, i32, bool) =
+        sqlx::query_as("SELECT last_x, last_y, is_online FROM users WHERE id = $1")
+            .bind(alice_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!((last_x, last_y, online), (5, 6, false));
+
+    assert!(connect(addr, &token).await.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn disconnects_sessions_closed_elsewhere(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let space_id = space(&app).await;
+    let alice_token = app.user_token("alice").await;
+    let bob_token = app.user_token("bob").await;
+    let alice_id = app.user_id("alice").await;
+    let addr = app.serve().await;
+
+    let mut alice = connect(addr, &alice_token).await.unwrap();
+    send(&mut alice, ClientMessage::Join { space_id }).await;
+    receive(&mut alice).await;
+    let mut bob = connect(addr, &bob_token).await.unwrap();
+    send(&mut bob, ClientMessage::Join { space_id }).await;
+    receive(&mut bob).await;
+    receive(&mut alice).await;
+
+    // What `metaverse-admin sessions kill` does.
+    sqlx::query("UPDATE user_sessions SET status = 'Inactive' WHERE user_id = $1")
+        .bind(alice_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    app.state.realtime.evict_closed().await;
+
+    let Message::Close(Some(frame)) = next_frame(&mut alice).await else {
+        panic!("expected a close frame");
+    };
+    assert_eq!(frame.code, CloseCode::Policy);
+    assert!(matches!(
+        receive(&mut bob).await,
+        ServerMessage::UserLeft { user_id } if user_id == alice_id
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn connecting_requires_a_token(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let addr = app.serve().await;
+
+    assert!(connect(addr, "not-a-token").await.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn uses_interactive_elements_within_range(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+    // Everyone arrives at (2, 3).
+    let sign = place(
+        &app,
+        &admin,
+        space_id,
+        "Sign",
+        3,
+        3,
+        json!({ "action": "show_text", "title": "Menu", "text": "Soup" }),
+    )
+    .await;
+    let far_sign = place(
+        &app,
+        &admin,
+        space_id,
+        "Far sign",
+        15,
+        8,
+        json!({ "action": "show_text", "text": "Hello" }),
+    )
+    .await;
+    let link = place(
+        &app,
+        &admin,
+        space_id,
+        "Link",
+        15,
+        8,
+        json!({ "action": "open_url", "url": "https://example.com", "range": 20 }),
+    )
+    .await;
+    let lamp = place(
+        &app,
+        &admin,
+        space_id,
+        "Lamp",
+        2,
+        5,
+        json!({ "action": "toggle" }),
+    )
+    .await;
+    let bench = place(
+        &app,
+        &admin,
+        space_id,
+        "Bench",
+        1,
+        2,
+        json!({ "action": "start_call", "room": "lounge" }),
+    )
+    .await;
+    let pad = place(
+        &app,
+        &admin,
+        space_id,
+        "Pad",
+        1,
+        3,
+        json!({ "action": "teleport", "x": 10, "y": 5 }),
+    )
+    .await;
+    let alice_token = app.user_token("alice").await;
+    let bob_token = app.user_token("bob").await;
+    let carol_token = app.user_token("carol").await;
+    let bob_id = app.user_id("bob").await;
+    let addr = app.serve().await;
+
+    let mut alice = connect(addr, &alice_token).await.unwrap();
+    send(&mut alice, ClientMessage::Join { space_id }).await;
+    receive(&mut alice).await;
+    let mut bob = connect(addr, &bob_token).await.unwrap();
+    send(&mut bob, ClientMessage::Join { space_id }).await;
+    receive(&mut bob).await;
+    receive(&mut alice).await;
+
+    // Only the user who used them sees text and pages.
+    send(
+        &mut bob,
+        ClientMessage::Interact {
+            element_id: sign,
+            version: None,
+            state: None,
+        },
+    )
+    .await;
+    assert_eq!(
+        receive(&mut bob).await,
+        ServerMessage::ShowText {
+            element_id: sign,
+            title: Some("Menu".to_string()),
+            text: "Soup".to_string(),
+        }
+    );
+    send(
+        &mut bob,
+        ClientMessage::Interact {
+            element_id: far_sign,
+            version: None,
+            state: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        receive(&mut bob).await,
+        ServerMessage::Error { message } if message == "too far away to use element"
+    ));
+    send(
+        &mut bob,
+        ClientMessage::Interact {
+            element_id: link,
+            version: None,
+            state: None,
+        },
+    )
+    .await;
+    assert_eq!(
+        receive(&mut bob).await,
+        ServerMessage::OpenUrl {
+            element_id: link,
+            url: "https://example.com".to_string(),
+        }
+    );
+
+    // Everyone in the space sees toggles, calls and teleports.
+    send(
+        &mut bob,
+        ClientMessage::Interact {
+            element_id: lamp,
+            version: None,
+            state: None,
+        },
+    )
+    .await;
+    let lit = ElementState {
+        element_id: lamp,
+        state: json!({ "on": true }),
+        version: 1,
+    };
+    for socket in [&mut alice, &mut bob] {
+        assert_eq!(
+            receive(socket).await,
+            ServerMessage::ElementChanged(lit.clone())
+        );
+    }
+    send(
+        &mut bob,
+        ClientMessage::Interact {
+            element_id: bench,
+            version: None,
+            state: None,
+        },
+    )
+    .await;
+    for socket in [&mut alice, &mut bob] {
+        assert_eq!(
+            receive(socket).await,
+            ServerMessage::CallStarted {
+                element_id: bench,
+                room: "lounge".to_string(),
+                user_id: bob_id,
+            }
+        );
+    }
+    send(
+        &mut bob,
+        ClientMessage::Interact {
+            element_id: pad,
+            version: None,
+            state: None,
+        },
+    )
+    .await;
+    for socket in [&mut alice, &mut bob] {
+        assert!(matches!(
+            receive(socket).await,
+            ServerMessage::UserMoved(PresentUser { user_id, x: 10, y: 5, .. }) if user_id == bob_id
+        ));
+    }
+
+    // Whoever joins later sees the lamp lit.
+    let mut carol = connect(addr, &carol_token).await.unwrap();
+    send(&mut carol, ClientMessage::Join { space_id }).await;
+    let ServerMessage::Joined { elements, .. } = receive(&mut carol).await else {
+        panic!("carol should have joined");
+    };
+    assert_eq!(elements, vec![lit]);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn element_state_is_versioned_and_survives_restarts(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+    let lamp = place(
+        &app,
+        &admin,
+        space_id,
+        "Lamp",
+        2,
+        4,
+        json!({ "action": "toggle" }),
+    )
+    .await;
+    let alice_token = app.user_token("alice").await;
+    let addr = app.serve().await;
+
+    let mut alice = connect(addr, &alice_token).await.unwrap();
+    send(&mut alice, ClientMessage::Join { space_id }).await;
+    let ServerMessage::Joined { elements, .. } = receive(&mut alice).await else {
+        panic!("alice should have joined");
+    };
+    assert!(elements.is_empty());
+    send(
+        &mut alice,
+        ClientMessage::Interact {
+            element_id: lamp,
+            version: Some(0),
+            state: None,
+        },
+    )
+    .await;
+    let lit = ElementState {
+        element_id: lamp,
+        state: json!({ "on": true }),
+        version: 1,
+    };
+    assert_eq!(
+        receive(&mut alice).await,
+        ServerMessage::ElementChanged(lit.clone())
+    );
+
+    // A change based on what the lamp was before is refused.
+    send(
+        &mut alice,
+        ClientMessage::Interact {
+            element_id: lamp,
+            version: Some(0),
+            state: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        receive(&mut alice).await,
+        ServerMessage::Error { message } if message == "element was changed by someone else"
+    ));
+
+    // A fresh server starts from the saved state.
+    let restarted = TestApp::new(app.pool.clone());
+    let addr = restarted.serve().await;
+    let mut alice = connect(addr, &alice_token).await.unwrap();
+    send(&mut alice, ClientMessage::Join { space_id }).await;
+    let ServerMessage::Joined { elements, .. } = receive(&mut alice).await else {
+        panic!("alice should have joined again");
+    };
+    assert_eq!(elements, vec![lit]);
+    send(
+        &mut alice,
+        ClientMessage::Interact {
+            element_id: lamp,
+            version: Some(1),
+            state: None,
+        },
+    )
+    .await;
+    assert_eq!(
+        receive(&mut alice).await,
+        ServerMessage::ElementChanged(ElementState {
+            element_id: lamp,
+            state: json!({ "on": false }),
+            version: 2,
+        })
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn set_state_accepts_only_states_matching_the_schema(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+    let whiteboard = place_with_schema(
+        &app,
+        &admin,
+        space_id,
+        "Whiteboard",
+        2,
+        4,
+        json!({ "action": "set_state" }),
+        json!({
+            "type": "object",
+            "properties": { "text": { "type": "string", "maxLength": 20 } },
+            "additionalProperties": false,
+        }),
+    )
+    .await;
+    let alice_token = app.user_token("alice").await;
+    let addr = app.serve().await;
+    let mut alice = connect(addr, &alice_token).await.unwrap();
+    send(&mut alice, ClientMessage::Join { space_id }).await;
+    receive(&mut alice).await;
+
+    for state in [
+        None,
+        Some(json!({ "text": 42 })),
+        Some(json!({ "text": "Menu", "script": "alert(1)" })),
+    ] {
+        send(
+            &mut alice,
+            ClientMessage::Interact {
+                element_id: whiteboard,
+                version: Some(0),
+                state,
+            },
+        )
+        .await;
+        assert!(matches!(
+            receive(&mut alice).await,
+            ServerMessage::Error { .. }
+        ));
+    }
+    send(
+        &mut alice,
+        ClientMessage::Interact {
+            element_id: whiteboard,
+            version: Some(0),
+            state: Some(json!({ "text": "Soup of the day" })),
+        },
+    )
+    .await;
+    assert_eq!(
+        receive(&mut alice).await,
+        ServerMessage::ElementChanged(ElementState {
+            element_id: whiteboard,
+            state: json!({ "text": "Soup of the day" }),
+            version: 1,
+        })
+    );
+}
@@ -0,0 +1,125 @@
+mod harness;
+
+use axum::http::StatusCode;
+use harness::TestApp;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn creates_fetches_and_deletes_a_space(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let user = app.user_token("alice").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+
+    let fetched = app
+        .post(
+            "/space/get_space",
+            Some(&user),
+            json!({ "space_id": space_id }),
+        )
+        .await;
+    assert_eq!(fetched.status, StatusCode::OK);
+    assert_eq!(fetched.body["name"], "Kitchen");
+    assert_eq!(fetched.body["map_id"], map_id);
+    assert_eq!(fetched.body["max_occupancy"], 8);
+
+    let deleted = app
+        .post(
+            "/space/delete_space",
+            Some(&admin),
+            json!({ "space_id": space_id }),
+        )
+        .await;
+    assert_eq!(deleted.status, StatusCode::OK);
+
+    let gone = app
+        .post(
+            "/space/get_space",
+            Some(&user),
+            json!({ "space_id": space_id }),
+        )
+        .await;
+    assert_eq!(gone.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn deleting_a_space_removes_its_elements(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+    let template_id = app.create_template(&admin, "Door").await;
+    app.post(
+        "/element/create_space_element",
+        Some(&admin),
+        json!({
+            "space_id": space_id,
+            "template_id": template_id,
+            "x": 1,
+            "y": 1,
+            "z_index": 0,
+            "rotation": 0,
+            "custom_properties": {},
+        }),
+    )
+    .await;
+
+    let deleted = app
+        .post(
+            "/space/delete_space",
+            Some(&admin),
+            json!({ "space_id": space_id }),
+        )
+        .await;
+
+    assert_eq!(deleted.status, StatusCode::OK);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn space_admin_routes_reject_users(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let user = app.user_token("alice").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+
+    let create = app
+        .post("/space/create", Some(&user), json!({ "map_id": map_id }))
+        .await;
+    let delete = app
+        .post(
+            "/space/delete_space",
+            Some(&user),
+            json!({ "space_id": space_id }),
+        )
+        .await;
+
+    assert_eq!(create.status, StatusCode::FORBIDDEN);
+    assert_eq!(delete.status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn unknown_space_is_not_found(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let user = app.user_token("alice").await;
+
+    let fetched = app
+        .post("/space/get_space", Some(&user), json!({ "space_id": 404 }))
+        .await;
+    let deleted = app
+        .post(
+            "/space/delete_space",
+            Some(&admin),
+            json!({ "space_id": 404 }),
+        )
+        .await;
+
+    assert_eq!(fetched.status, StatusCode::NOT_FOUND);
+    assert_eq!(deleted.status, StatusCode::NOT_FOUND);
+}
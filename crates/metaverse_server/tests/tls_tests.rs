@@ -0,0 +1,159 @@
+mod harness;
+
+use futures_util::{SinkExt, StreamExt};
+use harness::TestApp;
+use metaverse_core::realtime::{ClientMessage, ServerMessage};
+use metaverse_server::{config::TlsConfig, tls::CertResolver};
+use sqlx::PgPool;
+use std::{
+    fs::File,
+    net::SocketAddr,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    TlsConnector,
+    client::TlsStream,
+    rustls::{ClientConfig, RootCertStore, crypto::ring, pki_types::ServerName},
+};
+use tokio_tungstenite::{
+    WebSocketStream, client_async,
+    tungstenite::{Message, client::IntoClientRequest},
+};
+
+struct Pair {
+    cert: rcgen::Certificate,
+    key_pem: String,
+}
+
+fn generate() -> Pair {
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    Pair {
+        cert,
+        key_pem: key_pair.serialize_pem(),
+    }
+}
+
+// Pushes the modification time forward so the change is seen even on
+// filesystems with coarse timestamps.
+fn write(path: &Path, contents: &str, ahead_secs: u64) {
+    std::fs::write(path, contents).unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(ahead_secs))
+        .unwrap();
+}
+
+fn install(config: &TlsConfig, pair: &Pair, ahead_secs: u64) {
+    write(&config.cert_path, &pair.cert.pem(), ahead_secs);
+    write(&config.key_path, &pair.key_pem, ahead_secs);
+}
+
+/// Opens a TLS connection that trusts any of `pairs`.
+async fn connect_tls(addr: SocketAddr, pairs: &[&Pair]) -> TlsStream<TcpStream> {
+    let mut roots = RootCertStore::empty();
+    for pair in pairs {
+        roots.add(pair.cert.der().clone()).unwrap();
+    }
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    TlsConnector::from(Arc::new(config))
+        .connect(ServerName::try_from("localhost").unwrap(), tcp)
+        .await
+        .unwrap()
+}
+
+fn served_cert(stream: &TlsStream<TcpStream>) -> Vec<u8> {
+    stream.get_ref().1.peer_certificates().unwrap()[0].to_vec()
+}
+
+async fn healthz(mut stream: TlsStream<TcpStream>) -> String {
+    stream
+        .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+async fn receive(socket: &mut WebSocketStream<TlsStream<TcpStream>>) -> ServerMessage {
+    let frame = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .expect("timed out waiting for a frame")
+        .expect("socket closed")
+        .unwrap();
+    match frame {
+        Message::Text(text) => serde_json::from_str(&text).unwrap(),
+        other => panic!("expected a text frame, got {other:?}"),
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn serves_https_and_wss_across_certificate_reloads(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let map_id = app.create_map(&admin, world_id).await;
+    let space_id = app.create_space(&admin, map_id, "Kitchen").await;
+    let token = app.user_token("alice").await;
+
+    let dir = std::env::temp_dir().join(format!("metaverse-tls-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = TlsConfig {
+        cert_path: dir.join("server.crt"),
+        key_path: dir.join("server.key"),
+        reload_interval_secs: 1,
+    };
+    let (first, second) = (generate(), generate());
+    install(&config, &first, 0);
+    let certs = Arc::new(CertResolver::load(&config).unwrap());
+    let addr = app.serve_tls(certs.clone()).await;
+
+    let response = healthz(connect_tls(addr, &[&first]).await).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    let stream = connect_tls(addr, &[&first]).await;
+    let mut request = "wss://localhost/api/v1/realtime/ws"
+        .into_client_request()
+        .unwrap();
+    request
+        .headers_mut()
+        .insert("Authorization", format!("Bearer {token}").parse().unwrap());
+    let (mut socket, _) = client_async(request, stream).await.unwrap();
+    let join = serde_json::to_string(&ClientMessage::Join { space_id }).unwrap();
+    socket.send(Message::Text(join.into())).await.unwrap();
+    assert!(matches!(
+        receive(&mut socket).await,
+        ServerMessage::Joined { .. }
+    ));
+
+    install(&config, &second, 10);
+    assert!(certs.reload_if_changed().unwrap());
+
+    let renewed = connect_tls(addr, &[&first, &second]).await;
+    assert_eq!(served_cert(&renewed), second.cert.der().to_vec());
+    let response = healthz(renewed).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    // The socket opened before the reload is still served.
+    socket.send(Message::Text("not json".into())).await.unwrap();
+    assert!(matches!(
+        receive(&mut socket).await,
+        ServerMessage::Error { .. }
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
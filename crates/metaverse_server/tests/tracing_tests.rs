@@ -0,0 +1,78 @@
+mod harness;
+
+use axum::{body::Body, http::Request};
+use harness::{PASSWORD, TestApp};
+use metaverse_server::{
+    config::{TraceExporter, TracingConfig},
+    telemetry,
+};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tracing_subscriber::layer::SubscriberExt;
+use uuid::Uuid;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const CALLER_SPAN_ID: &str = "00f067aa0ba902b7";
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn exports_request_and_query_spans_to_a_file(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let path = std::env::temp_dir().join(format!("metaverse-traces-{}.jsonl", Uuid::new_v4()));
+    let provider = telemetry::tracer_provider(&TracingConfig {
+        exporter: TraceExporter::File,
+        file_path: path.clone(),
+        ..Default::default()
+    })
+    .unwrap()
+    .unwrap();
+
+    // The test runtime is single threaded, so a thread-local subscriber sees
+    // everything the request does.
+    let subscriber = tracing_subscriber::registry().with(telemetry::trace_layer(&provider));
+    let guard = tracing::subscriber::set_default(subscriber);
+    app.raw(
+        Request::get("/api/v1/worlds/get_worlds")
+            .header("Authorization", format!("Bearer {admin}"))
+            .header("traceparent", format!("00-{TRACE_ID}-{CALLER_SPAN_ID}-01"))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    app.post(
+        "/common/signin",
+        None,
+        json!({ "username": "root", "password": PASSWORD }),
+    )
+    .await;
+    drop(guard);
+    provider.shutdown().unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let spans: Vec<Value> = contents
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let named = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span["name"] == name)
+            .unwrap_or_else(|| panic!("no {name} span in {spans:#?}"))
+    };
+
+    let request = named("GET /api/v1/worlds/get_worlds");
+    assert_eq!(request["trace_id"], TRACE_ID);
+    assert_eq!(request["parent_span_id"], CALLER_SPAN_ID);
+    assert_eq!(request["kind"], "server");
+    assert_eq!(request["service"], "metaverse");
+
+    let query = named("worlds.list");
+    assert_eq!(query["trace_id"], TRACE_ID);
+    assert_eq!(query["parent_span_id"], request["span_id"]);
+    assert_eq!(query["kind"], "client");
+    assert_eq!(query["attributes"]["db.system"], "postgresql");
+
+    named("users.credentials");
+    assert!(!contents.contains(PASSWORD));
+}
@@ -0,0 +1,231 @@
+mod harness;
+
+use axum::http::StatusCode;
+use harness::TestApp;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn user_routes_require_a_token(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let avatars = app.get("/user/avatars", None).await;
+    let metadata = app
+        .post("/user/metadata", None, json!({ "avatar_id": 1 }))
+        .await;
+    let bulk = app
+        .post("/user/metadata/bulk", None, json!({ "ids": [1] }))
+        .await;
+
+    assert_eq!(avatars.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(metadata.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(bulk.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn picks_an_avatar_and_shows_it_to_others(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let alice = app.user_token("alice").await;
+    let bob = app.user_token("bob").await;
+    app.post(
+        "/common/create_avatar",
+        Some(&admin),
+        json!({ "name": "Robot", "image_url": "https://example.com/robot.png" }),
+    )
+    .await;
+
+    let avatars = app.get("/user/avatars", Some(&alice)).await;
+    assert_eq!(avatars.status, StatusCode::OK);
+    let avatar_id = avatars.body["avatars"][0]["id"].clone();
+
+    let chosen = app
+        .post(
+            "/user/metadata",
+            Some(&alice),
+            json!({ "avatar_id": avatar_id }),
+        )
+        .await;
+    assert_eq!(chosen.status, StatusCode::OK);
+
+    let alice_id = app.user_id("alice").await;
+    let bob_id = app.user_id("bob").await;
+    let bulk = app
+        .post(
+            "/user/metadata/bulk",
+            Some(&bob),
+            json!({ "ids": [bob_id, alice_id] }),
+        )
+        .await;
+    assert_eq!(bulk.status, StatusCode::OK);
+    assert_eq!(
+        bulk.body["avatars"],
+        json!([
+            { "id": alice_id, "image_url": "https://example.com/robot.png" },
+            { "id": bob_id, "image_url": null },
+        ])
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn bulk_metadata_needs_ids(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let token = app.user_token("alice").await;
+
+    let response = app
+        .post("/user/metadata/bulk", Some(&token), json!({ "ids": [] }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn composes_avatars_from_tinted_parts(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let alice = app.user_token("alice").await;
+    let bob = app.user_token("bob").await;
+    for (slot, name) in [
+        ("body", "Slim"),
+        ("hair", "Bob cut"),
+        ("outfit", "Overalls"),
+        ("accessory", "Glasses"),
+        ("accessory", "Scarf"),
+    ] {
+        let created = app
+            .post(
+                "/common/create_avatar_part",
+                Some(&admin),
+                json!({ "slot": slot, "name": name, "image_url": format!("/parts/{name}.png") }),
+            )
+            .await;
+        assert_eq!(created.status, StatusCode::CREATED);
+    }
+    let parts = app.get("/user/avatar_parts", Some(&alice)).await.body["parts"].clone();
+    let id = |name: &str| {
+        parts
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|part| part["name"] == name)
+            .unwrap()["id"]
+            .clone()
+    };
+
+    // Worn in any order; drawn body, outfit, hair, then accessories.
+    let set = app
+        .post(
+            "/user/set_avatar",
+            Some(&alice),
+            json!({ "parts": [
+                { "part_id": id("Scarf"), "tint": "#B22222" },
+                { "part_id": id("Bob cut"), "tint": "#a0522d" },
+                { "part_id": id("Slim") },
+                { "part_id": id("Glasses") },
+                { "part_id": id("Overalls") },
+            ] }),
+        )
+        .await;
+    assert_eq!(set.status, StatusCode::OK);
+
+    let alice_id = app.user_id("alice").await;
+    let avatar = app
+        .post(
+            "/user/get_avatar",
+            Some(&bob),
+            json!({ "user_id": alice_id }),
+        )
+        .await;
+    assert_eq!(avatar.status, StatusCode::OK);
+    let layers: Vec<_> = avatar.body["layers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|layer| (layer["name"].clone(), layer["tint"].clone()))
+        .collect();
+    assert_eq!(
+        layers,
+        [
+            (json!("Slim"), json!(null)),
+            (json!("Overalls"), json!(null)),
+            (json!("Bob cut"), json!("#a0522d")),
+            (json!("Scarf"), json!("#b22222")),
+            (json!("Glasses"), json!(null)),
+        ]
+    );
+    assert_eq!(avatar.body["layers"][0]["slot"], "body");
+    assert_eq!(avatar.body["layers"][0]["image_url"], "/parts/Slim.png");
+
+    let bob_id = app.user_id("bob").await;
+    let bulk = app
+        .post(
+            "/user/metadata/bulk",
+            Some(&bob),
+            json!({ "ids": [alice_id, bob_id] }),
+        )
+        .await
+        .body;
+    assert_eq!(bulk["avatars"][0]["composition"], avatar.body);
+    assert!(bulk["avatars"][1].get("composition").is_none());
+    let nobody = app
+        .post(
+            "/user/get_avatar",
+            Some(&alice),
+            json!({ "user_id": bob_id }),
+        )
+        .await;
+    assert_eq!(nobody.body, json!({ "layers": [] }));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn refuses_avatars_that_do_not_fit_together(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let alice = app.user_token("alice").await;
+    let user_part = app
+        .post(
+            "/common/create_avatar_part",
+            Some(&alice),
+            json!({ "slot": "hair", "name": "Mohawk", "image_url": "/parts/mohawk.png" }),
+        )
+        .await;
+    assert_eq!(user_part.status, StatusCode::FORBIDDEN);
+    for (slot, name) in [("body", "Slim"), ("body", "Broad"), ("hair", "Bob cut")] {
+        app.post(
+            "/common/create_avatar_part",
+            Some(&admin),
+            json!({ "slot": slot, "name": name, "image_url": format!("/parts/{name}.png") }),
+        )
+        .await;
+    }
+    let set = |parts: serde_json::Value| {
+        let app = &app;
+        let alice = &alice;
+        async move {
+            app.post("/user/set_avatar", Some(alice), json!({ "parts": parts }))
+                .await
+                .status
+        }
+    };
+
+    assert_eq!(
+        set(json!([{ "part_id": 3 }])).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        set(json!([{ "part_id": 1 }, { "part_id": 2 }])).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        set(json!([{ "part_id": 1 }, { "part_id": 99 }])).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        set(json!([{ "part_id": 1, "tint": "red" }])).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        set(json!([{ "part_id": 1 }, { "part_id": 3, "tint": "#000000" }])).await,
+        StatusCode::OK
+    );
+}
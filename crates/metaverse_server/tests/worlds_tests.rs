@@ -0,0 +1,50 @@
+mod harness;
+
+use axum::http::StatusCode;
+use harness::TestApp;
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn admins_create_worlds_everyone_lists_them(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let admin = app.admin_token("root").await;
+    let user = app.user_token("alice").await;
+
+    let world_id = app.create_world(&admin, "Lobby").await;
+    let listed = app.get("/worlds/get_worlds", Some(&user)).await;
+
+    assert_eq!(listed.status, StatusCode::OK);
+    assert_eq!(listed.body[0]["id"], world_id);
+    assert_eq!(listed.body[0]["name"], "Lobby");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn users_cannot_create_worlds(pool: PgPool) {
+    let app = TestApp::new(pool);
+    let user = app.user_token("alice").await;
+
+    let response = app
+        .post(
+            "/worlds/create",
+            Some(&user),
+            json!({
+                "name": "Mine",
+                "description": "",
+                "thumbnail_url": "",
+                "is_public": true,
+            }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn listing_worlds_requires_a_token(pool: PgPool) {
+    let app = TestApp::new(pool);
+
+    let response = app.get("/worlds/get_worlds", None).await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
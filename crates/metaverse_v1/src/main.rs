@@ -0,0 +1,173 @@
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use metaverse_core::db;
+use metaverse_server::{
+    AppState, assets,
+    config::{Config, ConfigArgs},
+    layers, router, shutdown, telemetry,
+    tls::{CertResolver, TlsListener},
+    with_docs,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Notify, time::Instant};
+use tracing::{error, info, warn};
+
+mod migrate;
+
+#[derive(Parser)]
+#[command(version, about = "Metaverse API server")]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the API server. This is the default.
+    Serve,
+    /// Apply, revert or list database migrations.
+    #[command(subcommand)]
+    Migrate(migrate::MigrateCommand),
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let cli = Cli::parse();
+    match cli.command {
+        None | Some(Command::Serve) => serve(cli.config).await,
+        Some(Command::Migrate(command)) => migrate::run(cli.config, command).await,
+    }
+}
+
+async fn serve(args: ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    // Logging is configured too, so problems are reported before it starts.
+    let config = match Config::load(args) {
+        Ok(config) => Arc::new(config),
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+    let telemetry = match telemetry::init(&config.logging, &config.tracing) {
+        Ok(telemetry) => telemetry,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+
+    // Read before anything starts, so a bad certificate stops startup.
+    let tls = match &config.tls {
+        Some(tls) => match CertResolver::load(tls) {
+            Ok(certs) => Some((Arc::new(certs), tls.reload_interval_secs)),
+            Err(err) => {
+                error!("{}", err);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    info!("Start Server");
+
+    let pool = db::connect(&config.database).await?;
+    if config.database.migrate_on_start {
+        let applied = db::migrate_up(&pool).await?;
+        info!("Applied {} migrations", applied.len());
+    }
+
+    let state = AppState::postgres(pool.clone(), config.clone());
+    let hub = state.realtime.clone();
+    tokio::spawn(
+        hub.clone()
+            .run_flusher(Duration::from_secs(config.realtime.position_flush_secs)),
+    );
+    if config.assets.gc_interval_secs > 0 {
+        tokio::spawn(assets::gc::run_collector(
+            state.assets.clone(),
+            state.storage.clone(),
+            Duration::from_secs(config.assets.orphan_grace_secs),
+            Duration::from_secs(config.assets.gc_interval_secs),
+        ));
+    }
+
+    let (api, openapi) = router(state).split_for_parts();
+    let app = if config.features.docs {
+        with_docs(api, openapi)
+    } else {
+        api
+    };
+    let app = layers::apply(app, &config);
+
+    let listener = tokio::net::TcpListener::bind(config.server.listen_addr).await?;
+
+    // On a signal, stop accepting connections and tell realtime clients to go
+    // elsewhere. Everything after that shares one deadline.
+    let draining = Arc::new(Notify::new());
+    let stop = {
+        let hub = hub.clone();
+        let draining = draining.clone();
+        async move {
+            shutdown::signal().await;
+            hub.begin_shutdown();
+            draining.notify_one();
+        }
+    };
+    let mut server = match tls {
+        Some((certs, reload_interval_secs)) => {
+            tokio::spawn(
+                certs
+                    .clone()
+                    .watch(Duration::from_secs(reload_interval_secs)),
+            );
+            info!("Listening on https://{}", config.server.listen_addr);
+            tokio::spawn(
+                axum::serve(TlsListener::new(listener, certs)?, app)
+                    .with_graceful_shutdown(stop)
+                    .into_future(),
+            )
+        }
+        None => {
+            info!("Listening on http://{}", config.server.listen_addr);
+            tokio::spawn(
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(stop)
+                    .into_future(),
+            )
+        }
+    };
+
+    let deadline = tokio::select! {
+        result = &mut server => {
+            result??;
+            telemetry.shutdown();
+            return Ok(());
+        }
+        _ = draining.notified() => {
+            Instant::now() + Duration::from_secs(config.server.shutdown_timeout_secs)
+        }
+    };
+
+    match tokio::time::timeout_at(deadline, &mut server).await {
+        Ok(result) => result??,
+        Err(_) => {
+            warn!("Connections still open at the shutdown deadline, dropping them");
+            server.abort();
+        }
+    }
+    if tokio::time::timeout_at(deadline, hub.close_sessions())
+        .await
+        .is_err()
+    {
+        warn!("Ran out of time saving realtime sessions");
+    }
+    pool.close().await;
+    info!("Shutdown complete");
+    telemetry.shutdown();
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! `migrate` subcommands, run against the migrations embedded in the binary.
+
+use clap::Subcommand;
+use metaverse_core::db;
+use metaverse_server::{
+    config::{Config, ConfigArgs, LoggingConfig, TracingConfig},
+    telemetry,
+};
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// Apply every pending migration.
+    Up,
+    /// Revert the latest migration, or every migration newer than `--to`.
+    Down {
+        /// Version to go back to; 0 reverts everything.
+        #[arg(long, value_name = "VERSION")]
+        to: Option<i64>,
+    },
+    /// List migrations and whether each has been applied.
+    Status,
+}
+
+pub async fn run(
+    args: ConfigArgs,
+    command: MigrateCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let database = match Config::load_database(args) {
+        Ok(database) => database,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+    let telemetry = telemetry::init(&LoggingConfig::default(), &TracingConfig::default())?;
+    let pool = db::connect(&database).await?;
+
+    match command {
+        MigrateCommand::Up => {
+            let applied = db::migrate_up(&pool).await?;
+            if applied.is_empty() {
+                println!("Nothing to apply");
+            }
+            for version in applied {
+                println!("Applied {version}");
+            }
+        }
+        MigrateCommand::Down { to } => {
+            let reverted = db::migrate_down(&pool, to).await?;
+            if reverted.is_empty() {
+                println!("Nothing to revert");
+            }
+            for version in reverted {
+                println!("Reverted {version}");
+            }
+        }
+        MigrateCommand::Status => {
+            let mut conn = pool.acquire().await?;
+            println!("{:<16}{:<10}DESCRIPTION", "VERSION", "STATE");
+            for migration in db::migration_status(&mut conn).await? {
+                println!(
+                    "{:<16}{:<10}{}",
+                    migration.version,
+                    migration.state.as_str(),
+                    migration.description
+                );
+            }
+        }
+    }
+
+    pool.close().await;
+    telemetry.shutdown();
+    Ok(())
+}
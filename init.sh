@@ -14,7 +14,12 @@ until PGPASSWORD="$PGPASSWORD" psql -h "$PGHOST" -U "$PGUSER" -d postgres -c '\q
     sleep 2
 done
 
-echo "Running migrations using sqlx..."
-sqlx migrate run
+echo "Running migrations..."
+cargo run --bin metaverse_v1 -- migrate up
+
+if [ "${SEED_DEMO:-0}" = "1" ]; then
+    echo "Seeding the demo world..."
+    cargo run --bin metaverse-admin -- seed
+fi
 
 echo "Database setup complete."
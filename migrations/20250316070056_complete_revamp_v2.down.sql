@@ -0,0 +1,29 @@
+-- Drops the whole schema created by the matching up migration, data included.
+
+-- Tables go first; their triggers go with them.
+DROP TABLE IF EXISTS
+    assets,
+    user_permissions,
+    user_relationships,
+    messages,
+    webrtc_connections,
+    room_members,
+    rooms,
+    user_sessions,
+    map_elements,
+    space_elements,
+    element_templates,
+    spaces,
+    maps,
+    worlds,
+    users,
+    avatars
+CASCADE;
+
+DROP FUNCTION IF EXISTS handle_user_disconnect();
+DROP FUNCTION IF EXISTS update_user_last_position();
+
+DROP TYPE IF EXISTS message_type_enum;
+DROP TYPE IF EXISTS session_status_enum;
+DROP TYPE IF EXISTS element_type_enum;
+DROP TYPE IF EXISTS role_enum;
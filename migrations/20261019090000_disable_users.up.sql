@@ -0,0 +1,2 @@
+-- Set by `metaverse-admin users disable`; disabled users can't sign in.
+ALTER TABLE users ADD COLUMN disabled_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,3 @@
+ALTER TABLE space_elements
+    DROP COLUMN IF EXISTS state_version,
+    DROP COLUMN IF EXISTS state;
@@ -0,0 +1,6 @@
+-- State every user in the space shares, such as whether a door is open. Only
+-- interactions change it, and each change bumps `state_version` so a write
+-- based on an older version can be refused. NULL until first changed.
+ALTER TABLE space_elements
+    ADD COLUMN state JSONB,
+    ADD COLUMN state_version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE element_templates DROP COLUMN IF EXISTS properties_schema;
@@ -0,0 +1,3 @@
+-- A JSON Schema the custom_properties of elements placed from the template
+-- must match. NULL accepts anything.
+ALTER TABLE element_templates ADD COLUMN properties_schema JSONB;
@@ -0,0 +1,13 @@
+DROP TRIGGER IF EXISTS on_map_element_insert ON map_elements;
+DROP TRIGGER IF EXISTS on_space_element_insert ON space_elements;
+DROP FUNCTION IF EXISTS pin_template_revision();
+ALTER TABLE map_elements DROP COLUMN IF EXISTS template_revision;
+ALTER TABLE space_elements DROP COLUMN IF EXISTS template_revision;
+
+DROP TRIGGER IF EXISTS on_template_write ON element_templates;
+DROP FUNCTION IF EXISTS record_template_revision();
+DROP TRIGGER IF EXISTS on_template_update ON element_templates;
+DROP FUNCTION IF EXISTS bump_template_revision();
+ALTER TABLE element_templates DROP COLUMN IF EXISTS revision;
+
+DROP TABLE IF EXISTS element_template_revisions;
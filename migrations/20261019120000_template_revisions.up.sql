@@ -0,0 +1,102 @@
+-- Every version of every element template. Editing a template adds a
+-- revision instead of changing what is already placed: each space and map
+-- element pins the revision it was placed at until an admin moves it.
+CREATE TABLE element_template_revisions (
+    id SERIAL PRIMARY KEY,
+    template_id INTEGER NOT NULL REFERENCES element_templates (id),
+    revision INTEGER NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    type element_type_enum NOT NULL,
+    image_url VARCHAR(200) NOT NULL,
+    model_url VARCHAR(200),
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    is_collidable BOOLEAN,
+    interaction_data JSONB,
+    physics_properties JSONB,
+    animation_data JSONB,
+    properties_schema JSONB,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (template_id, revision)
+);
+
+-- The template row always holds its latest revision.
+ALTER TABLE element_templates ADD COLUMN revision INTEGER NOT NULL DEFAULT 1;
+
+INSERT INTO element_template_revisions (template_id, revision, name, type, image_url, model_url, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema)
+SELECT id, revision, name, type, image_url, model_url, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema
+FROM element_templates;
+
+-- Edits that change anything bump the revision...
+CREATE OR REPLACE FUNCTION bump_template_revision()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF (NEW.name, NEW.type, NEW.image_url, NEW.model_url, NEW.width, NEW.height, NEW.is_collidable,
+        NEW.interaction_data, NEW.physics_properties, NEW.animation_data, NEW.properties_schema)
+        IS DISTINCT FROM
+       (OLD.name, OLD.type, OLD.image_url, OLD.model_url, OLD.width, OLD.height, OLD.is_collidable,
+        OLD.interaction_data, OLD.physics_properties, OLD.animation_data, OLD.properties_schema)
+    THEN
+        NEW.revision := OLD.revision + 1;
+    ELSE
+        NEW.revision := OLD.revision;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER on_template_update
+BEFORE UPDATE ON element_templates
+FOR EACH ROW
+EXECUTE FUNCTION bump_template_revision();
+
+-- ...and every new revision is kept.
+CREATE OR REPLACE FUNCTION record_template_revision()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO element_template_revisions (template_id, revision, name, type, image_url, model_url, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema)
+    VALUES (NEW.id, NEW.revision, NEW.name, NEW.type, NEW.image_url, NEW.model_url, NEW.width, NEW.height, NEW.is_collidable, NEW.interaction_data, NEW.physics_properties, NEW.animation_data, NEW.properties_schema)
+    ON CONFLICT (template_id, revision) DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER on_template_write
+AFTER INSERT OR UPDATE ON element_templates
+FOR EACH ROW
+EXECUTE FUNCTION record_template_revision();
+
+-- Placed elements pin their template's latest revision unless told otherwise.
+ALTER TABLE space_elements ADD COLUMN template_revision INTEGER;
+ALTER TABLE map_elements ADD COLUMN template_revision INTEGER;
+UPDATE space_elements SET template_revision = 1;
+UPDATE map_elements SET template_revision = 1;
+ALTER TABLE space_elements
+    ALTER COLUMN template_revision SET NOT NULL,
+    ADD FOREIGN KEY (template_id, template_revision)
+        REFERENCES element_template_revisions (template_id, revision);
+ALTER TABLE map_elements
+    ALTER COLUMN template_revision SET NOT NULL,
+    ADD FOREIGN KEY (template_id, template_revision)
+        REFERENCES element_template_revisions (template_id, revision);
+
+CREATE OR REPLACE FUNCTION pin_template_revision()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.template_revision IS NULL THEN
+        SELECT revision INTO NEW.template_revision
+        FROM element_templates WHERE id = NEW.template_id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER on_space_element_insert
+BEFORE INSERT ON space_elements
+FOR EACH ROW
+EXECUTE FUNCTION pin_template_revision();
+
+CREATE TRIGGER on_map_element_insert
+BEFORE INSERT ON map_elements
+FOR EACH ROW
+EXECUTE FUNCTION pin_template_revision();
@@ -0,0 +1,54 @@
+CREATE OR REPLACE FUNCTION record_template_revision()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO element_template_revisions (template_id, revision, name, type, image_url, model_url, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema)
+    VALUES (NEW.id, NEW.revision, NEW.name, NEW.type, NEW.image_url, NEW.model_url, NEW.width, NEW.height, NEW.is_collidable, NEW.interaction_data, NEW.physics_properties, NEW.animation_data, NEW.properties_schema)
+    ON CONFLICT (template_id, revision) DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION bump_template_revision()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF (NEW.name, NEW.type, NEW.image_url, NEW.model_url, NEW.width, NEW.height, NEW.is_collidable,
+        NEW.interaction_data, NEW.physics_properties, NEW.animation_data, NEW.properties_schema)
+        IS DISTINCT FROM
+       (OLD.name, OLD.type, OLD.image_url, OLD.model_url, OLD.width, OLD.height, OLD.is_collidable,
+        OLD.interaction_data, OLD.physics_properties, OLD.animation_data, OLD.properties_schema)
+    THEN
+        NEW.revision := OLD.revision + 1;
+    ELSE
+        NEW.revision := OLD.revision;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS on_template_model_asset ON element_templates;
+DROP TRIGGER IF EXISTS on_template_image_asset ON element_templates;
+DROP TRIGGER IF EXISTS on_avatar_image_asset ON avatars;
+DROP TRIGGER IF EXISTS on_space_thumbnail_asset ON spaces;
+DROP TRIGGER IF EXISTS on_space_background_asset ON spaces;
+DROP TRIGGER IF EXISTS on_map_background_asset ON maps;
+DROP TRIGGER IF EXISTS on_world_thumbnail_asset ON worlds;
+DROP FUNCTION IF EXISTS fill_asset_url();
+
+ALTER TABLE element_template_revisions
+    DROP COLUMN IF EXISTS model_asset_id,
+    DROP COLUMN IF EXISTS image_asset_id;
+ALTER TABLE element_templates
+    DROP COLUMN IF EXISTS model_asset_id,
+    DROP COLUMN IF EXISTS image_asset_id;
+ALTER TABLE avatars DROP COLUMN IF EXISTS image_asset_id;
+ALTER TABLE spaces
+    DROP COLUMN IF EXISTS thumbnail_asset_id,
+    DROP COLUMN IF EXISTS background_asset_id;
+ALTER TABLE maps DROP COLUMN IF EXISTS background_asset_id;
+ALTER TABLE worlds DROP COLUMN IF EXISTS thumbnail_asset_id;
+
+DROP INDEX IF EXISTS assets_hash_key;
+ALTER TABLE assets
+    DROP COLUMN IF EXISTS storage_key,
+    DROP COLUMN IF EXISTS size_bytes,
+    DROP COLUMN IF EXISTS mime_type;
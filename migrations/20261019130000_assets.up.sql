@@ -0,0 +1,111 @@
+-- Uploaded files. `hash` is the SHA-256 of the content, so each file is only
+-- stored once, under `storage_key` in whichever backend the server uses.
+ALTER TABLE assets
+    ADD COLUMN mime_type VARCHAR(100) NOT NULL DEFAULT '',
+    ADD COLUMN size_bytes BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN storage_key VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE assets
+    ALTER COLUMN mime_type DROP DEFAULT,
+    ALTER COLUMN size_bytes DROP DEFAULT,
+    ALTER COLUMN storage_key DROP DEFAULT;
+CREATE UNIQUE INDEX assets_hash_key ON assets (hash) WHERE hash IS NOT NULL;
+
+-- Entities may point at an asset instead of a free-text URL.
+ALTER TABLE worlds ADD COLUMN thumbnail_asset_id INTEGER REFERENCES assets (id);
+ALTER TABLE maps ADD COLUMN background_asset_id INTEGER REFERENCES assets (id);
+ALTER TABLE spaces
+    ADD COLUMN background_asset_id INTEGER REFERENCES assets (id),
+    ADD COLUMN thumbnail_asset_id INTEGER REFERENCES assets (id);
+ALTER TABLE avatars ADD COLUMN image_asset_id INTEGER REFERENCES assets (id);
+ALTER TABLE element_templates
+    ADD COLUMN image_asset_id INTEGER REFERENCES assets (id),
+    ADD COLUMN model_asset_id INTEGER REFERENCES assets (id);
+ALTER TABLE element_template_revisions
+    ADD COLUMN image_asset_id INTEGER REFERENCES assets (id),
+    ADD COLUMN model_asset_id INTEGER REFERENCES assets (id);
+
+-- While an asset is set, the URL column next to it holds the asset's URL, so
+-- everything that reads URLs keeps working. Arguments: the asset id column,
+-- then the URL column.
+CREATE OR REPLACE FUNCTION fill_asset_url()
+RETURNS TRIGGER AS $$
+DECLARE
+    asset_id INTEGER := (to_jsonb(NEW) ->> TG_ARGV[0])::INTEGER;
+    asset_url TEXT;
+BEGIN
+    IF asset_id IS NOT NULL THEN
+        SELECT url INTO asset_url FROM assets WHERE id = asset_id;
+        -- An unknown asset is left for the foreign key to refuse.
+        IF FOUND THEN
+            NEW := jsonb_populate_record(NEW, jsonb_build_object(TG_ARGV[1], asset_url));
+        END IF;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER on_world_thumbnail_asset
+BEFORE INSERT OR UPDATE ON worlds
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('thumbnail_asset_id', 'thumbnail_url');
+
+CREATE TRIGGER on_map_background_asset
+BEFORE INSERT OR UPDATE ON maps
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('background_asset_id', 'background_url');
+
+CREATE TRIGGER on_space_background_asset
+BEFORE INSERT OR UPDATE ON spaces
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('background_asset_id', 'background_url');
+
+CREATE TRIGGER on_space_thumbnail_asset
+BEFORE INSERT OR UPDATE ON spaces
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('thumbnail_asset_id', 'thumbnail_url');
+
+CREATE TRIGGER on_avatar_image_asset
+BEFORE INSERT OR UPDATE ON avatars
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('image_asset_id', 'image_url');
+
+-- Named to run before on_template_update, which compares the filled URLs.
+CREATE TRIGGER on_template_image_asset
+BEFORE INSERT OR UPDATE ON element_templates
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('image_asset_id', 'image_url');
+
+CREATE TRIGGER on_template_model_asset
+BEFORE INSERT OR UPDATE ON element_templates
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('model_asset_id', 'model_url');
+
+-- Revisions keep the assets they were made with.
+CREATE OR REPLACE FUNCTION bump_template_revision()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF (NEW.name, NEW.type, NEW.image_url, NEW.model_url, NEW.image_asset_id, NEW.model_asset_id,
+        NEW.width, NEW.height, NEW.is_collidable,
+        NEW.interaction_data, NEW.physics_properties, NEW.animation_data, NEW.properties_schema)
+        IS DISTINCT FROM
+       (OLD.name, OLD.type, OLD.image_url, OLD.model_url, OLD.image_asset_id, OLD.model_asset_id,
+        OLD.width, OLD.height, OLD.is_collidable,
+        OLD.interaction_data, OLD.physics_properties, OLD.animation_data, OLD.properties_schema)
+    THEN
+        NEW.revision := OLD.revision + 1;
+    ELSE
+        NEW.revision := OLD.revision;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION record_template_revision()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO element_template_revisions (template_id, revision, name, type, image_url, model_url, image_asset_id, model_asset_id, width, height, is_collidable, interaction_data, physics_properties, animation_data, properties_schema)
+    VALUES (NEW.id, NEW.revision, NEW.name, NEW.type, NEW.image_url, NEW.model_url, NEW.image_asset_id, NEW.model_asset_id, NEW.width, NEW.height, NEW.is_collidable, NEW.interaction_data, NEW.physics_properties, NEW.animation_data, NEW.properties_schema)
+    ON CONFLICT (template_id, revision) DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,2 @@
+DROP VIEW IF EXISTS image_variants;
+DROP TABLE IF EXISTS asset_variants;
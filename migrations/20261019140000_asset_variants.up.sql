@@ -0,0 +1,22 @@
+-- Resized, re-encoded copies of uploaded images, one per size. The file as
+-- uploaded is not kept: an image asset's own key and URL are its `full`
+-- variant's.
+CREATE TABLE asset_variants (
+    asset_id INTEGER NOT NULL REFERENCES assets (id) ON DELETE CASCADE,
+    variant VARCHAR(20) NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    mime_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    storage_key VARCHAR(255) NOT NULL,
+    url VARCHAR(255) NOT NULL,
+    PRIMARY KEY (asset_id, variant)
+);
+
+-- Each image's variant URLs as one JSON object, e.g.
+-- {"thumbnail": "...", "medium": "...", "full": "..."}, for joining onto
+-- whatever shows the image.
+CREATE VIEW image_variants AS
+SELECT asset_id, jsonb_object_agg(variant, url) AS urls
+FROM asset_variants
+GROUP BY asset_id;
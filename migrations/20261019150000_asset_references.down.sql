@@ -0,0 +1,24 @@
+DROP VIEW asset_references;
+DROP TRIGGER on_message_media_asset ON messages;
+
+CREATE OR REPLACE FUNCTION fill_asset_url()
+RETURNS TRIGGER AS $$
+DECLARE
+    asset_id INTEGER := (to_jsonb(NEW) ->> TG_ARGV[0])::INTEGER;
+    asset_url TEXT;
+BEGIN
+    IF asset_id IS NOT NULL THEN
+        SELECT url INTO asset_url FROM assets WHERE id = asset_id;
+        -- An unknown asset is left for the foreign key to refuse.
+        IF FOUND THEN
+            NEW := jsonb_populate_record(NEW, jsonb_build_object(TG_ARGV[1], asset_url));
+        END IF;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP FUNCTION asset_for_url(TEXT);
+DROP INDEX asset_variants_url;
+DROP INDEX assets_url;
+ALTER TABLE messages DROP COLUMN media_asset_id;
@@ -0,0 +1,107 @@
+-- Chat messages may carry an uploaded file too.
+ALTER TABLE messages ADD COLUMN media_asset_id INTEGER REFERENCES assets (id);
+
+CREATE INDEX assets_url ON assets (url);
+CREATE INDEX asset_variants_url ON asset_variants (url);
+
+-- The asset stored at `target`, at any of its sizes.
+CREATE FUNCTION asset_for_url(target TEXT)
+RETURNS INTEGER AS $$
+    SELECT id FROM assets WHERE url = target
+    UNION ALL
+    SELECT asset_id FROM asset_variants WHERE url = target
+    LIMIT 1;
+$$ LANGUAGE sql STABLE;
+
+-- Keeps each asset id column and the URL column next to it in step. An asset
+-- fills in its URL, unless the column already holds one of the asset's sizes;
+-- a URL an asset is stored at fills in the asset, so assets used by URL are
+-- tracked too. Arguments: the asset id column, then the URL column.
+CREATE OR REPLACE FUNCTION fill_asset_url()
+RETURNS TRIGGER AS $$
+DECLARE
+    asset_id INTEGER := (to_jsonb(NEW) ->> TG_ARGV[0])::INTEGER;
+    url TEXT := to_jsonb(NEW) ->> TG_ARGV[1];
+    asset_url TEXT;
+BEGIN
+    IF asset_id IS NOT NULL THEN
+        IF url IS NULL OR asset_for_url(url) IS DISTINCT FROM asset_id THEN
+            SELECT assets.url INTO asset_url FROM assets WHERE id = asset_id;
+            -- An unknown asset is left for the foreign key to refuse.
+            IF FOUND THEN
+                NEW := jsonb_populate_record(NEW, jsonb_build_object(TG_ARGV[1], asset_url));
+            END IF;
+        END IF;
+    ELSIF url IS NOT NULL THEN
+        asset_id := asset_for_url(url);
+        IF asset_id IS NOT NULL THEN
+            NEW := jsonb_populate_record(NEW, jsonb_build_object(TG_ARGV[0], asset_id));
+        END IF;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER on_message_media_asset
+BEFORE INSERT OR UPDATE ON messages
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('media_asset_id', 'media_url');
+
+-- Link what was saved by URL before. Templates keep their revision: nothing
+-- about them changes but the bookkeeping.
+UPDATE worlds SET thumbnail_asset_id = asset_for_url(thumbnail_url)
+WHERE thumbnail_asset_id IS NULL AND asset_for_url(thumbnail_url) IS NOT NULL;
+UPDATE maps SET background_asset_id = asset_for_url(background_url)
+WHERE background_asset_id IS NULL AND asset_for_url(background_url) IS NOT NULL;
+UPDATE spaces SET background_asset_id = asset_for_url(background_url)
+WHERE background_asset_id IS NULL AND asset_for_url(background_url) IS NOT NULL;
+UPDATE spaces SET thumbnail_asset_id = asset_for_url(thumbnail_url)
+WHERE thumbnail_asset_id IS NULL AND asset_for_url(thumbnail_url) IS NOT NULL;
+UPDATE avatars SET image_asset_id = asset_for_url(image_url)
+WHERE image_asset_id IS NULL AND asset_for_url(image_url) IS NOT NULL;
+UPDATE messages SET media_asset_id = asset_for_url(media_url)
+WHERE media_asset_id IS NULL AND asset_for_url(media_url) IS NOT NULL;
+
+ALTER TABLE element_templates DISABLE TRIGGER on_template_update, DISABLE TRIGGER on_template_write;
+UPDATE element_templates SET image_asset_id = asset_for_url(image_url)
+WHERE image_asset_id IS NULL AND asset_for_url(image_url) IS NOT NULL;
+UPDATE element_templates SET model_asset_id = asset_for_url(model_url)
+WHERE model_asset_id IS NULL AND asset_for_url(model_url) IS NOT NULL;
+ALTER TABLE element_templates ENABLE TRIGGER on_template_update, ENABLE TRIGGER on_template_write;
+UPDATE element_template_revisions SET image_asset_id = asset_for_url(image_url)
+WHERE image_asset_id IS NULL AND asset_for_url(image_url) IS NOT NULL;
+UPDATE element_template_revisions SET model_asset_id = asset_for_url(model_url)
+WHERE model_asset_id IS NULL AND asset_for_url(model_url) IS NOT NULL;
+
+-- Everything that uses an asset, one row per use. The foreign keys behind it
+-- stop a used asset from being deleted.
+CREATE VIEW asset_references (asset_id, referrer, referrer_id, field) AS
+SELECT thumbnail_asset_id, 'world', id, 'thumbnail_url' FROM worlds
+WHERE thumbnail_asset_id IS NOT NULL
+UNION ALL
+SELECT background_asset_id, 'map', id, 'background_url' FROM maps
+WHERE background_asset_id IS NOT NULL
+UNION ALL
+SELECT background_asset_id, 'space', id, 'background_url' FROM spaces
+WHERE background_asset_id IS NOT NULL
+UNION ALL
+SELECT thumbnail_asset_id, 'space', id, 'thumbnail_url' FROM spaces
+WHERE thumbnail_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'avatar', id, 'image_url' FROM avatars
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'element_template', id, 'image_url' FROM element_templates
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT model_asset_id, 'element_template', id, 'model_url' FROM element_templates
+WHERE model_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'template_revision', id, 'image_url' FROM element_template_revisions
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT model_asset_id, 'template_revision', id, 'model_url' FROM element_template_revisions
+WHERE model_asset_id IS NOT NULL
+UNION ALL
+SELECT media_asset_id, 'message', id, 'media_url' FROM messages
+WHERE media_asset_id IS NOT NULL;
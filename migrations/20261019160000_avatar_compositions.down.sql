@@ -0,0 +1,33 @@
+CREATE OR REPLACE VIEW asset_references (asset_id, referrer, referrer_id, field) AS
+SELECT thumbnail_asset_id, 'world', id, 'thumbnail_url' FROM worlds
+WHERE thumbnail_asset_id IS NOT NULL
+UNION ALL
+SELECT background_asset_id, 'map', id, 'background_url' FROM maps
+WHERE background_asset_id IS NOT NULL
+UNION ALL
+SELECT background_asset_id, 'space', id, 'background_url' FROM spaces
+WHERE background_asset_id IS NOT NULL
+UNION ALL
+SELECT thumbnail_asset_id, 'space', id, 'thumbnail_url' FROM spaces
+WHERE thumbnail_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'avatar', id, 'image_url' FROM avatars
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'element_template', id, 'image_url' FROM element_templates
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT model_asset_id, 'element_template', id, 'model_url' FROM element_templates
+WHERE model_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'template_revision', id, 'image_url' FROM element_template_revisions
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT model_asset_id, 'template_revision', id, 'model_url' FROM element_template_revisions
+WHERE model_asset_id IS NOT NULL
+UNION ALL
+SELECT media_asset_id, 'message', id, 'media_url' FROM messages
+WHERE media_asset_id IS NOT NULL;
+
+DROP TABLE user_avatar_parts;
+DROP TABLE avatar_parts;
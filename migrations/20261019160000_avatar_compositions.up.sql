@@ -0,0 +1,59 @@
+-- Parts avatars are put together from. Clients draw them by slot, back to
+-- front: body, outfit, hair, then accessories.
+CREATE TABLE avatar_parts (
+    id SERIAL PRIMARY KEY,
+    slot VARCHAR(20) NOT NULL CHECK (slot IN ('body', 'outfit', 'hair', 'accessory')),
+    name VARCHAR(100) NOT NULL,
+    image_url VARCHAR(255) NOT NULL,
+    image_asset_id INTEGER REFERENCES assets (id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER on_avatar_part_image_asset
+BEFORE INSERT OR UPDATE ON avatar_parts
+FOR EACH ROW
+EXECUTE FUNCTION fill_asset_url('image_asset_id', 'image_url');
+
+-- What each user wears. Parts in use can't be deleted. `position` orders the
+-- accessories.
+CREATE TABLE user_avatar_parts (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    part_id INTEGER NOT NULL REFERENCES avatar_parts (id),
+    position SMALLINT NOT NULL,
+    tint CHAR(7) CHECK (tint ~ '^#[0-9a-f]{6}$'),
+    PRIMARY KEY (user_id, part_id)
+);
+
+CREATE OR REPLACE VIEW asset_references (asset_id, referrer, referrer_id, field) AS
+SELECT thumbnail_asset_id, 'world', id, 'thumbnail_url' FROM worlds
+WHERE thumbnail_asset_id IS NOT NULL
+UNION ALL
+SELECT background_asset_id, 'map', id, 'background_url' FROM maps
+WHERE background_asset_id IS NOT NULL
+UNION ALL
+SELECT background_asset_id, 'space', id, 'background_url' FROM spaces
+WHERE background_asset_id IS NOT NULL
+UNION ALL
+SELECT thumbnail_asset_id, 'space', id, 'thumbnail_url' FROM spaces
+WHERE thumbnail_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'avatar', id, 'image_url' FROM avatars
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'avatar_part', id, 'image_url' FROM avatar_parts
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'element_template', id, 'image_url' FROM element_templates
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT model_asset_id, 'element_template', id, 'model_url' FROM element_templates
+WHERE model_asset_id IS NOT NULL
+UNION ALL
+SELECT image_asset_id, 'template_revision', id, 'image_url' FROM element_template_revisions
+WHERE image_asset_id IS NOT NULL
+UNION ALL
+SELECT model_asset_id, 'template_revision', id, 'model_url' FROM element_template_revisions
+WHERE model_asset_id IS NOT NULL
+UNION ALL
+SELECT media_asset_id, 'message', id, 'media_url' FROM messages
+WHERE media_asset_id IS NOT NULL;
@@ -1,3 +0,0 @@
-pub mod element_templates;
-pub mod map_elements;
-pub mod space_elements;
@@ -1,2 +0,0 @@
-pub mod create_maps;
-pub mod get_map;
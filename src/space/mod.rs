@@ -1,3 +0,0 @@
-pub mod create_space;
-pub mod delete_space;
-pub mod get_space;
@@ -1,2 +0,0 @@
-pub mod create_world;
-pub mod get_worlds;
@@ -6,7 +6,7 @@ until pg_isready -U anjal -h db; do
     sleep 2
 done
 
-echo "Running migrations using sqlx..."
-sqlx migrate run
+echo "Running migrations..."
+metaverse_v1 migrate up
 
 echo "Database setup complete."